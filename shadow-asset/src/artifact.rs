@@ -4,21 +4,195 @@ use crate::{
 };
 use std::collections::HashSet;
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Text(String),
+}
+
+impl StatValue {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            StatValue::Int(value) => Some(*value as f64),
+            StatValue::Float(value) => Some(*value),
+            StatValue::Bool(value) => Some(*value as u8 as f64),
+            StatValue::Text(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for StatValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatValue::Int(value) => write!(f, "{}", value),
+            StatValue::Float(value) => write!(f, "{}", value),
+            StatValue::Bool(value) => write!(f, "{}", value),
+            StatValue::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<i64> for StatValue {
+    fn from(value: i64) -> Self {
+        StatValue::Int(value)
+    }
+}
+
+impl From<u32> for StatValue {
+    fn from(value: u32) -> Self {
+        StatValue::Int(value as i64)
+    }
+}
+
+impl From<usize> for StatValue {
+    fn from(value: usize) -> Self {
+        StatValue::Int(value as i64)
+    }
+}
+
+impl From<f32> for StatValue {
+    fn from(value: f32) -> Self {
+        StatValue::Float(value as f64)
+    }
+}
+
+impl From<f64> for StatValue {
+    fn from(value: f64) -> Self {
+        StatValue::Float(value)
+    }
+}
+
+impl From<bool> for StatValue {
+    fn from(value: bool) -> Self {
+        StatValue::Bool(value)
+    }
+}
+
+impl From<&str> for StatValue {
+    fn from(value: &str) -> Self {
+        StatValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for StatValue {
+    fn from(value: String) -> Self {
+        StatValue::Text(value)
+    }
+}
+
+impl IntoBytes for StatValue {
+    fn into_bytes(&self) -> Vec<u8> {
+        let (tag, value) = match self {
+            StatValue::Int(value) => (0u8, value.into_bytes()),
+            StatValue::Float(value) => (1, value.into_bytes()),
+            StatValue::Bool(value) => (2, value.into_bytes()),
+            StatValue::Text(value) => (3, value.into_bytes()),
+        };
+
+        let mut bytes = vec![tag];
+        bytes.extend(value);
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (tag, value) = bytes.split_first()?;
+        match tag {
+            0 => i64::from_bytes(value).map(StatValue::Int),
+            1 => f64::from_bytes(value).map(StatValue::Float),
+            2 => bool::from_bytes(value).map(StatValue::Bool),
+            3 => String::from_bytes(value).map(StatValue::Text),
+            _ => None,
+        }
+    }
+}
+
+/// Statistics recorded at import, e.g. triangle counts or texture dimensions.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AssetStats {
+    keys: Vec<String>,
+    values: Vec<StatValue>,
+}
+
+impl AssetStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&StatValue> {
+        let index = self.keys.iter().position(|k| k == key)?;
+        Some(&self.values[index])
+    }
+
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<StatValue>) {
+        let key = key.into();
+        match self.keys.iter().position(|k| *k == key) {
+            Some(index) => self.values[index] = value.into(),
+            None => {
+                self.keys.push(key);
+                self.values.push(value.into());
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &StatValue)> {
+        self.keys.iter().map(String::as_str).zip(self.values.iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl IntoBytes for AssetStats {
+    fn into_bytes(&self) -> Vec<u8> {
+        let keys = self.keys.into_bytes();
+        let mut bytes = keys.len().into_bytes();
+        bytes.extend(keys);
+        bytes.extend(self.values.into_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let keys_end = usize::from_bytes(bytes.get(..8)?)?.checked_add(8)?;
+        let keys = Vec::<String>::from_bytes(bytes.get(8..keys_end)?)?;
+        let values = Vec::<StatValue>::from_bytes(bytes.get(keys_end..)?)?;
+        match keys.len() == values.len() {
+            true => Some(Self { keys, values }),
+            false => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ArtifactMeta {
     pub id: AssetId,
     pub ty: AssetType,
     pub checksum: u32,
+    pub version: u32,
     pub dependencies: HashSet<AssetId>,
+    pub stats: AssetStats,
 }
 
 impl ArtifactMeta {
+    /// Leads the serialized meta. Artifacts without it, or with another `FORMAT`,
+    /// fail to parse and are reimported.
+    pub const MAGIC: [u8; 4] = *b"SART";
+    pub const FORMAT: u32 = 1;
+
     pub fn new<A: Asset>(id: AssetId, checksum: u32, dependencies: HashSet<AssetId>) -> Self {
         Self {
             id,
             ty: AssetType::of::<A>(),
             checksum,
+            version: 0,
             dependencies,
+            stats: AssetStats::new(),
         }
     }
 
@@ -32,10 +206,22 @@ impl ArtifactMeta {
             id,
             ty,
             checksum,
+            version: 0,
             dependencies,
+            stats: AssetStats::new(),
         }
     }
 
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn with_stats(mut self, stats: AssetStats) -> Self {
+        self.stats = stats;
+        self
+    }
+
     pub fn id(&self) -> AssetId {
         self.id
     }
@@ -48,33 +234,52 @@ impl ArtifactMeta {
         self.checksum
     }
 
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
     pub fn dependencies(&self) -> &HashSet<AssetId> {
         &self.dependencies
     }
+
+    pub fn stats(&self) -> &AssetStats {
+        &self.stats
+    }
 }
 
 impl IntoBytes for ArtifactMeta {
     fn into_bytes(&self) -> Vec<u8> {
-        let mut bytes = Vec::new();
+        let mut bytes = Self::MAGIC.to_vec();
+        bytes.extend_from_slice(&Self::FORMAT.into_bytes());
         bytes.extend_from_slice(&self.id.into_bytes());
         bytes.extend_from_slice(&self.ty.into_bytes());
         bytes.extend_from_slice(&self.checksum.into_bytes());
+        bytes.extend_from_slice(&self.version.into_bytes());
 
         let deps = self.dependencies.into_bytes();
         bytes.extend_from_slice(&deps.len().into_bytes());
         bytes.extend_from_slice(&deps);
+        bytes.extend(self.stats.into_bytes());
 
         bytes
     }
 
     fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        let id = AssetId::from_bytes(&bytes[..8])?;
-        let ty = AssetType::from_bytes(&bytes[8..12])?;
-        let checksum = u32::from_bytes(&bytes[12..16])?;
-        let dependencies_len = usize::from_bytes(&bytes[16..24])?;
-        let dependencies = HashSet::from_bytes(&bytes[24..24 + dependencies_len])?;
+        if bytes.get(..4)? != Self::MAGIC || u32::from_bytes(bytes.get(4..8)?)? != Self::FORMAT {
+            return None;
+        }
 
-        Some(Self::with_type(id, ty, checksum, dependencies))
+        let bytes = &bytes[8..];
+        let id = AssetId::from_bytes(bytes.get(..8)?)?;
+        let ty = AssetType::from_bytes(bytes.get(8..12)?)?;
+        let checksum = u32::from_bytes(bytes.get(12..16)?)?;
+        let version = u32::from_bytes(bytes.get(16..20)?)?;
+        let dependencies_end = usize::from_bytes(bytes.get(20..28)?)?.checked_add(28)?;
+        let dependencies = HashSet::from_bytes(bytes.get(28..dependencies_end)?)?;
+        let stats = AssetStats::from_bytes(bytes.get(dependencies_end..)?)?;
+
+        let meta = Self::with_type(id, ty, checksum, dependencies).with_version(version);
+        Some(meta.with_stats(stats))
     }
 }
 
@@ -109,8 +314,8 @@ impl IntoBytes for ArtifactHeader {
     }
 
     fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        let meta = usize::from_bytes(&bytes[..8])?;
-        let asset = usize::from_bytes(&bytes[8..16])?;
+        let meta = usize::from_bytes(bytes.get(..8)?)?;
+        let asset = usize::from_bytes(bytes.get(8..16)?)?;
 
         Some(Self::new(meta, asset))
     }
@@ -174,9 +379,13 @@ impl IntoBytes for Artifact {
 
     fn from_bytes(bytes: &[u8]) -> Option<Self> {
         const HEADER_SIZE: usize = std::mem::size_of::<ArtifactHeader>();
-        let header = ArtifactHeader::from_bytes(&bytes[..HEADER_SIZE])?;
-        let meta = ArtifactMeta::from_bytes(&bytes[HEADER_SIZE..HEADER_SIZE + header.meta()])?;
+        let header = ArtifactHeader::from_bytes(bytes.get(..HEADER_SIZE)?)?;
+        let meta_end = header.meta().checked_add(HEADER_SIZE)?;
+        let meta = ArtifactMeta::from_bytes(bytes.get(HEADER_SIZE..meta_end)?)?;
         let data = bytes[HEADER_SIZE..].to_vec();
+        if data.len() < header.meta().checked_add(header.asset())? {
+            return None;
+        }
 
         Some(Self { header, meta, data })
     }
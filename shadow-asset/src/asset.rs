@@ -3,8 +3,13 @@ use serde::ser::SerializeStruct;
 use shadow_ecs::core::{DenseMap, Resource};
 use std::{
     any::TypeId,
+    collections::{BTreeSet, HashMap, HashSet},
     hash::{Hash, Hasher},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
 };
 
 #[derive(
@@ -12,14 +17,42 @@ use std::{
 )]
 pub struct AssetId(u64);
 
+thread_local! {
+    static ID_GENERATOR: std::cell::RefCell<Option<IdGenerator>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+pub struct IdGenerator {
+    next: u64,
+}
+
+impl IdGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self { next: seed }
+    }
+
+    pub fn next_id(&mut self) -> u64 {
+        let id = self.next;
+        self.next = self.next.wrapping_add(1);
+        id
+    }
+}
+
 impl AssetId {
     pub fn gen() -> Self {
-        let id = ulid::Ulid::new();
+        let seeded = ID_GENERATOR.with(|gen| gen.borrow_mut().as_mut().map(|gen| gen.next_id()));
         let mut hasher = crc32fast::Hasher::new();
-        id.hash(&mut hasher);
+        match seeded {
+            Some(id) => id.hash(&mut hasher),
+            None => ulid::Ulid::new().hash(&mut hasher),
+        }
         AssetId(hasher.finish())
     }
 
+    pub fn set_generator(generator: Option<IdGenerator>) {
+        ID_GENERATOR.with(|gen| *gen.borrow_mut() = generator);
+    }
+
     pub fn raw(id: u64) -> Self {
         Self(id)
     }
@@ -266,15 +299,126 @@ impl<'de, S: Settings> serde::Deserialize<'de> for AssetSettings<S> {
     }
 }
 
+/// How long loaded assets are kept in `Assets` before they are unloaded. Reading a
+/// discarded asset through `Assets::get` loads it again from its artifact.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionPolicy {
+    #[default]
+    KeepAlways,
+    /// Discard assets once a consumer marks them with `Assets::mark_extracted`.
+    DiscardAfterExtract,
+    /// Discard assets once the references counted by `Assets::retain` and
+    /// `Assets::release` drop to zero.
+    DiscardWhenUnreferenced,
+    /// Discard assets that haven't been added or read for this many frames.
+    DiscardAfterFrames(u64),
+}
+
+/// Retention bookkeeping for one `Assets`. Reads go through `&Assets`, so the
+/// state they update uses atomics and locks.
+#[derive(Debug, Default)]
+struct Retention {
+    policy: RetentionPolicy,
+    frame: u64,
+    /// The frame each tracked asset was last added or read.
+    used: HashMap<AssetId, AtomicU64>,
+    /// Tracked assets ordered by the frame they were queued at. An asset read since
+    /// is queued again when it reaches the front, rather than moved on every read.
+    queue: BTreeSet<(u64, AssetId)>,
+    references: HashMap<AssetId, usize>,
+    /// Assets marked extracted or left unreferenced, waiting to be discarded.
+    pending: Mutex<Vec<AssetId>>,
+    discarded: HashSet<AssetId>,
+    reloads: Mutex<HashSet<AssetId>>,
+}
+
+impl Retention {
+    fn read(&self, id: &AssetId) {
+        if let RetentionPolicy::DiscardAfterFrames(_) = self.policy {
+            if let Some(used) = self.used.get(id) {
+                used.store(self.frame, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn miss(&self, id: &AssetId) {
+        if self.discarded.contains(id) {
+            self.reloads.lock().unwrap().insert(*id);
+        }
+    }
+
+    fn track(&mut self, id: AssetId) {
+        self.used.insert(id, AtomicU64::new(self.frame));
+        if let RetentionPolicy::DiscardAfterFrames(_) = self.policy {
+            self.queue.insert((self.frame, id));
+        }
+        self.discarded.remove(&id);
+        if self.policy == RetentionPolicy::DiscardWhenUnreferenced && !self.is_referenced(&id) {
+            self.pending.get_mut().unwrap().push(id);
+        }
+    }
+
+    fn is_referenced(&self, id: &AssetId) -> bool {
+        self.references.contains_key(id)
+    }
+
+    fn reset(&mut self, ids: &[AssetId]) {
+        self.used.clear();
+        self.queue.clear();
+        self.pending.get_mut().unwrap().clear();
+        for id in ids {
+            self.track(*id);
+        }
+    }
+
+    fn pop_expired(&mut self) -> Option<AssetId> {
+        match self.policy {
+            RetentionPolicy::KeepAlways => None,
+            RetentionPolicy::DiscardAfterExtract | RetentionPolicy::DiscardWhenUnreferenced => {
+                loop {
+                    let id = self.pending.get_mut().unwrap().pop()?;
+                    let unreferenced = self.policy == RetentionPolicy::DiscardAfterExtract
+                        || !self.is_referenced(&id);
+                    if self.used.contains_key(&id) && unreferenced {
+                        return Some(id);
+                    }
+                }
+            }
+            RetentionPolicy::DiscardAfterFrames(frames) => loop {
+                let (queued, id) = *self.queue.first()?;
+                if self.frame - queued < frames {
+                    return None;
+                }
+
+                self.queue.pop_first();
+                let Some(used) = self.used.get(&id) else {
+                    continue;
+                };
+
+                match used.load(Ordering::Relaxed) {
+                    used if used > queued => {
+                        self.queue.insert((used, id));
+                    }
+                    _ => return Some(id),
+                }
+            },
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Assets<A: Asset> {
     assets: DenseMap<AssetId, A>,
+    fallback: Option<A>,
+    retention: Retention,
 }
 
 impl<A: Asset> Assets<A> {
     pub fn new() -> Self {
         Self {
             assets: DenseMap::new(),
+            fallback: None,
+            retention: Retention::default(),
         }
     }
 
@@ -282,22 +426,114 @@ impl<A: Asset> Assets<A> {
         self.assets.contains(id)
     }
 
+    /// The asset, counting as a use for `RetentionPolicy::DiscardAfterFrames`. Asking
+    /// for a discarded asset queues it to be loaded again.
     pub fn get(&self, id: &AssetId) -> Option<&A> {
-        self.assets.get(id)
+        let asset = self.assets.get(id);
+        match asset {
+            Some(_) => self.retention.read(id),
+            None => self.retention.miss(id),
+        }
+
+        asset
     }
 
     pub fn get_mut(&mut self, id: &AssetId) -> Option<&mut A> {
+        match self.assets.contains(id) {
+            true => self.retention.read(id),
+            false => self.retention.miss(id),
+        }
+
         self.assets.get_mut(id)
     }
 
+    /// The asset, or the fallback while it is still loading or finalizing.
+    pub fn get_or_fallback(&self, id: &AssetId) -> Option<&A> {
+        self.get(id).or(self.fallback.as_ref())
+    }
+
+    pub fn fallback(&self) -> Option<&A> {
+        self.fallback.as_ref()
+    }
+
+    pub fn set_fallback(&mut self, fallback: Option<A>) {
+        self.fallback = fallback;
+    }
+
+    pub fn retention(&self) -> RetentionPolicy {
+        self.retention.policy
+    }
+
+    /// Sets the policy, counting every loaded asset as used this frame.
+    pub fn set_retention(&mut self, retention: RetentionPolicy) {
+        self.retention.policy = retention;
+        self.retention.reset(self.assets.keys());
+    }
+
     pub fn add(&mut self, id: AssetId, asset: A) -> Option<A> {
+        self.retention.track(id);
         self.assets.insert(id, asset)
     }
 
     pub fn remove(&mut self, id: &AssetId) -> Option<A> {
+        self.retention.used.remove(id);
         self.assets.remove(id)
     }
 
+    /// Marks the asset as handed off to its consumer, discarding it under
+    /// `RetentionPolicy::DiscardAfterExtract`.
+    pub fn mark_extracted(&self, id: &AssetId) {
+        if self.retention.policy == RetentionPolicy::DiscardAfterExtract {
+            self.retention.pending.lock().unwrap().push(*id);
+        }
+    }
+
+    /// Counts a reference to the asset, keeping it under `RetentionPolicy::DiscardWhenUnreferenced`.
+    pub fn retain(&mut self, id: &AssetId) {
+        *self.retention.references.entry(*id).or_default() += 1;
+    }
+
+    /// Drops a reference counted by `retain`.
+    pub fn release(&mut self, id: &AssetId) {
+        let Some(count) = self.retention.references.get_mut(id) else {
+            return;
+        };
+
+        *count -= 1;
+        if *count == 0 {
+            self.retention.references.remove(id);
+            let unreferenced = self.retention.policy == RetentionPolicy::DiscardWhenUnreferenced;
+            if unreferenced && self.assets.contains(id) {
+                self.retention.pending.get_mut().unwrap().push(*id);
+            }
+        }
+    }
+
+    pub fn references(&self, id: &AssetId) -> usize {
+        self.retention.references.get(id).copied().unwrap_or(0)
+    }
+
+    /// The next asset the retention policy allows to be discarded. Assets leave the
+    /// queue as they are returned, so each call does a bounded amount of work.
+    pub(crate) fn pop_expired(&mut self) -> Option<AssetId> {
+        self.retention.pop_expired()
+    }
+
+    /// Stops tracking an asset being unloaded, and loads it again if it is asked for.
+    pub(crate) fn discard(&mut self, id: &AssetId) {
+        self.retention.used.remove(id);
+        self.retention.discarded.insert(*id);
+    }
+
+    /// Discarded assets asked for since the last call.
+    pub(crate) fn take_reloads(&mut self) -> HashSet<AssetId> {
+        std::mem::take(self.retention.reloads.get_mut().unwrap())
+    }
+
+    pub(crate) fn advance_frame(&mut self) {
+        self.retention.frame += 1;
+    }
+
     pub fn len(&self) -> usize {
         self.assets.len()
     }
@@ -327,6 +563,7 @@ impl<A: Asset> Assets<A> {
     }
 
     pub fn clear(&mut self) {
+        self.retention.reset(&[]);
         self.assets.clear();
     }
 }
@@ -338,3 +575,172 @@ impl<A: Asset> Default for Assets<A> {
         Self::new()
     }
 }
+
+pub struct AssetCollections<A: Asset> {
+    collections: DenseMap<String, Assets<A>>,
+}
+
+impl<A: Asset> AssetCollections<A> {
+    pub fn new() -> Self {
+        Self {
+            collections: DenseMap::new(),
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.collections.contains(name)
+    }
+
+    pub fn collection(&self, name: &str) -> Option<&Assets<A>> {
+        self.collections.get(name)
+    }
+
+    pub fn collection_mut(&mut self, name: &str) -> Option<&mut Assets<A>> {
+        self.collections.get_mut(name)
+    }
+
+    pub fn get_or_insert(&mut self, name: &str) -> &mut Assets<A> {
+        if !self.collections.contains(name) {
+            self.collections.insert(name.to_string(), Assets::new());
+        }
+
+        self.collections.get_mut(name).unwrap()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Assets<A>> {
+        self.collections.remove(name)
+    }
+
+    pub fn names(&self) -> &[String] {
+        self.collections.keys()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut Assets<A>)> {
+        self.collections.iter_mut()
+    }
+}
+
+impl<A: Asset + Clone> AssetCollections<A> {
+    pub fn snapshot(&mut self, from: &str, to: &str) {
+        let mut snapshot = Assets::new();
+        if let Some(source) = self.collection(from) {
+            for (id, asset) in source.iter() {
+                snapshot.add(*id, asset.clone());
+            }
+        }
+
+        self.collections.insert(to.to_string(), snapshot);
+    }
+}
+
+impl<A: Asset> Resource for AssetCollections<A> {}
+
+impl<A: Asset> Default for AssetCollections<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Asset, AssetId, Assets, IdGenerator, RetentionPolicy};
+
+    struct Text;
+    impl Asset for Text {}
+
+    #[test]
+    fn seeded_ids_repeat() {
+        let generate = |seed| {
+            AssetId::set_generator(Some(IdGenerator::new(seed)));
+            let ids = (0..4).map(|_| AssetId::gen()).collect::<Vec<_>>();
+            AssetId::set_generator(None);
+            ids
+        };
+
+        let ids = generate(7);
+        assert_eq!(ids, generate(7));
+        assert_ne!(ids, generate(8));
+        assert!(ids.iter().skip(1).all(|id| *id != ids[0]));
+    }
+
+    #[test]
+    fn read_assets_expire_later() {
+        let (first, second) = (AssetId::gen(), AssetId::gen());
+        let mut assets = Assets::new();
+        assets.set_retention(RetentionPolicy::DiscardAfterFrames(2));
+        assets.add(first, Text);
+        assets.add(second, Text);
+
+        assets.advance_frame();
+        assert!(assets.get(&first).is_some());
+        assets.advance_frame();
+        assert_eq!(assets.pop_expired(), Some(second));
+        assert_eq!(assets.pop_expired(), None);
+
+        assets.advance_frame();
+        assert_eq!(assets.pop_expired(), Some(first));
+        assert_eq!(assets.pop_expired(), None);
+    }
+
+    #[test]
+    fn discarded_assets_do_not_expire() {
+        let id = AssetId::gen();
+        let mut assets = Assets::new();
+        assets.set_retention(RetentionPolicy::DiscardAfterFrames(1));
+        assets.add(id, Text);
+        assets.discard(&id);
+        assets.advance_frame();
+        assert_eq!(assets.pop_expired(), None);
+        assert!(assets.contains(&id));
+    }
+
+    #[test]
+    fn extracted_assets_expire() {
+        let id = AssetId::gen();
+        let mut assets = Assets::new();
+        assets.set_retention(RetentionPolicy::DiscardAfterExtract);
+        assets.add(id, Text);
+        assert_eq!(assets.pop_expired(), None);
+
+        assets.mark_extracted(&id);
+        assert_eq!(assets.pop_expired(), Some(id));
+    }
+
+    #[test]
+    fn unreferenced_assets_expire() {
+        let (kept, unreferenced) = (AssetId::gen(), AssetId::gen());
+        let mut assets = Assets::new();
+        assets.set_retention(RetentionPolicy::DiscardWhenUnreferenced);
+        assets.retain(&kept);
+        assets.retain(&kept);
+        assets.add(kept, Text);
+        assets.add(unreferenced, Text);
+        assert_eq!(assets.pop_expired(), Some(unreferenced));
+        assert_eq!(assets.pop_expired(), None);
+
+        assets.release(&kept);
+        assert_eq!(assets.pop_expired(), None);
+        assets.release(&kept);
+        assert_eq!(assets.references(&kept), 0);
+        assert_eq!(assets.pop_expired(), Some(kept));
+    }
+
+    #[test]
+    fn reading_discarded_assets_requests_reload() {
+        let id = AssetId::gen();
+        let mut assets = Assets::new();
+        assets.add(id, Text);
+        assets.discard(&id);
+        assets.remove(&id);
+
+        assert!(assets.get(&id).is_none());
+        assert!(assets.get(&id).is_none());
+        assert_eq!(assets.take_reloads().into_iter().collect::<Vec<_>>(), [id]);
+        assert!(assets.take_reloads().is_empty());
+
+        assets.add(id, Text);
+        assets.remove(&id);
+        assets.get(&id);
+        assert!(assets.take_reloads().is_empty());
+    }
+}
@@ -1,23 +1,28 @@
 use super::{
-    load::{LoadAssets, UnloadAsset},
+    load::{LoadAsset, LoadAssets, UnloadAsset},
     AssetEvent, StartAssetEvent,
 };
 use crate::{
     asset::{Asset, AssetId, AssetKind, AssetSettings, Settings},
-    database::{library::DependentLibrary, AssetDatabase},
-    io::PathExt,
-    loader::{AssetError, AssetErrorKind, LoadErrorKind, LoadedAssets},
+    database::{
+        library::{DependentLibrary, QuarantineLibrary},
+        AssetDatabase,
+    },
+    io::{path::SourcePath, AssetIoError, PathExt},
+    loader::{AssetError, AssetErrorKind, LoadErrorKind, LoadPriority, LoadedAssets},
+    validation::Violation,
 };
 use shadow_ecs::{
     core::DenseSet,
     world::{
-        event::{Event, Events},
+        event::{ErasedEvent, Event, Events},
         World,
     },
 };
 use std::{
     collections::HashSet,
     error::Error,
+    panic::{catch_unwind, AssertUnwindSafe},
     path::{Path, PathBuf},
 };
 
@@ -26,7 +31,7 @@ impl Asset for Folder {}
 
 #[derive(Default, serde::Serialize, serde::Deserialize)]
 pub struct FolderMeta {
-    children: HashSet<PathBuf>,
+    children: HashSet<SourcePath>,
 }
 
 impl FolderMeta {
@@ -36,21 +41,32 @@ impl FolderMeta {
         }
     }
 
-    pub fn children(&self) -> &HashSet<PathBuf> {
+    pub fn children(&self) -> &HashSet<SourcePath> {
         &self.children
     }
 
-    pub fn set_children(&mut self, children: impl IntoIterator<Item = PathBuf>) {
+    pub fn set_children(&mut self, children: impl IntoIterator<Item = SourcePath>) {
         self.children = children.into_iter().collect();
     }
 }
 
 impl Settings for FolderMeta {}
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportReason {
+    New,
+    Moved,
+    MissingArtifact,
+    InvalidArtifact,
+    LoaderVersion,
+    SourceChanged,
+}
+
 pub enum ImportScan {
     Added(PathBuf),
     Removed(PathBuf),
-    Modified(PathBuf),
+    Modified(PathBuf, ImportReason),
+    Unchanged(PathBuf),
     Error(AssetError),
 }
 
@@ -63,8 +79,12 @@ impl ImportScan {
         Self::Removed(path.as_ref().to_path_buf())
     }
 
-    pub fn modified(path: impl AsRef<Path>) -> Self {
-        Self::Modified(path.as_ref().to_path_buf())
+    pub fn modified(path: impl AsRef<Path>, reason: ImportReason) -> Self {
+        Self::Modified(path.as_ref().to_path_buf(), reason)
+    }
+
+    pub fn unchanged(path: impl AsRef<Path>) -> Self {
+        Self::Unchanged(path.as_ref().to_path_buf())
     }
 
     pub fn error(path: impl AsRef<Path>, error: impl Error + Send + Sync + 'static) -> Self {
@@ -75,7 +95,8 @@ impl ImportScan {
         match self {
             ImportScan::Added(path) => Some(path),
             ImportScan::Removed(path) => Some(path),
-            ImportScan::Modified(path) => Some(path),
+            ImportScan::Modified(path, _) => Some(path),
+            ImportScan::Unchanged(path) => Some(path),
             ImportScan::Error(error) => match error.kind() {
                 AssetErrorKind::Load(_) => None,
                 AssetErrorKind::Import(path) => Some(path),
@@ -84,16 +105,88 @@ impl ImportScan {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanAction {
+    Unchanged,
+    Import(ImportReason),
+    Fail(String),
+    Remove,
+}
+
+/// What an `ImportFolder` would do, produced by a dry run without touching the library or cache.
+#[derive(Debug, Default, Clone)]
+pub struct ImportPlan {
+    entries: Vec<(PathBuf, PlanAction)>,
+}
+
+impl ImportPlan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> &[(PathBuf, PlanAction)] {
+        &self.entries
+    }
+
+    pub fn action(&self, path: impl AsRef<Path>) -> Option<&PlanAction> {
+        self.entries
+            .iter()
+            .find(|(entry, _)| entry == path.as_ref())
+            .map(|(_, action)| action)
+    }
+
+    pub fn imports(&self) -> impl Iterator<Item = &PathBuf> {
+        self.entries
+            .iter()
+            .filter_map(|(path, action)| match action {
+                PlanAction::Import(_) => Some(path),
+                _ => None,
+            })
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|(_, action)| !matches!(action, PlanAction::Unchanged))
+    }
+
+    fn push(&mut self, path: PathBuf, action: PlanAction) {
+        self.entries.push((path, action));
+    }
+}
+
+impl Event for ImportPlan {
+    type Output = Self;
+
+    fn invoke(self, _: &mut World) -> Option<Self::Output> {
+        Some(self)
+    }
+}
+
 pub struct ImportFolder {
     path: PathBuf,
+    dry_run: bool,
 }
 
 impl ImportFolder {
     pub fn new(path: impl AsRef<Path>) -> Self {
         Self {
             path: path.as_ref().to_path_buf(),
+            dry_run: false,
+        }
+    }
+
+    /// Scans the folder and emits an `ImportPlan` instead of importing anything.
+    pub fn dry_run(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            dry_run: true,
         }
     }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
 }
 
 impl Event for ImportFolder {
@@ -125,19 +218,28 @@ impl ImportFolder {
             _ => return Some(ImportScan::added(path)),
         };
 
+        let root = config.root().join(config.assets());
+        let source = SourcePath::new(path.without_prefix(&root)).ok();
         if library
             .path(&metadata.id)
-            .map(|source| source != path)
+            .map(|known| Some(known) != source.as_ref())
             .unwrap_or_default()
         {
-            return Some(ImportScan::added(path));
+            return Some(ImportScan::modified(path, ImportReason::Moved));
         }
 
         let artifact_meta = match config.load_artifact_meta(metadata.id) {
             Ok(artifact_meta) => artifact_meta,
-            Err(_) => return Some(ImportScan::added(path)),
+            Err(AssetIoError::NotFound(_)) => {
+                return Some(ImportScan::modified(path, ImportReason::MissingArtifact))
+            }
+            Err(_) => return Some(ImportScan::modified(path, ImportReason::InvalidArtifact)),
         };
 
+        if artifact_meta.version() != loader.version() {
+            return Some(ImportScan::modified(path, ImportReason::LoaderVersion));
+        }
+
         let asset = {
             let mut reader = config.reader(path);
             match reader.read_to_end().and_then(|_| reader.flush()) {
@@ -147,12 +249,12 @@ impl ImportFolder {
         };
 
         match config.checksum(&asset, metadata.data()) != artifact_meta.checksum() {
-            true => Some(ImportScan::modified(path)),
-            false => None,
+            true => Some(ImportScan::modified(path, ImportReason::SourceChanged)),
+            false => Some(ImportScan::unchanged(path)),
         }
     }
 
-    fn scan_folder(path: &Path, database: &AssetDatabase) -> Vec<ImportScan> {
+    fn scan_folder(path: &Path, database: &AssetDatabase, dry_run: bool) -> Vec<ImportScan> {
         let config = database.config();
         let children = match config.reader(path).read_dir() {
             Ok(children) => children,
@@ -167,18 +269,28 @@ impl ImportFolder {
         let mut scans = Vec::new();
         for child in &children {
             match config.filesystem().is_dir(&child) {
-                true => scans.extend(Self::scan_folder(child, database)),
+                true => scans.extend(Self::scan_folder(child, database, dry_run)),
                 false => scans.extend(Self::scan_file(child, database)),
             }
         }
 
+        let root = config.root().join(config.assets());
+        let sources = children
+            .iter()
+            .filter_map(|child| SourcePath::new(child.without_prefix(&root)).ok())
+            .collect::<HashSet<_>>();
+
         for child in metadata.children() {
-            if !children.contains(child) {
-                scans.push(ImportScan::removed(child));
+            if !sources.contains(child) {
+                scans.push(ImportScan::removed(root.join(child)));
             }
         }
 
-        metadata.set_children(children);
+        if dry_run {
+            return scans;
+        }
+
+        metadata.set_children(sources);
 
         if let Err(e) = config.save_metadata(path, &metadata) {
             scans.push(ImportScan::error(path, e));
@@ -186,13 +298,67 @@ impl ImportFolder {
 
         scans
     }
+
+    /// Imports of quarantined sources are planned as failures, matching `ImportAssets`,
+    /// which skips them until the source changes.
+    fn plan(scans: Vec<ImportScan>, database: &AssetDatabase) -> ImportPlan {
+        let config = database.config();
+        let root = config.root().join(config.assets());
+        let quarantine = QuarantineLibrary::load(config).unwrap_or_default();
+        let threshold = config.quarantine_threshold();
+        let quarantined = |path: &PathBuf| {
+            quarantine.contains(path)
+                && QuarantineLibrary::checksum(config, path)
+                    .is_some_and(|checksum| quarantine.is_quarantined(path, checksum, threshold))
+        };
+
+        let mut plan = ImportPlan::new();
+        for scan in scans {
+            let (path, action) = match scan {
+                ImportScan::Added(path) => (path, PlanAction::Import(ImportReason::New)),
+                ImportScan::Modified(path, reason) => (path, PlanAction::Import(reason)),
+                ImportScan::Unchanged(path) => (path, PlanAction::Unchanged),
+                ImportScan::Removed(path) => (path, PlanAction::Remove),
+                ImportScan::Error(error) => match error.kind() {
+                    AssetErrorKind::Import(path) => {
+                        (path.clone(), PlanAction::Fail(error.to_string()))
+                    }
+                    AssetErrorKind::Load(_) => continue,
+                },
+            };
+
+            let path = path.without_prefix(&root).to_path_buf();
+            let action = match action {
+                PlanAction::Import(_) if quarantined(&path) => {
+                    let error = AssetError::import(&path, LoadErrorKind::Quarantined);
+                    PlanAction::Fail(error.to_string())
+                }
+                action => action,
+            };
+
+            plan.push(path, action);
+        }
+
+        plan
+    }
 }
 
 impl AssetEvent for ImportFolder {
     fn execute(&mut self, database: &AssetDatabase, events: &Events) {
         let config = database.config();
-        let path = self.path.with_prefix(config.root().join(config.assets()));
-        let scans = Self::scan_folder(&path, database);
+        let root = config.root().join(config.assets());
+        if let Err(error) = SourcePath::new(self.path.without_prefix(&root)) {
+            events.add(AssetError::import(&self.path, error));
+            return;
+        }
+
+        let path = self.path.with_prefix(&root);
+        let scans = Self::scan_folder(&path, database, self.dry_run);
+
+        if self.dry_run {
+            events.add(Self::plan(scans, database));
+            return;
+        }
 
         let mut errors = vec![];
         let mut imports = vec![];
@@ -200,8 +366,9 @@ impl AssetEvent for ImportFolder {
 
         for scan in scans {
             match scan {
-                ImportScan::Added(path) | ImportScan::Modified(path) => imports.push(path),
+                ImportScan::Added(path) | ImportScan::Modified(path, _) => imports.push(path),
                 ImportScan::Removed(path) => removed.push(path),
+                ImportScan::Unchanged(_) => {}
                 ImportScan::Error(error) => errors.push(error),
             }
         }
@@ -214,6 +381,11 @@ impl AssetEvent for ImportFolder {
         }
         events.extend(errors);
     }
+
+    /// Runs ahead of queued loads, which may depend on the library it updates.
+    fn priority(&self) -> LoadPriority {
+        LoadPriority::Critical
+    }
 }
 
 pub struct ImportAsset {
@@ -271,6 +443,7 @@ impl AssetEvent for ImportAssets {
         let mut errors = vec![];
         let mut imports = vec![];
         let mut dependents = DependentLibrary::load(config).unwrap_or_default();
+        let mut quarantine = QuarantineLibrary::load(config).unwrap_or_default();
 
         while !paths.is_empty() {
             let paths = paths.drain(..batch_size.min(paths.len()));
@@ -281,6 +454,14 @@ impl AssetEvent for ImportAssets {
                     .without_prefix(config.root().join(config.assets()))
                     .to_path_buf();
 
+                let source = match SourcePath::new(&path) {
+                    Ok(source) => source,
+                    Err(error) => {
+                        errors.push(AssetError::import(path, error));
+                        continue;
+                    }
+                };
+
                 let loader = match path.ext().and_then(|ext| registry.get_metadata_by_ext(ext)) {
                     Some(loader) => loader,
                     None => {
@@ -289,12 +470,41 @@ impl AssetEvent for ImportAssets {
                     }
                 };
 
-                let imported = match loader.import(&path, &registry, config, &mut assets) {
-                    Ok(imported) => imported,
-                    Err(error) => {
+                if quarantine.contains(&path) {
+                    let threshold = config.quarantine_threshold();
+                    match QuarantineLibrary::checksum(config, &path) {
+                        Some(checksum) if quarantine.is_quarantined(&path, checksum, threshold) => {
+                            errors.push(AssetError::import(path, LoadErrorKind::Quarantined));
+                            continue;
+                        }
+                        Some(checksum) if quarantine.panics(&path, checksum) > 0 => {}
+                        _ => {
+                            quarantine.remove(&path);
+                        }
+                    }
+                }
+
+                let import = catch_unwind(AssertUnwindSafe(|| {
+                    loader.import(&path, &registry, config, &mut assets)
+                }));
+
+                let imported = match import {
+                    Ok(Ok(imported)) => {
+                        quarantine.remove(&path);
+                        imported
+                    }
+                    Ok(Err(error)) => {
                         errors.push(error);
                         continue;
                     }
+                    Err(payload) => {
+                        if let Some(checksum) = QuarantineLibrary::checksum(config, &path) {
+                            quarantine.add(path.clone(), checksum);
+                        }
+
+                        errors.push(AssetError::import(path, LoadErrorKind::panicked(payload)));
+                        continue;
+                    }
                 };
 
                 for id in imported.dependencies() {
@@ -307,10 +517,13 @@ impl AssetEvent for ImportAssets {
                     }
                 }
 
+                let violations = imported.violations().to_vec();
+                let import = AssetImported::new(imported.id(), &source);
+                imports.push(import.with_violations(violations));
+                database.states_mut().clear_error(&source);
                 database
                     .library_mut()
-                    .add_asset(imported.id(), path.clone(), AssetKind::Main);
-                imports.push(AssetImported::new(imported.id(), path));
+                    .add_asset(imported.id(), source, AssetKind::Main);
                 assets.add_erased(imported.id(), imported.into());
             }
         }
@@ -319,29 +532,38 @@ impl AssetEvent for ImportAssets {
             errors.push(AssetError::import(DependentLibrary::path(config), e));
         }
 
+        if let Err(e) = quarantine.save(config) {
+            errors.push(AssetError::import(QuarantineLibrary::path(config), e));
+        }
+
         let library = database.library();
         let mut reimports = DenseSet::new();
-        let mut reloads = DenseSet::new();
+        let mut reloads = vec![];
         for import in &imports {
             if let Some(dependents) = dependents.get(&import.id()) {
-                let dependents = dependents.iter().filter_map(|id| library.path(id).cloned());
-                reimports.extend(dependents);
+                let dependents = dependents.iter().filter_map(|id| library.path(id));
+                reimports.extend(dependents.map(SourcePath::to_path_buf));
             }
 
-            if database.states().is_loaded(&import.id()) {
-                reloads.insert(import.id());
+            for collection in database.states().collections_of(&import.id()) {
+                let collection = collection.map(str::to_string);
+                reloads.push(LoadAsset::soft(import.id()).with_collection(collection));
             }
         }
 
         if !reloads.is_empty() {
-            database.events().push_front(LoadAssets::soft(reloads));
+            database.events().push_front(LoadAssets::new(reloads));
         }
         if !reimports.is_empty() {
             database.events().push_front(ImportAssets::new(reimports));
         }
 
-        events.extend(errors);
-        events.extend(imports);
+        let errors = errors.into_iter().map(ErasedEvent::from);
+        events.extend(errors.chain(imports.into_iter().map(ErasedEvent::from)));
+    }
+
+    fn priority(&self) -> LoadPriority {
+        LoadPriority::Critical
     }
 }
 
@@ -393,14 +615,19 @@ impl Event for RemoveAssets {
 impl AssetEvent for RemoveAssets {
     fn execute(&mut self, database: &AssetDatabase, events: &Events) {
         let config = database.config();
+        let root = config.root().join(config.assets());
         let mut dependents = DependentLibrary::load(config).unwrap_or_default();
         let mut reimports = DenseSet::new();
         let mut unloads = Vec::new();
 
         for path in &self.paths {
-            let path = path
-                .without_prefix(config.root().join(config.assets()))
-                .to_path_buf();
+            let path = match SourcePath::new(path.without_prefix(&root)) {
+                Ok(source) => source.to_path_buf(),
+                Err(error) => {
+                    events.add(AssetError::import(path, error));
+                    continue;
+                }
+            };
 
             let id = match database.library_mut().remove_path(&path) {
                 Some(id) => id,
@@ -419,13 +646,15 @@ impl AssetEvent for RemoveAssets {
             }
 
             let mut dependents = dependents.remove_asset(&id);
-            let dependents = dependents
-                .drain()
-                .filter_map(|id| database.library().path(&id).cloned());
-            reimports.extend(dependents);
+            let library = database.library();
+            let dependents = dependents.drain().filter_map(|id| library.path(&id));
+            reimports.extend(dependents.map(SourcePath::to_path_buf));
 
             reimports.remove(&path);
-            unloads.push(UnloadAsset::new(id));
+            for collection in database.states().collections_of(&id) {
+                let collection = collection.map(str::to_string);
+                unloads.push(UnloadAsset::new(id).with_collection(collection));
+            }
         }
 
         if let Err(e) = dependents.save(config) {
@@ -435,11 +664,16 @@ impl AssetEvent for RemoveAssets {
         database.events().push_front(ImportAssets::new(reimports));
         events.extend(unloads);
     }
+
+    fn priority(&self) -> LoadPriority {
+        LoadPriority::Critical
+    }
 }
 
 pub struct AssetImported {
     id: AssetId,
     path: PathBuf,
+    violations: Vec<Violation>,
 }
 
 impl AssetImported {
@@ -447,9 +681,15 @@ impl AssetImported {
         Self {
             id,
             path: path.as_ref().to_path_buf(),
+            violations: vec![],
         }
     }
 
+    pub fn with_violations(mut self, violations: Vec<Violation>) -> Self {
+        self.violations = violations;
+        self
+    }
+
     pub fn id(&self) -> AssetId {
         self.id
     }
@@ -457,6 +697,12 @@ impl AssetImported {
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
+
+    /// Stat checks the asset failed, copied from its `ImportedAsset` so observers
+    /// can surface them after the artifact is written.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
 }
 
 impl Event for AssetImported {
@@ -1,15 +1,13 @@
 use super::{AssetEvent, StartAssetEvent};
 use crate::{
-    asset::{Asset, AssetId, AssetPath, Assets},
-    database::{state::AssetState, AssetDatabase},
-    loader::{AssetError, LoadErrorKind, LoadedAssets},
+    asset::{Asset, AssetCollections, AssetId, AssetPath, Assets},
+    database::{finalize::PendingFinalize, state::AssetState, AssetDatabase},
+    io::path::SourcePath,
+    loader::{AssetError, LoadErrorKind, LoadPriority, LoadedAssets},
 };
-use shadow_ecs::{
-    core::DenseSet,
-    world::{
-        event::{Event, Events},
-        World,
-    },
+use shadow_ecs::world::{
+    event::{ErasedEvent, Event, Events},
+    World,
 };
 use std::collections::HashSet;
 
@@ -17,6 +15,8 @@ use std::collections::HashSet;
 pub struct LoadAsset {
     path: AssetPath,
     load_dependencies: bool,
+    collection: Option<String>,
+    priority: Option<LoadPriority>,
 }
 
 impl LoadAsset {
@@ -24,6 +24,8 @@ impl LoadAsset {
         Self {
             path: path.into(),
             load_dependencies: true,
+            collection: None,
+            priority: None,
         }
     }
 
@@ -31,6 +33,8 @@ impl LoadAsset {
         Self {
             path: path.into(),
             load_dependencies: true,
+            collection: None,
+            priority: None,
         }
     }
 
@@ -38,13 +42,39 @@ impl LoadAsset {
         Self {
             path: path.into(),
             load_dependencies: false,
+            collection: None,
+            priority: None,
         }
     }
 
+    pub fn into_collection(mut self, name: impl Into<String>) -> Self {
+        self.collection = Some(name.into());
+        self
+    }
+
+    pub fn with_collection(mut self, collection: Option<String>) -> Self {
+        self.collection = collection;
+        self
+    }
+
+    /// Overrides the priority of the asset's type for this load.
+    pub fn with_priority(mut self, priority: LoadPriority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    pub fn priority(&self) -> Option<LoadPriority> {
+        self.priority
+    }
+
     pub fn path(&self) -> &AssetPath {
         &self.path
     }
 
+    pub fn collection(&self) -> Option<&str> {
+        self.collection.as_deref()
+    }
+
     pub fn load_dependencies(&self) -> bool {
         self.load_dependencies
     }
@@ -64,37 +94,68 @@ impl Event for LoadAsset {
 
 pub struct LoadAssets {
     loads: Vec<LoadAsset>,
+    priority: LoadPriority,
 }
 
 impl LoadAssets {
     pub fn new(loads: impl IntoIterator<Item = LoadAsset>) -> Self {
         Self {
             loads: loads.into_iter().collect(),
+            priority: LoadPriority::Normal,
         }
     }
 
     pub fn hard(paths: impl IntoIterator<Item = impl Into<AssetPath>>) -> Self {
-        Self {
-            loads: paths.into_iter().map(LoadAsset::hard).collect(),
-        }
+        Self::new(paths.into_iter().map(LoadAsset::hard))
     }
 
     pub fn soft(paths: impl IntoIterator<Item = impl Into<AssetPath>>) -> Self {
-        Self {
-            loads: paths.into_iter().map(LoadAsset::soft).collect(),
+        Self::new(paths.into_iter().map(LoadAsset::soft))
+    }
+
+    /// Sets the priority of every load.
+    pub fn with_priority(mut self, priority: LoadPriority) -> Self {
+        for load in &mut self.loads {
+            load.priority = Some(priority);
         }
+
+        self.priority = priority;
+        self
     }
 
     pub fn loads(&self) -> &[LoadAsset] {
         &self.loads
     }
+
+    pub fn priority(&self) -> LoadPriority {
+        self.priority
+    }
 }
 
 impl Event for LoadAssets {
     type Output = ();
 
+    /// Splits the loads by priority, so that each group is queued separately.
     fn invoke(self, world: &mut World) -> Option<Self::Output> {
-        world.events().add(StartAssetEvent::new(self));
+        let database = world.resource::<AssetDatabase>();
+        let mut groups: Vec<LoadAssets> = vec![];
+        for load in self.loads {
+            let priority = match load.priority {
+                Some(priority) => priority,
+                None => database.load_priority(&load.path),
+            };
+
+            match groups.iter_mut().find(|group| group.priority == priority) {
+                Some(group) => group.loads.push(load),
+                None => groups.push(LoadAssets {
+                    loads: vec![load],
+                    priority,
+                }),
+            }
+        }
+
+        let groups = groups.into_iter().map(StartAssetEvent::new);
+        world.events().extend(groups.map(ErasedEvent::new));
         None
     }
 }
@@ -103,20 +164,30 @@ impl AssetEvent for LoadAssets {
     fn execute(&mut self, database: &AssetDatabase, events: &Events) {
         let mut errors = vec![];
         let mut assets = LoadedAssets::new();
-        let mut loaded_ids = DenseSet::new();
+        let mut loaded_ids: Vec<(AssetId, Option<String>)> = vec![];
+        let mut loaded_assets = vec![];
         let config = database.config();
 
         let registry = database.registry();
         for load in &self.loads {
             let id = match &load.path {
                 AssetPath::Id(id) => *id,
-                AssetPath::Path(path) => match database.library().id(&path).copied() {
-                    Some(id) => id,
-                    None => continue,
-                },
+                AssetPath::Path(path) => {
+                    if let Err(error) = SourcePath::new(path) {
+                        errors.push(AssetError::load(path, error));
+                        continue;
+                    }
+
+                    match database.library().id(path).copied() {
+                        Some(id) => id,
+                        None => continue,
+                    }
+                }
             };
 
-            if assets.contains(&id) {
+            // Each collection gets its own copy of the asset.
+            let key = (id, load.collection.clone());
+            if loaded_ids.contains(&key) {
                 continue;
             }
 
@@ -145,38 +216,62 @@ impl AssetEvent for LoadAssets {
                     }
                 };
 
-            loaded_ids.insert(id);
-            assets.add_erased(id, asset);
+            loaded_ids.push(key);
+            loaded_assets.push(asset);
         }
 
         let registry = database.registry();
         let mut loaded = vec![];
-        for id in loaded_ids.drain() {
-            let asset = match assets.remove(&id) {
-                Some(loaded) => loaded,
-                _ => continue,
-            };
-
+        for ((_, collection), asset) in loaded_ids.into_iter().zip(loaded_assets) {
             let metadata = match registry.get_metadata(asset.meta().ty()) {
                 Some(metadata) => metadata,
                 None => continue,
             };
 
-            loaded.push(metadata.loaded(asset));
+            match metadata.finalizes_on_main() {
+                true => {
+                    let pending = PendingFinalize::new(asset, collection, self.priority);
+                    database.finalizing().push(pending);
+                }
+                false => loaded.push(metadata.loaded(asset, collection)),
+            }
         }
 
-        events.extend(loaded);
-        events.extend(errors);
+        let errors = errors.into_iter().map(ErasedEvent::from);
+        events.extend(loaded.into_iter().chain(errors));
+    }
+
+    fn priority(&self) -> LoadPriority {
+        self.priority
     }
 }
 
 pub struct UnloadAsset {
     path: AssetPath,
+    collection: Option<String>,
 }
 
 impl UnloadAsset {
     pub fn new(path: impl Into<AssetPath>) -> Self {
-        Self { path: path.into() }
+        Self {
+            path: path.into(),
+            collection: None,
+        }
+    }
+
+    /// Unloads the asset from a named collection instead of the default `Assets<A>`.
+    pub fn from_collection(mut self, name: impl Into<String>) -> Self {
+        self.collection = Some(name.into());
+        self
+    }
+
+    pub fn with_collection(mut self, collection: Option<String>) -> Self {
+        self.collection = collection;
+        self
+    }
+
+    pub fn collection(&self) -> Option<&str> {
+        self.collection.as_deref()
     }
 }
 
@@ -188,13 +283,14 @@ impl Event for UnloadAsset {
 
         let id = match self.path {
             AssetPath::Id(id) => id,
-            AssetPath::Path(path) => database.library().id(&path).cloned()?,
+            AssetPath::Path(path) => database.library().id(path).cloned()?,
         };
 
-        let state = database.states_mut().unload(&id)?;
+        let collection = self.collection.as_deref();
+        let state = database.states_mut().unload_in(collection, &id)?;
         let registry = database.registry();
         let metadata = registry.get_metadata(state.ty())?;
-        let event = metadata.unloaded(id, state, world)?;
+        let event = metadata.unloaded(id, collection, state, world)?;
         world.events().add(event);
 
         None
@@ -205,17 +301,32 @@ pub struct AssetUnloaded<A: Asset> {
     id: AssetId,
     asset: A,
     state: AssetState,
+    collection: Option<String>,
 }
 
 impl<A: Asset> AssetUnloaded<A> {
     pub fn new(id: AssetId, asset: A, state: AssetState) -> Self {
-        Self { id, asset, state }
+        Self {
+            id,
+            asset,
+            state,
+            collection: None,
+        }
+    }
+
+    pub fn with_collection(mut self, collection: Option<String>) -> Self {
+        self.collection = collection;
+        self
     }
 
     pub fn id(&self) -> AssetId {
         self.id
     }
 
+    pub fn collection(&self) -> Option<&str> {
+        self.collection.as_deref()
+    }
+
     pub fn asset(&self) -> &A {
         &self.asset
     }
@@ -224,16 +335,19 @@ impl<A: Asset> AssetUnloaded<A> {
         &self.state
     }
 
+    /// Reloads the dependents of unloaded assets in the collection they were unloaded from.
     pub fn observer(unloaded: &[AssetUnloaded<A>], database: &AssetDatabase, events: &Events) {
         let states = database.states();
-        let mut reloads = DenseSet::new();
+        let mut reloads = vec![];
 
         for unloaded in unloaded {
-            let dependents = states.dependents(&unloaded.id());
-            reloads.extend(dependents);
+            let collection = unloaded.collection.clone();
+            let dependents = states.dependents_in(unloaded.collection(), &unloaded.id());
+            let dependents = dependents.into_iter().map(LoadAsset::soft);
+            reloads.extend(dependents.map(|load| load.with_collection(collection.clone())));
         }
 
-        events.add(LoadAssets::soft(reloads));
+        events.add(LoadAssets::new(reloads));
     }
 }
 
@@ -249,6 +363,7 @@ pub struct AssetLoaded<A: Asset> {
     id: AssetId,
     asset: A,
     dependencies: HashSet<AssetId>,
+    collection: Option<String>,
 }
 
 impl<A: Asset> AssetLoaded<A> {
@@ -257,29 +372,25 @@ impl<A: Asset> AssetLoaded<A> {
             id,
             asset,
             dependencies,
+            collection: None,
         }
     }
 
+    pub fn with_collection(mut self, collection: Option<String>) -> Self {
+        self.collection = collection;
+        self
+    }
+
     pub fn id(&self) -> AssetId {
         self.id
     }
 
-    pub fn asset(&self) -> &A {
-        &self.asset
+    pub fn collection(&self) -> Option<&str> {
+        self.collection.as_deref()
     }
 
-    pub fn observer(loaded: &[AssetId], database: &AssetDatabase, events: &Events) {
-        let states = database.states();
-        let mut reloads = DenseSet::new();
-
-        for id in loaded {
-            let dependents = states.dependents(id);
-            reloads.extend(dependents);
-        }
-
-        if !reloads.is_empty() {
-            events.add(LoadAssets::soft(reloads));
-        }
+    pub fn asset(&self) -> &A {
+        &self.asset
     }
 }
 
@@ -288,11 +399,26 @@ impl<A: Asset> Event for AssetLoaded<A> {
 
     fn invoke(self, world: &mut World) -> Option<Self::Output> {
         let database = world.resource_mut::<AssetDatabase>();
-        let assets = world.resource_mut::<Assets<A>>();
+        let assets = match &self.collection {
+            Some(name) => world
+                .resource_mut::<AssetCollections<A>>()
+                .get_or_insert(name),
+            None => world.resource_mut::<Assets<A>>(),
+        };
         let mut states = database.states_mut();
+        let collection = self.collection.as_deref();
 
         assets.add(self.id, self.asset);
-        states.load(self.id, AssetState::new::<A>(self.dependencies));
+        states.load_in(collection, self.id, AssetState::new::<A>(self.dependencies));
+
+        // Dependents are reloaded here rather than by an observer, which only sees the ids
+        // and not the collection they were loaded into.
+        let dependents = states.dependents_in(collection, &self.id);
+        if !dependents.is_empty() {
+            let reloads = dependents.into_iter().map(LoadAsset::soft);
+            let reloads = reloads.map(|load| load.with_collection(self.collection.clone()));
+            world.events().add(LoadAssets::new(reloads));
+        }
 
         Some(self.id)
     }
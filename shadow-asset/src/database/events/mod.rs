@@ -1,12 +1,17 @@
-use crate::loader::{AssetError, AssetErrorKind};
+use crate::loader::{AssetError, AssetErrorKind, LoadPriority};
 
 use super::AssetDatabase;
 use shadow_ecs::{
-    system::RunMode,
-    task::TaskPool,
-    world::event::{Event, Events},
+    system::{PanicPolicy, RunMode},
+    world::{
+        event::{Event, Events, SystemPanicked},
+        World,
+    },
+};
+use std::{
+    collections::VecDeque,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
 };
-use std::collections::VecDeque;
 
 pub mod import;
 pub mod load;
@@ -16,6 +21,10 @@ pub use load::*;
 
 pub trait AssetEvent: Send + Sync + 'static {
     fn execute(&mut self, database: &AssetDatabase, events: &Events);
+
+    fn priority(&self) -> LoadPriority {
+        LoadPriority::Normal
+    }
 }
 
 impl<A: AssetEvent> From<A> for Box<dyn AssetEvent> {
@@ -41,8 +50,16 @@ impl AssetEvents {
         self.running
     }
 
+    /// Queues `event` after every event of the same or higher priority.
     pub fn push(&mut self, event: impl Into<Box<dyn AssetEvent>>) {
-        self.events.push_back(event.into());
+        let event = event.into();
+        let index = self
+            .events
+            .iter()
+            .position(|queued| queued.priority() < event.priority())
+            .unwrap_or(self.events.len());
+
+        self.events.insert(index, event);
     }
 
     pub fn push_front(&mut self, event: impl Into<Box<dyn AssetEvent>>) {
@@ -81,7 +98,7 @@ impl StartAssetEvent {
         &*self.event
     }
 
-    pub fn on_start(_: &[()], database: &AssetDatabase, events: &Events, tasks: &TaskPool) {
+    pub fn on_start(_: &[()], world: &World, database: &AssetDatabase, events: &Events) {
         let mut db_events = database.events();
         if !db_events.is_running() {
             db_events.start();
@@ -89,17 +106,30 @@ impl StartAssetEvent {
 
             let database = database.clone();
             let events = events.clone();
+            let policy = world.panic_policy();
 
             match database.config().mode() {
                 RunMode::Sequential => {
+                    let _running = RunningGuard(&database);
                     AssetEventExecutor::execute(&database, &events);
-
-                    database.events().stop();
                 }
-                RunMode::Parallel => tasks.spawn(move || {
-                    AssetEventExecutor::execute(&database, &events);
+                RunMode::Parallel => world.tasks().spawn(move || {
+                    // The guard is dropped while unwinding, so it stops the executor
+                    // whether or not the panic is caught.
+                    let result = catch_unwind(AssertUnwindSafe(|| {
+                        let _running = RunningGuard(&database);
+                        AssetEventExecutor::execute(&database, &events)
+                    }));
 
-                    database.events().stop();
+                    // Like systems, the panic is only caught when the policy allows it.
+                    match result {
+                        Err(payload) if policy == PanicPolicy::Propagate => resume_unwind(payload),
+                        Err(payload) => {
+                            let name = std::any::type_name::<AssetEventExecutor>();
+                            events.add(SystemPanicked::from_payload(name, payload));
+                        }
+                        Ok(()) => {}
+                    }
                 }),
             }
         }
@@ -116,6 +146,18 @@ impl Event for StartAssetEvent {
     }
 }
 
+/// Clears the running flag if an asset event unwinds. On a normal exit the flag is
+/// cleared by the final `pop_event`, and a new executor may already be running.
+struct RunningGuard<'a>(&'a AssetDatabase);
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.0.events().stop();
+        }
+    }
+}
+
 pub struct AssetEventExecutor;
 
 impl AssetEventExecutor {
@@ -127,11 +169,12 @@ impl AssetEventExecutor {
 }
 
 impl AssetError {
-    pub fn observer(errors: &[AssetError], events: &Events) {
+    pub fn observer(errors: &[AssetError], database: &AssetDatabase, events: &Events) {
         let mut remove = Vec::new();
         let mut unloads = Vec::new();
 
         for error in errors {
+            database.record_error(error);
             match error.kind() {
                 AssetErrorKind::Import(path) => remove.push(path.clone()),
                 AssetErrorKind::Load(path) => unloads.push(UnloadAsset::new(path.clone())),
@@ -147,24 +190,41 @@ impl AssetError {
 mod tests {
     use shadow_ecs::{
         core::Resource,
-        system::{schedule::Root, RunMode},
-        world::World,
+        system::{schedule::Root, PanicPolicy, RunMode},
+        world::{
+            event::{Events, SystemPanicked},
+            World,
+        },
+    };
+    use std::{
+        collections::HashSet,
+        panic::{catch_unwind, AssertUnwindSafe},
+        path::PathBuf,
+        sync::{Arc, Mutex},
+        time::Duration,
     };
-    use std::path::PathBuf;
 
     use crate::{
-        asset::{Asset, AssetId, Assets, DefaultSettings},
+        artifact::{Artifact, ArtifactHeader, ArtifactMeta, StatValue},
+        asset::{Asset, AssetCollections, AssetId, Assets, DefaultSettings, RetentionPolicy},
         database::{
             events::{
-                AssetLoaded, AssetUnloaded, ImportFolder, LoadAssets, StartAssetEvent, UnloadAsset,
+                AssetLoaded, AssetUnloaded, ImportFolder, ImportPlan, ImportReason, LoadAsset,
+                LoadAssets, PlanAction, StartAssetEvent, UnloadAsset,
             },
+            finalize::{finalize_assets, PendingFinalize},
+            retention::{discard_assets, discard_collection_assets},
             AssetConfig, AssetDatabase,
         },
         io::{vfs::VirtualFileSystem, AssetIoError, AssetReader},
-        loader::{AssetSerializer, AssetError, AssetLoader, LoadContext},
+        loader::{
+            AssetError, AssetLoader, AssetSerializer, LoadContext, LoadErrorKind, LoadPriority,
+            LoadedAsset,
+        },
+        validation::StatCheck,
     };
 
-    use super::{AssetImported, ImportAssets, RemoveAssets};
+    use super::{AssetEvent, AssetImported, ImportAssets, RemoveAssets};
 
     struct PlainText(String);
     impl Asset for PlainText {}
@@ -192,11 +252,13 @@ mod tests {
         type Serializer = Self;
 
         fn load(
-            _: &mut LoadContext<Self::Settings>,
+            ctx: &mut LoadContext<Self::Settings>,
             reader: &mut dyn AssetReader,
         ) -> Result<Self::Asset, Self::Error> {
             reader.read_to_end()?;
-            <Self::Serializer as AssetSerializer>::deserialize(&reader.flush()?)
+            let text = <Self::Serializer as AssetSerializer>::deserialize(&reader.flush()?)?;
+            ctx.add_stat("chars", text.0.len());
+            Ok(text)
         }
 
         fn extensions() -> &'static [&'static str] {
@@ -204,20 +266,114 @@ mod tests {
         }
     }
 
+    struct Broken;
+    impl Asset for Broken {}
+
+    impl AssetSerializer for Broken {
+        type Asset = Self;
+        type Error = AssetIoError;
+
+        fn serialize(_: &Self::Asset) -> Result<Vec<u8>, Self::Error> {
+            Ok(vec![])
+        }
+
+        fn deserialize(_: &[u8]) -> Result<Self::Asset, Self::Error> {
+            Ok(Self)
+        }
+    }
+
+    impl AssetLoader for Broken {
+        type Asset = Self;
+        type Settings = DefaultSettings;
+        type Error = AssetIoError;
+        type Serializer = Self;
+
+        fn load(
+            _: &mut LoadContext<Self::Settings>,
+            _: &mut dyn AssetReader,
+        ) -> Result<Self::Asset, Self::Error> {
+            panic!("malformed file")
+        }
+
+        fn extensions() -> &'static [&'static str] {
+            &["bad"]
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Mesh {
+        uploaded: bool,
+    }
+
+    impl Asset for Mesh {}
+
+    impl AssetSerializer for Mesh {
+        type Asset = Self;
+        type Error = AssetIoError;
+
+        fn serialize(_: &Self::Asset) -> Result<Vec<u8>, Self::Error> {
+            Ok(vec![])
+        }
+
+        fn deserialize(_: &[u8]) -> Result<Self::Asset, Self::Error> {
+            Ok(Self { uploaded: false })
+        }
+    }
+
+    impl AssetLoader for Mesh {
+        type Asset = Self;
+        type Settings = DefaultSettings;
+        type Error = AssetIoError;
+        type Serializer = Self;
+
+        fn load(
+            _: &mut LoadContext<Self::Settings>,
+            reader: &mut dyn AssetReader,
+        ) -> Result<Self::Asset, Self::Error> {
+            reader.read_to_end()?;
+            Ok(Self { uploaded: false })
+        }
+
+        fn extensions() -> &'static [&'static str] {
+            &["mesh"]
+        }
+
+        fn finalize_on_main() -> bool {
+            true
+        }
+
+        fn finalize(asset: &mut Self::Asset, _: &World) {
+            asset.uploaded = true;
+        }
+    }
+
     #[derive(Default)]
     pub struct Tracker {
         pub imported: bool,
         pub loaded: bool,
         pub unloaded: bool,
+        pub planned: Vec<ImportPlan>,
+        pub errors: Vec<String>,
+        pub order: Vec<AssetId>,
+        pub violations: Vec<String>,
     }
 
     impl Resource for Tracker {}
 
     fn create_world() -> World {
+        create_world_with(|_| {})
+    }
+
+    fn create_world_with(setup: impl FnOnce(&mut AssetConfig)) -> World {
         let mut config = AssetConfig::new(VirtualFileSystem::new(""));
         config.register::<PlainText>();
         config.set_loader::<PlainText>();
+        config.register::<Broken>();
+        config.set_loader::<Broken>();
+        config.register::<Mesh>();
+        config.set_loader::<Mesh>();
         config.set_run_mode(RunMode::Sequential);
+        setup(&mut config);
         config.init().unwrap();
 
         let mut writer = config.writer(config.assets().join("test.txt"));
@@ -228,10 +384,14 @@ mod tests {
         world
             .add_resource(AssetDatabase::new(config))
             .init_resource::<Assets<PlainText>>()
+            .init_resource::<AssetCollections<PlainText>>()
+            .init_resource::<Assets<Mesh>>()
             .init_resource::<Tracker>()
             .register_event::<AssetLoaded<PlainText>>()
+            .register_event::<AssetLoaded<Mesh>>()
             .register_event::<AssetUnloaded<PlainText>>()
             .register_event::<ImportFolder>()
+            .register_event::<ImportPlan>()
             .register_event::<ImportAssets>()
             .register_event::<AssetImported>()
             .register_event::<RemoveAssets>()
@@ -244,6 +404,21 @@ mod tests {
         world
     }
 
+    fn write_assets(world: &World, files: &[(&str, &str)]) {
+        let config = world.resource::<AssetDatabase>().config();
+        for (name, content) in files {
+            let mut writer = config.writer(config.assets().join(name));
+            writer.write(content.as_bytes()).unwrap();
+            writer.flush().unwrap();
+        }
+    }
+
+    fn asset_id(world: &World, path: &str) -> AssetId {
+        let database = world.resource::<AssetDatabase>();
+        let id = database.library().id(path).copied();
+        id.unwrap()
+    }
+
     #[test]
     fn import() {
         let mut world = create_world();
@@ -254,7 +429,7 @@ mod tests {
 
         let database = world.resource::<AssetDatabase>();
 
-        let id = database.library().id(&PathBuf::from("test.txt")).cloned();
+        let id = database.library().id("test.txt").cloned();
         assert!(id.is_some());
         assert!(database
             .config()
@@ -316,18 +491,14 @@ mod tests {
 
         let id = {
             let database = world.resource::<AssetDatabase>();
-            database
-                .library()
-                .id(&PathBuf::from("test.txt"))
-                .cloned()
-                .unwrap()
+            database.library().id("test.txt").cloned().unwrap()
         };
 
         world.events().add(RemoveAssets::new(vec!["test.txt"]));
         world.run(Root);
 
         let database = world.resource::<AssetDatabase>();
-        let removed = database.library().id(&PathBuf::from("test.txt")).cloned();
+        let removed = database.library().id("test.txt").cloned();
 
         assert!(removed.is_none());
         assert!(!database
@@ -335,4 +506,683 @@ mod tests {
             .filesystem()
             .exists(&database.config().artifact(id)))
     }
+
+    #[test]
+    fn collections_load_and_unload_independently() {
+        let mut world = create_world();
+        world.build();
+
+        world.events().add(ImportFolder::new(""));
+        world.events().add(LoadAssets::new([
+            LoadAsset::hard("test.txt"),
+            LoadAsset::hard("test.txt").into_collection("play"),
+        ]));
+        world.run(Root);
+
+        let id = asset_id(&world, "test.txt");
+        let loaded = |world: &World| {
+            let database = world.resource::<AssetDatabase>();
+            let states = database.states();
+            let play = world.resource::<AssetCollections<PlainText>>();
+            let play = play.collection("play").map(|play| play.contains(&id));
+            (
+                world.resource::<Assets<PlainText>>().contains(&id),
+                play.unwrap_or_default(),
+                states.is_loaded(&id),
+                states.is_loaded_in(Some("play"), &id),
+            )
+        };
+        assert_eq!(loaded(&world), (true, true, true, true));
+
+        let unload = UnloadAsset::new(id).from_collection("play");
+        world.events().add(unload);
+        world.run(Root);
+        assert_eq!(loaded(&world), (true, false, true, false));
+
+        world.events().add(UnloadAsset::new(id));
+        world.run(Root);
+        assert_eq!(loaded(&world), (false, false, false, false));
+    }
+
+    #[test]
+    fn collections_keep_their_own_retention() {
+        let mut world = create_world();
+        world
+            .add_system(Root, discard_assets::<PlainText>)
+            .add_system(Root, discard_collection_assets::<PlainText>)
+            .build();
+
+        world.events().add(ImportFolder::new(""));
+        world.events().add(LoadAssets::new([
+            LoadAsset::hard("test.txt").into_collection("play"),
+            LoadAsset::hard("test.txt"),
+        ]));
+        world.run(Root);
+
+        let id = asset_id(&world, "test.txt");
+        world
+            .resource_mut::<AssetCollections<PlainText>>()
+            .get_or_insert("play")
+            .set_retention(RetentionPolicy::DiscardAfterFrames(1));
+        world.run(Root);
+        world.run(Root);
+
+        let database = world.resource::<AssetDatabase>();
+        let play = world.resource::<AssetCollections<PlainText>>();
+        assert!(!play.collection("play").unwrap().contains(&id));
+        assert!(!database.states().is_loaded_in(Some("play"), &id));
+        assert!(world.resource::<Assets<PlainText>>().contains(&id));
+        assert!(database.states().is_loaded(&id));
+    }
+
+    fn plan(world: &mut World) -> Vec<(PathBuf, PlanAction)> {
+        world.resource_mut::<Tracker>().planned.clear();
+        world.events().add(ImportFolder::dry_run(""));
+        world.run(Root);
+
+        let plans = &world.resource::<Tracker>().planned;
+        assert_eq!(plans.len(), 1);
+        let mut entries = plans[0].entries().to_vec();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+
+    #[test]
+    fn dry_run() {
+        let mut world = create_world_with(|config| config.set_quarantine_threshold(1));
+        world.observe::<ImportPlan, _>(|plans: &[ImportPlan], tracker: &mut Tracker| {
+            tracker.planned.extend(plans.iter().cloned());
+        });
+        world.build();
+        write_assets(
+            &world,
+            &[
+                ("changed.txt", "Before"),
+                ("orphan.txt", "Orphan"),
+                ("stale.txt", "Stale"),
+                ("broken.bad", "???"),
+            ],
+        );
+
+        let new = PlanAction::Import(ImportReason::New);
+        let entries = plan(&mut world);
+        let paths = [
+            "broken.bad",
+            "changed.txt",
+            "orphan.txt",
+            "stale.txt",
+            "test.txt",
+        ];
+        let expected = paths.map(|path| (PathBuf::from(path), new.clone()));
+        assert_eq!(entries, expected);
+        {
+            let database = world.resource::<AssetDatabase>();
+            let config = database.config();
+            assert!(database.library().id("test.txt").is_none());
+            assert!(!config
+                .filesystem()
+                .exists(&config.assets().join("test.txt.meta")));
+        }
+
+        // The importer panics on `broken.bad`, which quarantines it at this threshold.
+        world.events().add(ImportFolder::new(""));
+        world.run(Root);
+
+        write_assets(&world, &[("changed.txt", "After"), ("new.txt", "New")]);
+        let stale = asset_id(&world, "stale.txt");
+        {
+            let config = world.resource::<AssetDatabase>().config();
+            for path in ["orphan.txt", "orphan.txt.meta"] {
+                config
+                    .writer(config.assets().join(path))
+                    .remove_file()
+                    .unwrap();
+            }
+
+            let artifact = config.load_artifact(stale).unwrap();
+            let version = artifact.meta().version() + 1;
+            let meta = artifact.meta().clone().with_version(version);
+            let mut writer = config.writer(config.artifact(stale));
+            writer
+                .write(&Artifact::bytes(artifact.asset(), &meta))
+                .unwrap();
+            writer.flush().unwrap();
+        }
+
+        let quarantined = AssetError::import("broken.bad", LoadErrorKind::Quarantined);
+        let expected = [
+            ("broken.bad", PlanAction::Fail(quarantined.to_string())),
+            (
+                "changed.txt",
+                PlanAction::Import(ImportReason::SourceChanged),
+            ),
+            ("new.txt", new),
+            ("orphan.txt", PlanAction::Remove),
+            ("stale.txt", PlanAction::Import(ImportReason::LoaderVersion)),
+            ("test.txt", PlanAction::Unchanged),
+        ];
+        let expected = expected.map(|(path, action)| (PathBuf::from(path), action));
+        assert_eq!(plan(&mut world), expected);
+
+        let database = world.resource::<AssetDatabase>();
+        assert!(database.library().id("new.txt").is_none());
+        assert!(database.library().id("orphan.txt").is_some());
+        let version = database
+            .config()
+            .load_artifact_meta(stale)
+            .unwrap()
+            .version();
+        assert_eq!(version, PlainText::version() + 1);
+    }
+
+    #[test]
+    fn invalid_artifact() {
+        let mut world = create_world();
+        world.observe::<ImportPlan, _>(|plans: &[ImportPlan], tracker: &mut Tracker| {
+            tracker.planned.extend(plans.iter().cloned());
+        });
+        world.build();
+
+        world.events().add(ImportFolder::new(""));
+        world.run(Root);
+
+        let id = asset_id(&world, "test.txt");
+        let artifact = {
+            let config = world.resource::<AssetDatabase>().config();
+            let mut reader = config.reader(config.artifact(id));
+            reader.read_to_end().unwrap();
+            reader.flush().unwrap()
+        };
+
+        // Truncated inside the meta, and written before the meta had a format header.
+        let truncated = artifact[..ArtifactHeader::SIZE + 12].to_vec();
+        let mut unversioned = artifact[..ArtifactHeader::SIZE].to_vec();
+        unversioned.extend_from_slice(&artifact[ArtifactHeader::SIZE + 8..]);
+
+        for bytes in [truncated, unversioned] {
+            {
+                let config = world.resource::<AssetDatabase>().config();
+                let mut writer = config.writer(config.artifact(id));
+                writer.write(&bytes).unwrap();
+                writer.flush().unwrap();
+                assert!(config.load_artifact_meta(id).is_err());
+            }
+
+            world.resource_mut::<Tracker>().planned.clear();
+            world.events().add(ImportFolder::dry_run(""));
+            world.run(Root);
+
+            let plan = &world.resource::<Tracker>().planned[0];
+            let action = Some(&PlanAction::Import(ImportReason::InvalidArtifact));
+            assert_eq!(plan.action("test.txt"), action);
+
+            world.events().add(ImportFolder::new(""));
+            world.run(Root);
+
+            let config = world.resource::<AssetDatabase>().config();
+            assert!(config.load_artifact_meta(id).is_ok());
+        }
+    }
+
+    fn import_broken(world: &mut World) -> Vec<String> {
+        world.resource_mut::<Tracker>().errors.clear();
+        world.events().add(ImportFolder::new(""));
+        world.run(Root);
+        std::mem::take(&mut world.resource_mut::<Tracker>().errors)
+    }
+
+    fn broken_world(threshold: u32) -> World {
+        let mut world = create_world_with(|config| config.set_quarantine_threshold(threshold));
+        world.observe::<AssetError, _>(|errors: &[AssetError], tracker: &mut Tracker| {
+            let errors = errors.iter().map(|error| error.to_string());
+            tracker.errors.extend(errors);
+        });
+        world.build();
+        write_assets(&world, &[("other.txt", "Other"), ("broken.bad", "???")]);
+        world
+    }
+
+    #[test]
+    fn importer_panic() {
+        let mut world = broken_world(1);
+        let errors = import_broken(&mut world);
+
+        {
+            let database = world.resource::<AssetDatabase>();
+            let library = database.library();
+            assert!(library.id("test.txt").is_some());
+            assert!(library.id("other.txt").is_some());
+            assert!(library.id("broken.bad").is_none());
+            assert!(!database.events().is_running());
+            assert!(errors.iter().any(|error| error.contains("malformed file")));
+        }
+
+        let errors = import_broken(&mut world);
+        assert!(errors.iter().any(|error| error.contains("quarantined")));
+        assert!(!errors.iter().any(|error| error.contains("malformed file")));
+    }
+
+    #[test]
+    fn quarantine_after_repeated_panics() {
+        let mut world = broken_world(AssetConfig::QUARANTINE_THRESHOLD);
+        for _ in 0..AssetConfig::QUARANTINE_THRESHOLD {
+            let errors = import_broken(&mut world);
+            assert!(errors.iter().any(|error| error.contains("malformed file")));
+            assert!(!errors.iter().any(|error| error.contains("quarantined")));
+        }
+
+        let errors = import_broken(&mut world);
+        assert!(errors.iter().any(|error| error.contains("quarantined")));
+        assert!(!errors.iter().any(|error| error.contains("malformed file")));
+    }
+
+    struct Explode;
+
+    impl AssetEvent for Explode {
+        fn execute(&mut self, _: &AssetDatabase, _: &Events) {
+            panic!("exploded")
+        }
+    }
+
+    fn explode_in_parallel(policy: PanicPolicy) -> World {
+        let mut world = create_world_with(|config| config.set_run_mode(RunMode::Parallel));
+        world.set_panic_policy(policy);
+        world.observe::<SystemPanicked, _>(|panics: &[SystemPanicked], tracker: &mut Tracker| {
+            let panics = panics.iter().map(|panic| panic.message().to_string());
+            tracker.errors.extend(panics);
+        });
+        world.build();
+
+        world.events().add(StartAssetEvent::new(Explode));
+        world.run(Root);
+        while world.resource::<AssetDatabase>().events().is_running() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        world.flush();
+        world
+    }
+
+    #[test]
+    fn executor_panic_isolated() {
+        let world = explode_in_parallel(PanicPolicy::Isolate);
+        assert_eq!(world.resource::<Tracker>().errors, ["exploded"]);
+    }
+
+    #[test]
+    fn executor_panic_propagates() {
+        let world = explode_in_parallel(PanicPolicy::Propagate);
+        assert!(world.resource::<Tracker>().errors.is_empty());
+
+        // The task pool rethrows the executor's panic when it joins its threads.
+        let dropped = catch_unwind(AssertUnwindSafe(|| drop(world)));
+        assert!(dropped.is_err());
+    }
+
+    struct Record {
+        id: usize,
+        ran: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl AssetEvent for Record {
+        fn execute(&mut self, _: &AssetDatabase, _: &Events) {
+            self.ran.lock().unwrap().push(self.id);
+        }
+    }
+
+    #[test]
+    fn events_pushed_while_executor_stops_run_once() {
+        let mut world = create_world_with(|config| config.set_run_mode(RunMode::Parallel));
+        world.build();
+
+        // Each event is queued just as the executor may be popping its last one, so
+        // it must either be popped by that executor or start a new one.
+        let ran = Arc::new(Mutex::new(Vec::new()));
+        for id in 0..5000 {
+            let ran = ran.clone();
+            world.events().add(StartAssetEvent::new(Record { id, ran }));
+            world.flush();
+            if id % 7 == 0 {
+                std::thread::yield_now();
+            }
+        }
+
+        while world.resource::<AssetDatabase>().events().is_running() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        let mut ran = std::mem::take(&mut *ran.lock().unwrap());
+        ran.sort_unstable();
+        assert_eq!(ran, (0..5000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn reject_traversal() {
+        let mut world = create_world();
+        world.observe::<AssetError, _>(|errors: &[AssetError], tracker: &mut Tracker| {
+            let errors = errors.iter().map(|error| error.to_string());
+            tracker.errors.extend(errors);
+        });
+        world.build();
+
+        world.events().add(ImportFolder::new(""));
+        world.events().add(ImportFolder::new("../outside"));
+        world
+            .events()
+            .add(RemoveAssets::new(["sub/../../test.txt"]));
+        world.run(Root);
+
+        let errors = &world.resource::<Tracker>().errors;
+        let rejected = errors
+            .iter()
+            .filter(|error| error.contains("leaves the assets root"));
+        assert_eq!(rejected.count(), 2);
+
+        let database = world.resource::<AssetDatabase>();
+        assert!(database.library().id(".\\test.txt").is_some());
+    }
+
+    #[test]
+    fn inspect() {
+        let mut world = create_world();
+        world.observe::<AssetError, _>(AssetError::observer);
+        world.build();
+
+        {
+            let config = world.resource::<AssetDatabase>().config();
+            let mut writer = config.writer(config.assets().join("bad.txt"));
+            writer.write(&[0xff, 0xfe]).unwrap();
+            writer.flush().unwrap();
+        }
+
+        world.events().add(ImportFolder::new(""));
+        world.run(Root);
+
+        let database = world.resource::<AssetDatabase>();
+        let text = std::any::type_name::<PlainText>();
+        let healthy = database.describe_path("test.txt").unwrap();
+        assert_eq!((healthy.asset, healthy.loader), (Some(text), Some(text)));
+        assert!(healthy.meta.is_some());
+        assert_eq!(healthy.artifact.and_then(|a| a.current), Some(true));
+        assert_eq!(healthy.error, None);
+
+        let failed = database.describe_path("bad.txt").unwrap();
+        assert_eq!((failed.id, failed.artifact), (None, None));
+        assert!(failed.error.is_some());
+
+        let id = healthy.id.unwrap();
+        let report = database.describe_asset(id);
+        assert_eq!(report.asset, Some(text));
+        assert!(!report.is_orphaned());
+
+        database.library_mut().remove_path("test.txt");
+        let orphan = database.describe_asset(id);
+        assert!(orphan.is_orphaned());
+        assert_eq!(orphan.artifact.and_then(|a| a.current), None);
+
+        let loaders = database.list_loaders();
+        assert!(loaders.iter().any(|loader| loader.extensions == ["bad"]));
+    }
+
+    #[test]
+    fn load_priority() {
+        let mut world = create_world_with(|config| {
+            config.set_priority::<PlainText>(LoadPriority::High);
+        });
+        world.observe::<AssetLoaded<PlainText>, _>(|ids: &[AssetId], tracker: &mut Tracker| {
+            tracker.order.extend_from_slice(ids);
+        });
+        world.build();
+
+        write_assets(&world, &[("low.txt", "Low"), ("critical.txt", "Critical")]);
+        world.events().add(ImportFolder::new(""));
+        world.run(Root);
+
+        world.events().add(LoadAssets::new([
+            LoadAsset::new("low.txt").with_priority(LoadPriority::Low),
+            LoadAsset::new("test.txt"),
+            LoadAsset::new("critical.txt").with_priority(LoadPriority::Critical),
+        ]));
+        world.run(Root);
+
+        let expected = ["critical.txt", "test.txt", "low.txt"].map(|path| asset_id(&world, path));
+        assert_eq!(world.resource::<Tracker>().order, expected);
+    }
+
+    #[test]
+    fn finalize_unregistered_type() {
+        struct Unregistered;
+        impl Asset for Unregistered {}
+
+        let mut world = create_world();
+        world.add_system(Root, finalize_assets);
+        world.observe::<AssetError, _>(|errors: &[AssetError], tracker: &mut Tracker| {
+            tracker.errors.extend(errors.iter().map(|e| e.to_string()));
+        });
+        world.build();
+
+        let id = AssetId::gen();
+        let meta = ArtifactMeta::new::<Unregistered>(id, 0, HashSet::new());
+        let asset = LoadedAsset::new(Unregistered, meta);
+        let pending = PendingFinalize::new(asset, None, LoadPriority::Normal);
+        world.resource::<AssetDatabase>().finalizing().push(pending);
+        world.run(Root);
+
+        assert!(!world.resource::<AssetDatabase>().is_finalizing(&id));
+        assert_eq!(world.resource::<Tracker>().errors.len(), 1);
+    }
+
+    #[test]
+    fn finalize_on_main() {
+        let mut world = create_world_with(|config| config.set_finalize_budget(Duration::ZERO));
+        world.add_system(Root, finalize_assets);
+        world
+            .resource_mut::<Assets<Mesh>>()
+            .set_fallback(Some(Mesh { uploaded: false }));
+        world.build();
+
+        let paths = ["a.mesh", "b.mesh", "c.mesh"];
+        write_assets(&world, &paths.map(|path| (path, "")));
+        world.events().add(ImportFolder::new(""));
+        world.events().add(LoadAssets::hard(paths));
+        world.run(Root);
+
+        let ids = paths.map(|path| asset_id(&world, path));
+        {
+            let database = world.resource::<AssetDatabase>();
+            let meshes = world.resource::<Assets<Mesh>>();
+            for id in &ids {
+                assert!(database.is_finalizing(id));
+                assert!(!database.states().is_loaded(id));
+                assert_eq!(meshes.get_or_fallback(id), Some(&Mesh { uploaded: false }));
+            }
+        }
+
+        for finalized in 1..=ids.len() {
+            world.run(Root);
+
+            let database = world.resource::<AssetDatabase>();
+            let meshes = world.resource::<Assets<Mesh>>();
+            let pending = ids.iter().filter(|id| database.is_finalizing(id)).count();
+            assert_eq!((meshes.len(), pending), (finalized, ids.len() - finalized));
+            assert!(meshes.assets().iter().all(|mesh| mesh.uploaded));
+        }
+
+        let database = world.resource::<AssetDatabase>();
+        assert!(ids.iter().all(|id| database.states().is_loaded(id)));
+    }
+
+    #[test]
+    fn import_stats() {
+        let mut world = create_world_with(|config| {
+            config.validation_mut().add("chars", StatCheck::Max(5.0));
+        });
+        world.observe::<AssetImported, _>(|imports: &[AssetImported], tracker: &mut Tracker| {
+            let violations = imports.iter().flat_map(|import| import.violations());
+            tracker.violations.extend(violations.map(|v| v.to_string()));
+        });
+        world.build();
+
+        write_assets(&world, &[("short.txt", "Hi")]);
+        world.events().add(ImportFolder::new(""));
+        world.run(Root);
+
+        let config = world.resource::<AssetDatabase>().config();
+        let id = asset_id(&world, "test.txt");
+        let meta = config.load_artifact_meta(id).unwrap();
+        assert_eq!(meta.stats().get("chars"), Some(&StatValue::Int(13)));
+
+        let violations = &world.resource::<Tracker>().violations;
+        assert_eq!(violations, &["chars is 13, max 5"]);
+    }
+
+    #[test]
+    fn strict_validation() {
+        let mut world = create_world_with(|config| {
+            config
+                .validation_mut()
+                .set_strict(true)
+                .add("chars", StatCheck::Max(5.0));
+        });
+        world.observe::<AssetError, _>(|errors: &[AssetError], tracker: &mut Tracker| {
+            tracker.errors.extend(errors.iter().map(|e| e.to_string()));
+        });
+        world.build();
+
+        write_assets(&world, &[("short.txt", "Hi")]);
+        world.events().add(ImportFolder::new(""));
+        world.run(Root);
+
+        let database = world.resource::<AssetDatabase>();
+        let library = database.library();
+        assert!(library.id("short.txt").is_some());
+        assert!(library.id("test.txt").is_none());
+
+        let errors = &world.resource::<Tracker>().errors;
+        assert!(errors.iter().any(|error| error.contains("max 5")));
+    }
+
+    #[test]
+    fn discard_assets_incrementally() {
+        let mut world = create_world_with(|config| config.set_discard_budget(1));
+        world.add_system(Root, discard_assets::<PlainText>);
+        world
+            .resource_mut::<Assets<PlainText>>()
+            .set_retention(RetentionPolicy::DiscardAfterFrames(2));
+        world.build();
+
+        write_assets(&world, &[("other.txt", "Other")]);
+        world.events().add(ImportFolder::new(""));
+        world.run(Root);
+        let paths = ["test.txt", "other.txt"];
+        world.events().add(LoadAssets::hard(paths));
+        world.run(Root);
+
+        let mut counts = vec![];
+        for _ in 0..4 {
+            world.run(Root);
+            counts.push(world.resource::<Assets<PlainText>>().len());
+        }
+        assert_eq!(counts, [2, 1, 0, 0]);
+
+        let id = asset_id(&world, "test.txt");
+        assert!(!world.resource::<AssetDatabase>().states().is_loaded(&id));
+
+        world.events().add(LoadAssets::hard(["test.txt"]));
+        world.run(Root);
+
+        let text = world.resource::<Assets<PlainText>>().get(&id);
+        assert_eq!(text.map(|text| text.0.as_str()), Some("Hello, world!"));
+    }
+
+    #[test]
+    fn keep_assets() {
+        let mut world = create_world();
+        world.add_system(Root, discard_assets::<PlainText>);
+        world.build();
+
+        world.events().add(ImportFolder::new(""));
+        world.events().add(LoadAssets::hard(["test.txt"]));
+        for _ in 0..4 {
+            world.run(Root);
+        }
+
+        assert_eq!(world.resource::<Assets<PlainText>>().len(), 1);
+    }
+
+    /// Loads `test.txt` with `policy` and returns its id.
+    fn load_with_retention(world: &mut World, policy: RetentionPolicy) -> AssetId {
+        world.add_system(Root, discard_assets::<PlainText>);
+        world
+            .resource_mut::<Assets<PlainText>>()
+            .set_retention(policy);
+        world.build();
+
+        world.events().add(ImportFolder::new(""));
+        world.events().add(LoadAssets::hard(["test.txt"]));
+        world.run(Root);
+        asset_id(world, "test.txt")
+    }
+
+    fn is_loaded(world: &World, id: &AssetId) -> bool {
+        let loaded = world.resource::<AssetDatabase>().states().is_loaded(id);
+        assert_eq!(loaded, world.resource::<Assets<PlainText>>().contains(id));
+        loaded
+    }
+
+    #[test]
+    fn discard_after_extract() {
+        let mut world = create_world();
+        let id = load_with_retention(&mut world, RetentionPolicy::DiscardAfterExtract);
+        world.run(Root);
+        assert!(is_loaded(&world, &id));
+
+        world.resource::<Assets<PlainText>>().mark_extracted(&id);
+        world.run(Root);
+        assert!(!is_loaded(&world, &id));
+    }
+
+    #[test]
+    fn discard_when_unreferenced() {
+        let mut world = create_world();
+        let id = load_with_retention(&mut world, RetentionPolicy::DiscardWhenUnreferenced);
+        world.resource_mut::<Assets<PlainText>>().retain(&id);
+        world.run(Root);
+        world.run(Root);
+        assert!(is_loaded(&world, &id));
+
+        world.resource_mut::<Assets<PlainText>>().release(&id);
+        world.run(Root);
+        assert!(!is_loaded(&world, &id));
+    }
+
+    #[test]
+    fn read_assets_are_kept() {
+        let mut world = create_world();
+        let id = load_with_retention(&mut world, RetentionPolicy::DiscardAfterFrames(2));
+        for _ in 0..4 {
+            assert!(world.resource::<Assets<PlainText>>().get(&id).is_some());
+            world.run(Root);
+        }
+
+        assert!(is_loaded(&world, &id));
+        world.run(Root);
+        world.run(Root);
+        assert!(!is_loaded(&world, &id));
+    }
+
+    #[test]
+    fn discarded_assets_reload_when_read() {
+        let mut world = create_world();
+        let id = load_with_retention(&mut world, RetentionPolicy::DiscardAfterFrames(1));
+        world.run(Root);
+        world.run(Root);
+        assert!(!is_loaded(&world, &id));
+
+        assert!(world.resource::<Assets<PlainText>>().get(&id).is_none());
+        world.run(Root);
+
+        let text = world.resource::<Assets<PlainText>>().get(&id);
+        assert_eq!(text.map(|text| text.0.as_str()), Some("Hello, world!"));
+    }
 }
@@ -0,0 +1,104 @@
+use super::AssetDatabase;
+use crate::{
+    asset::AssetId,
+    loader::{AssetError, LoadErrorKind, LoadPriority, LoadedAsset},
+};
+use shadow_ecs::world::{event::Events, World};
+use std::{collections::VecDeque, time::Instant};
+
+pub struct PendingFinalize {
+    asset: LoadedAsset,
+    collection: Option<String>,
+    priority: LoadPriority,
+}
+
+impl PendingFinalize {
+    pub fn new(asset: LoadedAsset, collection: Option<String>, priority: LoadPriority) -> Self {
+        Self {
+            asset,
+            collection,
+            priority,
+        }
+    }
+
+    pub fn id(&self) -> AssetId {
+        self.asset.meta().id()
+    }
+
+    pub fn priority(&self) -> LoadPriority {
+        self.priority
+    }
+}
+
+/// Loaded assets waiting for their loader's `finalize` to run on the main thread.
+pub struct FinalizeQueue {
+    pending: VecDeque<PendingFinalize>,
+}
+
+impl FinalizeQueue {
+    pub fn new() -> Self {
+        Self {
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn contains(&self, id: &AssetId) -> bool {
+        self.pending.iter().any(|pending| pending.id() == *id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Queues `pending` after every asset of the same or higher priority.
+    pub fn push(&mut self, pending: PendingFinalize) {
+        let index = self
+            .pending
+            .iter()
+            .position(|queued| queued.priority < pending.priority)
+            .unwrap_or(self.pending.len());
+
+        self.pending.insert(index, pending);
+    }
+
+    pub fn pop(&mut self) -> Option<PendingFinalize> {
+        self.pending.pop_front()
+    }
+}
+
+impl Default for FinalizeQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Finalizes queued assets until `AssetConfig::finalize_budget` is spent, so a burst of
+/// completed loads is spread across frames. At least one asset is finalized per run.
+pub fn finalize_assets(world: &World, database: &AssetDatabase, events: &Events) {
+    let budget = database.config().finalize_budget();
+    let started = Instant::now();
+
+    loop {
+        let pending = database.finalizing().pop();
+        let mut pending = match pending {
+            Some(pending) => pending,
+            None => break,
+        };
+
+        match database.registry().get_metadata(pending.asset.meta().ty()) {
+            Some(metadata) => {
+                metadata.finalize(&mut pending.asset, world);
+                events.add(metadata.loaded(pending.asset, pending.collection));
+            }
+            None => events.add(AssetError::load(pending.id(), LoadErrorKind::NoLoader)),
+        }
+
+        if started.elapsed() >= budget {
+            break;
+        }
+    }
+}
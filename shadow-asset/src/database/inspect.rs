@@ -0,0 +1,276 @@
+use super::{library::DependentLibrary, AssetConfig, AssetDatabase};
+use crate::{
+    asset::{AssetId, AssetPath},
+    io::{
+        path::{PathError, SourcePath},
+        PathExt,
+    },
+    loader::{AssetError, AssetErrorKind},
+};
+use std::{fmt::Display, path::Path};
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct LoaderReport {
+    pub asset: &'static str,
+    pub loader: &'static str,
+    pub extensions: Vec<&'static str>,
+    pub version: u32,
+}
+
+impl Display for LoaderReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}] loader {} v{}",
+            self.asset,
+            self.extensions.join(", "),
+            self.loader,
+            self.version
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ArtifactReport {
+    pub size: usize,
+    pub checksum: u32,
+    pub version: u32,
+    /// Whether the checksum matches the source and its meta file. `None` if the
+    /// source can't be read.
+    pub current: Option<bool>,
+}
+
+/// What the database knows about a source path.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct PathReport {
+    pub path: SourcePath,
+    pub extension: Option<String>,
+    /// Asset type claiming the extension.
+    pub asset: Option<&'static str>,
+    pub loader: Option<&'static str>,
+    /// Contents of the meta file next to the source, if any.
+    pub meta: Option<String>,
+    pub id: Option<AssetId>,
+    pub artifact: Option<ArtifactReport>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum LoadState {
+    Unloaded,
+    Finalizing,
+    Loaded,
+}
+
+/// What the database knows about an asset id.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct AssetReport {
+    pub id: AssetId,
+    pub path: Option<SourcePath>,
+    pub asset: Option<&'static str>,
+    pub state: LoadState,
+    pub dependencies: Vec<AssetId>,
+    pub dependents: Vec<AssetId>,
+    pub artifact: Option<ArtifactReport>,
+    pub error: Option<String>,
+}
+
+impl AssetReport {
+    /// True if the artifact exists but no source in the library points to it.
+    pub fn is_orphaned(&self) -> bool {
+        self.path.is_none() && self.artifact.is_some()
+    }
+}
+
+fn write_option<T: Display>(
+    f: &mut std::fmt::Formatter<'_>,
+    name: &str,
+    value: &Option<T>,
+) -> std::fmt::Result {
+    match value {
+        Some(value) => writeln!(f, "  {}: {}", name, value),
+        None => writeln!(f, "  {}: none", name),
+    }
+}
+
+impl Display for ArtifactReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let current = match self.current {
+            Some(true) => "current",
+            Some(false) => "stale",
+            None => "unknown source",
+        };
+
+        write!(
+            f,
+            "{} bytes, checksum {:08x}, v{}, {}",
+            self.size, self.checksum, self.version, current
+        )
+    }
+}
+
+impl Display for PathReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.path)?;
+        write_option(f, "extension", &self.extension)?;
+        write_option(f, "asset", &self.asset)?;
+        write_option(f, "loader", &self.loader)?;
+        write_option(f, "id", &self.id.map(|id| *id))?;
+        write_option(f, "artifact", &self.artifact)?;
+        write_option(f, "error", &self.error)?;
+        match &self.meta {
+            Some(meta) => write!(f, "  meta:\n{}", meta),
+            None => writeln!(f, "  meta: none"),
+        }
+    }
+}
+
+impl Display for AssetReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ids = |ids: &[AssetId]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        writeln!(f, "{}", *self.id)?;
+        write_option(f, "path", &self.path)?;
+        write_option(f, "asset", &self.asset)?;
+        writeln!(f, "  state: {:?}", self.state)?;
+        writeln!(
+            f,
+            "  dependencies: [{}]",
+            ids(&self.dependencies).join(", ")
+        )?;
+        writeln!(f, "  dependents: [{}]", ids(&self.dependents).join(", "))?;
+        write_option(f, "artifact", &self.artifact)?;
+        write_option(f, "error", &self.error)
+    }
+}
+
+impl AssetDatabase {
+    pub fn list_loaders(&self) -> Vec<LoaderReport> {
+        self.registry().list_loaders()
+    }
+
+    pub fn describe_path(&self, path: impl AsRef<Path>) -> Result<PathReport, PathError> {
+        let path = SourcePath::new(path)?;
+        let config = self.config();
+        let extension = path.as_path().ext().map(str::to_string);
+        let metadata = extension
+            .as_deref()
+            .and_then(|ext| self.registry().get_metadata_by_ext(ext));
+        let id = self.library().id(&path).copied();
+
+        Ok(PathReport {
+            extension,
+            asset: metadata.map(|metadata| metadata.name()),
+            loader: metadata.and_then(|metadata| metadata.loader()),
+            meta: read(config, config.asset(&path).append_ext("meta"))
+                .and_then(|meta| String::from_utf8(meta).ok()),
+            id,
+            artifact: id.and_then(|id| self.describe_artifact(id, Some(&path))),
+            error: self.states().error(&path).map(str::to_string),
+            path,
+        })
+    }
+
+    pub fn describe_asset(&self, id: AssetId) -> AssetReport {
+        let config = self.config();
+        let path = self.library().path(&id).cloned();
+        let meta = config.load_artifact_meta(id).ok();
+
+        let states = self.states();
+        let state = match states.is_loaded(&id) {
+            true => LoadState::Loaded,
+            false if self.is_finalizing(&id) => LoadState::Finalizing,
+            false => LoadState::Unloaded,
+        };
+
+        let ty = states.get(&id).map(|state| state.ty());
+        let ty = ty.or(meta.as_ref().map(|meta| meta.ty()));
+        let asset = ty
+            .and_then(|ty| self.registry().get_metadata(ty))
+            .map(|metadata| metadata.name());
+
+        let mut dependencies: Vec<AssetId> = match (states.get(&id), &meta) {
+            (Some(state), _) => state.dependencies().iter().copied().collect(),
+            (None, Some(meta)) => meta.dependencies().iter().copied().collect(),
+            (None, None) => vec![],
+        };
+        dependencies.sort();
+
+        let mut dependents = states.dependents(&id);
+        if let Ok(library) = DependentLibrary::load(config) {
+            dependents.extend(library.get(&id).into_iter().flat_map(|ids| ids.iter()));
+        }
+        let mut dependents = dependents.into_iter().collect::<Vec<_>>();
+        dependents.sort();
+
+        AssetReport {
+            id,
+            asset,
+            state,
+            dependencies,
+            dependents,
+            artifact: self.describe_artifact(id, path.as_ref()),
+            error: path
+                .as_ref()
+                .and_then(|path| states.error(path))
+                .map(str::to_string),
+            path,
+        }
+    }
+
+    /// Keeps the error as the last error of its source path.
+    pub(crate) fn record_error(&self, error: &AssetError) {
+        let path = match error.kind() {
+            AssetErrorKind::Import(path) => {
+                let config = self.config();
+                let assets = config.root().join(config.assets());
+                let path = path.without_prefix(assets);
+                let path = path.without_prefix(config.assets());
+                SourcePath::new(path).ok()
+            }
+            AssetErrorKind::Load(path) => match path {
+                AssetPath::Id(id) => self.library().path(id).cloned(),
+                AssetPath::Path(path) => SourcePath::new(path).ok(),
+            },
+        };
+
+        if let Some(path) = path {
+            let message = error.error().to_string();
+            self.states_mut().set_error(path, message);
+        }
+    }
+
+    fn describe_artifact(
+        &self,
+        id: AssetId,
+        source: Option<&SourcePath>,
+    ) -> Option<ArtifactReport> {
+        let config = self.config();
+        let size = read(config, config.artifact(id))?.len();
+        let meta = config.load_artifact_meta(id).ok()?;
+
+        let current = source.and_then(|source| {
+            let path = config.asset(source);
+            let asset = read(config, &path)?;
+            let settings = read(config, path.append_ext("meta")).unwrap_or_default();
+            Some(config.checksum(&asset, &settings) == meta.checksum())
+        });
+
+        Some(ArtifactReport {
+            size,
+            checksum: meta.checksum(),
+            version: meta.version(),
+            current,
+        })
+    }
+}
+
+fn read(config: &AssetConfig, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+    if !config.filesystem().exists(path.as_ref()) {
+        return None;
+    }
+
+    let mut reader = config.reader(path);
+    reader.read_to_end().ok()?;
+    reader.flush().ok()
+}
@@ -1,17 +1,17 @@
 use crate::{
     asset::{AssetId, AssetKind},
     bytes::IntoBytes,
-    io::{AssetIoError, AssetWriter},
+    io::{path::SourcePath, AssetIoError, AssetWriter},
 };
 use shadow_ecs::core::{DenseMap, DenseSet};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use super::AssetConfig;
 
 #[derive(Default, Debug)]
 pub struct AssetLibrary {
-    ids: DenseMap<AssetId, PathBuf>,
-    paths: DenseMap<PathBuf, AssetId>,
+    ids: DenseMap<AssetId, SourcePath>,
+    paths: DenseMap<SourcePath, AssetId>,
 }
 
 impl AssetLibrary {
@@ -22,15 +22,21 @@ impl AssetLibrary {
         }
     }
 
-    pub fn id(&self, path: &PathBuf) -> Option<&AssetId> {
-        self.paths.get(path)
+    /// Looks up a path with either separator. Invalid paths have no id.
+    pub fn id(&self, path: impl AsRef<Path>) -> Option<&AssetId> {
+        self.paths.get(&SourcePath::new(path).ok()?)
     }
 
-    pub fn path(&self, id: &AssetId) -> Option<&PathBuf> {
+    pub fn path(&self, id: &AssetId) -> Option<&SourcePath> {
         self.ids.get(id)
     }
 
-    pub fn add_asset(&mut self, id: AssetId, path: PathBuf, kind: AssetKind) -> Option<PathBuf> {
+    pub fn add_asset(
+        &mut self,
+        id: AssetId,
+        path: SourcePath,
+        kind: AssetKind,
+    ) -> Option<SourcePath> {
         let old = self.ids.insert(id, path.clone()).map(|old_path| {
             self.paths.remove(&old_path);
             old_path
@@ -41,7 +47,7 @@ impl AssetLibrary {
         old
     }
 
-    pub fn remove_asset(&mut self, id: &AssetId, kind: AssetKind) -> Option<PathBuf> {
+    pub fn remove_asset(&mut self, id: &AssetId, kind: AssetKind) -> Option<SourcePath> {
         let path = self.ids.remove(id)?;
         if kind == AssetKind::Main {
             self.paths.remove(&path);
@@ -49,8 +55,8 @@ impl AssetLibrary {
         Some(path)
     }
 
-    pub fn remove_path(&mut self, path: &PathBuf) -> Option<AssetId> {
-        let id = self.paths.remove(path)?;
+    pub fn remove_path(&mut self, path: impl AsRef<Path>) -> Option<AssetId> {
+        let id = self.paths.remove(&SourcePath::new(path).ok()?)?;
         self.ids.remove(&id);
         Some(id)
     }
@@ -59,8 +65,8 @@ impl AssetLibrary {
         self.ids.contains(id)
     }
 
-    pub fn contains_path(&self, path: &PathBuf) -> bool {
-        self.paths.contains(path)
+    pub fn contains_path(&self, path: impl AsRef<Path>) -> bool {
+        self.id(path).is_some()
     }
 
     pub fn save(&self, mut writer: impl AssetWriter) -> Result<Vec<u8>, AssetIoError> {
@@ -73,11 +79,12 @@ impl AssetLibrary {
         writer.flush()
     }
 
+    /// Paths saved with platform separators are normalized as they load.
     pub fn load(&mut self, bytes: &[u8]) -> Option<&Self> {
-        let ids_len = usize::from_bytes(&bytes)?;
+        let ids_len = usize::from_bytes(bytes.get(..8)?)?;
 
-        self.ids = DenseMap::from_bytes(&bytes[8..8 + ids_len])?;
-        self.paths = DenseMap::from_bytes(&bytes[16 + ids_len..])?;
+        self.ids = DenseMap::from_bytes(bytes.get(8..8 + ids_len)?)?;
+        self.paths = DenseMap::from_bytes(&bytes[8 + ids_len..])?;
 
         Some(self)
     }
@@ -214,3 +221,96 @@ impl DependentLibrary {
         config.temp().join("dependents.lib")
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Quarantined {
+    checksum: u32,
+    panics: u32,
+}
+
+impl IntoBytes for Quarantined {
+    fn into_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.checksum.into_bytes();
+        bytes.extend(self.panics.into_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let checksum = u32::from_bytes(bytes.get(..4)?)?;
+        let panics = u32::from_bytes(bytes.get(4..8)?)?;
+        Some(Self { checksum, panics })
+    }
+}
+
+/// Source files whose loader panicked, keyed by path with the checksum of the
+/// source at the time and how many times it panicked. Once a source reaches
+/// `AssetConfig::quarantine_threshold` panics it is skipped until it changes.
+#[derive(Default)]
+pub struct QuarantineLibrary {
+    paths: DenseMap<PathBuf, Quarantined>,
+}
+
+impl QuarantineLibrary {
+    pub fn new() -> Self {
+        Self {
+            paths: DenseMap::new(),
+        }
+    }
+
+    pub fn contains(&self, path: &PathBuf) -> bool {
+        self.paths.contains(path)
+    }
+
+    /// Times the source panicked while it had this checksum.
+    pub fn panics(&self, path: &PathBuf, checksum: u32) -> u32 {
+        match self.paths.get(path) {
+            Some(entry) if entry.checksum == checksum => entry.panics,
+            _ => 0,
+        }
+    }
+
+    pub fn is_quarantined(&self, path: &PathBuf, checksum: u32, threshold: u32) -> bool {
+        self.panics(path, checksum) >= threshold
+    }
+
+    /// Counts a panic of the source, restarting the count if it changed. Returns the count.
+    pub fn add(&mut self, path: PathBuf, checksum: u32) -> u32 {
+        let panics = self.panics(&path, checksum) + 1;
+        self.paths.insert(path, Quarantined { checksum, panics });
+        panics
+    }
+
+    pub fn remove(&mut self, path: &PathBuf) -> bool {
+        self.paths.remove(path).is_some()
+    }
+
+    pub fn checksum(config: &AssetConfig, path: &Path) -> Option<u32> {
+        let mut reader = config.reader(config.asset(path));
+        reader.read_to_end().ok()?;
+        Some(config.checksum(reader.bytes(), &[]))
+    }
+
+    pub fn save(&self, config: &AssetConfig) -> Result<Vec<u8>, AssetIoError> {
+        let mut writer = config.writer(Self::path(config));
+        writer.write(&self.paths.into_bytes())?;
+        writer.flush()
+    }
+
+    pub fn load(config: &AssetConfig) -> Result<Self, AssetIoError> {
+        let path = Self::path(config);
+        if !config.filesystem().exists(&path) {
+            return Ok(Self::new());
+        }
+
+        let mut reader = config.reader(path);
+        reader.read_to_end()?;
+        let paths = DenseMap::from_bytes(reader.bytes())
+            .ok_or(AssetIoError::from(std::io::ErrorKind::InvalidData))?;
+
+        Ok(Self { paths })
+    }
+
+    pub fn path(config: &AssetConfig) -> PathBuf {
+        config.temp().join("quarantine.lib")
+    }
+}
@@ -1,13 +1,16 @@
 use crate::{
     artifact::{Artifact, ArtifactHeader, ArtifactMeta},
-    asset::{Asset, AssetId, AssetSettings, Settings},
+    asset::{Asset, AssetId, AssetPath, AssetSettings, Settings},
     bytes::IntoBytes,
     io::{
-        local::LocalFileSystem, AssetFileSystem, AssetIoError, AssetReader, AssetWriter, PathExt,
+        local::LocalFileSystem, path::SourcePath, AssetFileSystem, AssetIoError, AssetReader,
+        AssetWriter, PathExt,
     },
-    loader::{AssetSerializer, AssetLoader, AssetProcessor},
+    loader::{AssetSerializer, AssetLoader, AssetProcessor, LoadPriority},
+    validation::ValidationRules,
 };
 use events::{AssetEvent, AssetEvents};
+use finalize::FinalizeQueue;
 use library::AssetLibrary;
 use registry::AssetRegistry;
 use shadow_ecs::{core::Resource, system::RunMode};
@@ -15,11 +18,15 @@ use state::AssetStates;
 use std::{
     path::{Path, PathBuf},
     sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::Duration,
 };
 
 pub mod events;
+pub mod finalize;
+pub mod inspect;
 pub mod library;
 pub mod registry;
+pub mod retention;
 pub mod state;
 
 #[derive(Clone)]
@@ -28,6 +35,7 @@ pub struct AssetDatabase {
     library: Arc<RwLock<AssetLibrary>>,
     states: Arc<RwLock<AssetStates>>,
     events: Arc<Mutex<AssetEvents>>,
+    finalizing: Arc<Mutex<FinalizeQueue>>,
 }
 
 impl AssetDatabase {
@@ -36,6 +44,7 @@ impl AssetDatabase {
             library: Arc::new(RwLock::new(AssetLibrary::new())),
             states: Arc::new(RwLock::new(AssetStates::new())),
             events: Arc::new(Mutex::new(AssetEvents::new())),
+            finalizing: Arc::new(Mutex::new(FinalizeQueue::new())),
             config: Arc::new(config),
         }
     }
@@ -60,6 +69,29 @@ impl AssetDatabase {
         self.events.lock().unwrap().push(event);
     }
 
+    /// True if the asset is loaded but still waiting for its loader's `finalize`.
+    pub fn is_finalizing(&self, id: &AssetId) -> bool {
+        self.finalizing().contains(id)
+    }
+
+    /// The priority of the asset's type, found through its extension.
+    pub fn load_priority(&self, path: &AssetPath) -> LoadPriority {
+        let library = self.library();
+        let path = match path {
+            AssetPath::Id(id) => library.path(id).map(SourcePath::as_path),
+            AssetPath::Path(path) => Some(path.as_path()),
+        };
+
+        path.and_then(|path| path.extension())
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.registry().get_metadata_by_ext(ext))
+            .map_or(LoadPriority::Normal, |metadata| metadata.priority())
+    }
+
+    pub(crate) fn finalizing(&self) -> MutexGuard<'_, FinalizeQueue> {
+        self.finalizing.lock().unwrap()
+    }
+
     pub(crate) fn library_mut(&self) -> RwLockWriteGuard<AssetLibrary> {
         self.library.write().unwrap()
     }
@@ -72,8 +104,17 @@ impl AssetDatabase {
         self.events.lock().unwrap()
     }
 
+    /// Pops the next event, or marks the executor as stopped if there is none.
+    /// Both happen under one lock so an event pushed concurrently always either
+    /// gets popped here or finds the executor stopped and starts a new one.
     pub(crate) fn pop_event(&self) -> Option<Box<dyn AssetEvent>> {
-        self.events().pop()
+        let mut events = self.events();
+        let event = events.pop();
+        if event.is_none() {
+            events.stop();
+        }
+
+        event
     }
 }
 
@@ -84,12 +125,20 @@ pub struct AssetConfig {
     cache: PathBuf,
     temp: PathBuf,
     import_batch_size: usize,
+    finalize_budget: Duration,
+    discard_budget: usize,
+    quarantine_threshold: u32,
+    validation: ValidationRules,
     registry: AssetRegistry,
     filesystem: Box<dyn AssetFileSystem>,
     mode: RunMode,
 }
 
 impl AssetConfig {
+    pub const FINALIZE_BUDGET: Duration = Duration::from_millis(4);
+    pub const DISCARD_BUDGET: usize = 64;
+    pub const QUARANTINE_THRESHOLD: u32 = 3;
+
     pub fn new<Fs: AssetFileSystem>(filesystem: Fs) -> Self {
         let assets = PathBuf::from("assets");
         let cache = PathBuf::from(".cache");
@@ -100,6 +149,10 @@ impl AssetConfig {
             cache,
             temp,
             import_batch_size: 250,
+            finalize_budget: Self::FINALIZE_BUDGET,
+            discard_budget: Self::DISCARD_BUDGET,
+            quarantine_threshold: Self::QUARANTINE_THRESHOLD,
+            validation: ValidationRules::new(),
             registry: AssetRegistry::new(),
             filesystem: Box::new(filesystem),
             mode: RunMode::Parallel,
@@ -134,6 +187,26 @@ impl AssetConfig {
         self.import_batch_size
     }
 
+    pub fn finalize_budget(&self) -> Duration {
+        self.finalize_budget
+    }
+
+    pub fn discard_budget(&self) -> usize {
+        self.discard_budget
+    }
+
+    pub fn quarantine_threshold(&self) -> u32 {
+        self.quarantine_threshold
+    }
+
+    pub fn validation(&self) -> &ValidationRules {
+        &self.validation
+    }
+
+    pub fn validation_mut(&mut self) -> &mut ValidationRules {
+        &mut self.validation
+    }
+
     pub fn registry(&self) -> &AssetRegistry {
         &self.registry
     }
@@ -153,6 +226,25 @@ impl AssetConfig {
         self.mode = mode;
     }
 
+    /// Main thread time per frame spent finalizing loaded assets.
+    pub fn set_finalize_budget(&mut self, budget: Duration) {
+        self.finalize_budget = budget;
+    }
+
+    /// Max assets per type discarded each frame by their retention policy.
+    pub fn set_discard_budget(&mut self, budget: usize) {
+        self.discard_budget = budget.max(1);
+    }
+
+    /// Times an unchanged source may panic its loader before it is quarantined.
+    pub fn set_quarantine_threshold(&mut self, threshold: u32) {
+        self.quarantine_threshold = threshold.max(1);
+    }
+
+    pub fn set_priority<A: Asset>(&mut self, priority: LoadPriority) {
+        self.registry.set_priority::<A>(priority);
+    }
+
     pub fn register<A: Asset>(&mut self) {
         self.registry.register::<A>();
     }
@@ -256,10 +348,11 @@ impl AssetConfig {
 
         reader.read(header.meta())?;
 
-        let meta_bytes =
-            &reader.bytes()[ArtifactHeader::SIZE..ArtifactHeader::SIZE + header.meta()];
-
-        ArtifactMeta::from_bytes(meta_bytes)
+        header
+            .meta()
+            .checked_add(ArtifactHeader::SIZE)
+            .and_then(|end| reader.bytes().get(ArtifactHeader::SIZE..end))
+            .and_then(ArtifactMeta::from_bytes)
             .ok_or(AssetIoError::from(std::io::ErrorKind::InvalidData))
     }
 
@@ -284,6 +377,10 @@ impl Default for AssetConfig {
             cache: PathBuf::from(".cache"),
             temp: PathBuf::from(".temp"),
             import_batch_size: 250,
+            finalize_budget: Self::FINALIZE_BUDGET,
+            discard_budget: Self::DISCARD_BUDGET,
+            quarantine_threshold: Self::QUARANTINE_THRESHOLD,
+            validation: ValidationRules::new(),
             registry: AssetRegistry::new(),
             filesystem: Box::new(LocalFileSystem::new("Project")),
             mode: RunMode::Parallel,
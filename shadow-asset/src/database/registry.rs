@@ -2,17 +2,19 @@ use std::{collections::HashSet, path::Path};
 
 use super::{
     events::{AssetLoaded, AssetUnloaded},
+    inspect::LoaderReport,
     state::AssetState,
     AssetConfig,
 };
 use crate::{
     artifact::{Artifact, ArtifactMeta},
-    asset::{Asset, AssetId, AssetSettings, AssetType, Assets, Settings},
+    asset::{Asset, AssetCollections, AssetId, AssetSettings, AssetType, Assets, Settings},
     io::AssetIoError,
     loader::{
         AssetError, AssetLoader, AssetProcessor, AssetSerializer, LoadContext, LoadErrorKind,
-        LoadedAsset, LoadedAssets, LoadedMetadata,
+        LoadPriority, LoadedAsset, LoadedAssets, LoadedMetadata,
     },
+    validation::{ValidationError, Violation},
 };
 use shadow_ecs::{
     core::{internal::blob::BlobCell, DenseMap},
@@ -20,8 +22,11 @@ use shadow_ecs::{
 };
 
 pub struct AssetMetadata {
-    loaded: fn(LoadedAsset) -> ErasedEvent,
-    unloaded: fn(AssetId, AssetState, &World) -> Option<ErasedEvent>,
+    name: &'static str,
+    loader: Option<&'static str>,
+    extensions: &'static [&'static str],
+    loaded: fn(LoadedAsset, Option<String>) -> ErasedEvent,
+    unloaded: fn(AssetId, Option<&str>, AssetState, &World) -> Option<ErasedEvent>,
     import: fn(
         &Self,
         &Path,
@@ -40,22 +45,39 @@ pub struct AssetMetadata {
     process: Option<fn(&mut ImportedAsset, &LoadedAssets) -> Result<(), AssetError>>,
     serialize: fn(&Path, &ImportedAsset, &AssetConfig) -> Result<Vec<u8>, AssetError>,
     load_metadata: Option<fn(&Path, &AssetConfig) -> Result<LoadedMetadata, AssetError>>,
+    finalize: Option<fn(&mut LoadedAsset, &World)>,
+    priority: LoadPriority,
+    version: u32,
 }
 
 impl AssetMetadata {
     pub fn new<A: Asset>() -> Self {
         Self {
-            loaded: |loaded: LoadedAsset| {
+            name: std::any::type_name::<A>(),
+            loader: None,
+            extensions: &[],
+            loaded: |loaded: LoadedAsset, collection| {
                 let id = loaded.meta.id();
                 let dependencies = loaded.meta.dependencies;
                 let asset = loaded.asset.take::<A>();
-                ErasedEvent::new(AssetLoaded::new(id, asset, dependencies))
+                let loaded = AssetLoaded::new(id, asset, dependencies).with_collection(collection);
+                ErasedEvent::new(loaded)
             },
-            unloaded: |id, state, world| {
-                let assets = world.resource_mut::<Assets<A>>();
-                let asset = assets.remove(&id)?;
+            unloaded: |id, collection, state, world| {
+                let asset = match collection {
+                    Some(name) => world
+                        .resource_mut::<AssetCollections<A>>()
+                        .collection_mut(name)?
+                        .remove(&id)?,
+                    None => world.resource_mut::<Assets<A>>().remove(&id)?,
+                };
 
-                Some(AssetUnloaded::new(id, asset, state).into())
+                let collection = collection.map(str::to_string);
+                Some(
+                    AssetUnloaded::new(id, asset, state)
+                        .with_collection(collection)
+                        .into(),
+                )
             },
             import: |_self, path, _, _, _| Err(AssetError::import(path, LoadErrorKind::NoLoader)),
             load: |_self, id, _, _, _, _| Err(AssetError::load(id, LoadErrorKind::NoLoader)),
@@ -64,11 +86,15 @@ impl AssetMetadata {
                 Err(AssetError::import(path, LoadErrorKind::NoSerializer))
             },
             load_metadata: None,
+            finalize: None,
+            priority: LoadPriority::Normal,
+            version: 0,
         }
     }
 
     pub fn set_loader<L: AssetLoader>(&mut self) {
         self.import = |_self, path, registry, config, assets| {
+            let source = path;
             let path = config.asset(path);
             let mut reader = config.reader(&path);
             let settings = match config.load_metadata::<L::Settings>(&path) {
@@ -82,7 +108,7 @@ impl AssetMetadata {
 
             let prev_meta = config.load_artifact_meta(settings.id()).ok();
 
-            let (asset, dependencies) = {
+            let (asset, (dependencies, stats)) = {
                 let mut ctx = LoadContext::new(&settings);
                 let asset = match L::load(&mut ctx, reader.as_mut()) {
                     Ok(asset) => asset,
@@ -91,11 +117,20 @@ impl AssetMetadata {
                 (asset, ctx.finish())
             };
 
+            let violations = config.validation().check(source, &stats);
+            if !violations.is_empty() && config.validation().is_strict() {
+                return Err(AssetError::import(&path, ValidationError::new(violations)));
+            }
+
             let checksum = config.checksum(reader.bytes(), settings_data.as_bytes());
 
             let (id, settings) = settings.take();
-            let meta = ArtifactMeta::new::<L::Asset>(id, checksum, dependencies);
-            let mut asset = ImportedAsset::new(asset, settings, meta).with_prev_meta(prev_meta);
+            let meta = ArtifactMeta::new::<L::Asset>(id, checksum, dependencies)
+                .with_version(L::version())
+                .with_stats(stats);
+            let mut asset = ImportedAsset::new(asset, settings, meta)
+                .with_prev_meta(prev_meta)
+                .with_violations(violations);
 
             if let Some(processor) = &_self.process {
                 registry.load_dependencies(asset.dependencies(), config, assets, false);
@@ -109,6 +144,25 @@ impl AssetMetadata {
             Ok(asset)
         };
 
+        self.load_metadata = Some(|path, config| {
+            let settings = config
+                .load_metadata::<L::Settings>(path)
+                .map_err(|e| AssetError::import(path, e))?;
+            let data = toml::to_string(&settings)
+                .map_err(|e| AssetError::import(path, AssetIoError::from(e)))?;
+
+            Ok(LoadedMetadata::new(settings.id(), data))
+        });
+
+        self.finalize = None;
+        if L::finalize_on_main() {
+            self.finalize = Some(|asset, world| L::finalize(asset.cast_mut::<L::Asset>(), world));
+        }
+
+        self.loader = Some(std::any::type_name::<L>());
+        self.extensions = L::extensions();
+        self.version = L::version();
+        self.priority = L::priority();
         self.set_serializer::<L::Serializer>();
     }
 
@@ -146,12 +200,54 @@ impl AssetMetadata {
         self.process = Some(|_, _| todo!());
     }
 
-    pub fn loaded(&self, loaded: LoadedAsset) -> ErasedEvent {
-        (self.loaded)(loaded)
+    /// Type name of the asset.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Type name of the asset's loader, if it has one.
+    pub fn loader(&self) -> Option<&'static str> {
+        self.loader
+    }
+
+    pub fn extensions(&self) -> &'static [&'static str] {
+        self.extensions
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn priority(&self) -> LoadPriority {
+        self.priority
+    }
+
+    pub fn set_priority(&mut self, priority: LoadPriority) {
+        self.priority = priority;
+    }
+
+    pub fn finalizes_on_main(&self) -> bool {
+        self.finalize.is_some()
     }
 
-    pub fn unloaded(&self, id: AssetId, state: AssetState, world: &World) -> Option<ErasedEvent> {
-        (self.unloaded)(id, state, world)
+    pub fn finalize(&self, asset: &mut LoadedAsset, world: &World) {
+        if let Some(finalize) = self.finalize {
+            finalize(asset, world);
+        }
+    }
+
+    pub fn loaded(&self, loaded: LoadedAsset, collection: Option<String>) -> ErasedEvent {
+        (self.loaded)(loaded, collection)
+    }
+
+    pub fn unloaded(
+        &self,
+        id: AssetId,
+        collection: Option<&str>,
+        state: AssetState,
+        world: &World,
+    ) -> Option<ErasedEvent> {
+        (self.unloaded)(id, collection, state, world)
     }
 
     pub fn import(
@@ -253,6 +349,18 @@ impl AssetRegistry {
         metadata.set_processor::<P>();
     }
 
+    /// Overrides the priority set by the asset's loader.
+    pub fn set_priority<A: Asset>(&mut self, priority: LoadPriority) {
+        let asset_type = AssetType::of::<A>();
+        if !self.metadata.contains(&asset_type) {
+            self.register::<A>();
+        }
+
+        if let Some(metadata) = self.metadata.get_mut(&asset_type) {
+            metadata.set_priority(priority);
+        }
+    }
+
     pub fn set_serializer<S: AssetSerializer>(&mut self) {
         let asset_type = AssetType::of::<S::Asset>();
         let metadata = match self.metadata.get_mut(&asset_type) {
@@ -284,6 +392,22 @@ impl AssetRegistry {
         self.ext_map.get(&ext).copied()
     }
 
+    /// Asset types with a loader, in registration order.
+    pub fn list_loaders(&self) -> Vec<LoaderReport> {
+        self.metadata
+            .values()
+            .iter()
+            .filter_map(|metadata| {
+                Some(LoaderReport {
+                    asset: metadata.name(),
+                    loader: metadata.loader()?,
+                    extensions: metadata.extensions().to_vec(),
+                    version: metadata.version(),
+                })
+            })
+            .collect()
+    }
+
     fn load_dependencies<'a>(
         &self,
         dependencies: impl IntoIterator<Item = &'a AssetId>,
@@ -317,6 +441,7 @@ pub struct ImportedAsset {
     settings: BlobCell,
     meta: ArtifactMeta,
     prev_meta: Option<ArtifactMeta>,
+    violations: Vec<Violation>,
 }
 
 impl ImportedAsset {
@@ -326,6 +451,7 @@ impl ImportedAsset {
             settings: BlobCell::new(settings),
             meta,
             prev_meta: None,
+            violations: vec![],
         }
     }
 
@@ -334,6 +460,11 @@ impl ImportedAsset {
         self
     }
 
+    pub fn with_violations(mut self, violations: Vec<Violation>) -> Self {
+        self.violations = violations;
+        self
+    }
+
     pub fn id(&self) -> AssetId {
         self.meta.id()
     }
@@ -362,6 +493,12 @@ impl ImportedAsset {
         self.prev_meta.as_ref()
     }
 
+    /// Stat checks the importer's output failed. Always empty in strict mode, where
+    /// a failed check aborts the import.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
     pub fn dependencies(&self) -> &HashSet<AssetId> {
         self.meta.dependencies()
     }
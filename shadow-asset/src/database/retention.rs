@@ -0,0 +1,67 @@
+use super::{
+    events::{LoadAsset, LoadAssets, UnloadAsset},
+    AssetDatabase,
+};
+use crate::asset::{Asset, AssetCollections, Assets};
+use shadow_ecs::world::event::Events;
+
+/// Applies the retention policy of `Assets<A>`, unloading at most
+/// `AssetConfig::discard_budget` expired assets per frame.
+pub fn discard_assets<A: Asset>(assets: &mut Assets<A>, database: &AssetDatabase, events: &Events) {
+    let budget = database.config().discard_budget();
+    discard(assets, None, budget, database, events);
+}
+
+/// Applies the retention policy of each named collection, sharing one
+/// `AssetConfig::discard_budget` between them.
+pub fn discard_collection_assets<A: Asset>(
+    collections: &mut AssetCollections<A>,
+    database: &AssetDatabase,
+    events: &Events,
+) {
+    let mut budget = database.config().discard_budget();
+    for (name, assets) in collections.iter_mut() {
+        budget -= discard(assets, Some(name), budget, database, events);
+    }
+}
+
+/// Returns how many expired assets were discarded. Assets loaded from an artifact are
+/// unloaded and load again when asked for; others are just removed. Discarded assets
+/// asked for since the last frame are loaded here.
+fn discard<A: Asset>(
+    assets: &mut Assets<A>,
+    collection: Option<&str>,
+    budget: usize,
+    database: &AssetDatabase,
+    events: &Events,
+) -> usize {
+    assets.advance_frame();
+
+    let reloads = assets.take_reloads();
+    if !reloads.is_empty() {
+        let collection = collection.map(str::to_string);
+        let loads = reloads.into_iter().map(LoadAsset::hard);
+        events.add(LoadAssets::new(
+            loads.map(|load| load.with_collection(collection.clone())),
+        ));
+    }
+
+    let mut discarded = 0;
+    while discarded < budget {
+        let Some(id) = assets.pop_expired() else {
+            break;
+        };
+
+        if database.states().is_loaded_in(collection, &id) {
+            let collection = collection.map(str::to_string);
+            assets.discard(&id);
+            events.add(UnloadAsset::new(id).with_collection(collection));
+        } else {
+            assets.remove(&id);
+        }
+
+        discarded += 1;
+    }
+
+    discarded
+}
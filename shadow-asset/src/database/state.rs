@@ -1,4 +1,7 @@
-use crate::asset::{Asset, AssetId, AssetType};
+use crate::{
+    asset::{Asset, AssetId, AssetType},
+    io::path::SourcePath,
+};
 use shadow_ecs::core::DenseMap;
 use std::collections::HashSet;
 
@@ -24,17 +27,36 @@ impl AssetState {
     }
 }
 
+/// Load states of the default `Assets<A>` resources and of each named collection.
+/// The same asset can be loaded in several collections, each with its own state.
 pub struct AssetStates {
     states: DenseMap<AssetId, AssetState>,
+    collections: DenseMap<String, DenseMap<AssetId, AssetState>>,
+    errors: DenseMap<SourcePath, String>,
 }
 
 impl AssetStates {
     pub fn new() -> Self {
         Self {
             states: DenseMap::new(),
+            collections: DenseMap::new(),
+            errors: DenseMap::new(),
         }
     }
 
+    /// The last import or load error of the source, cleared when it imports again.
+    pub fn error(&self, path: &SourcePath) -> Option<&str> {
+        self.errors.get(path).map(String::as_str)
+    }
+
+    pub fn set_error(&mut self, path: SourcePath, error: String) {
+        self.errors.insert(path, error);
+    }
+
+    pub fn clear_error(&mut self, path: &SourcePath) -> Option<String> {
+        self.errors.remove(path)
+    }
+
     pub fn is_loaded(&self, id: &AssetId) -> bool {
         self.states.contains(id)
     }
@@ -60,9 +82,36 @@ impl AssetStates {
     }
 
     pub fn dependents<'a>(&self, id: &AssetId) -> HashSet<AssetId> {
+        self.dependents_in(None, id)
+    }
+
+    pub fn is_loaded_in(&self, collection: Option<&str>, id: &AssetId) -> bool {
+        self.collection(collection)
+            .map(|states| states.contains(id))
+            .unwrap_or_default()
+    }
+
+    pub fn get_in(&self, collection: Option<&str>, id: &AssetId) -> Option<&AssetState> {
+        self.collection(collection)?.get(id)
+    }
+
+    pub fn load_in(&mut self, collection: Option<&str>, id: AssetId, state: AssetState) {
+        self.collection_mut(collection).insert(id, state);
+    }
+
+    pub fn unload_in(&mut self, collection: Option<&str>, id: &AssetId) -> Option<AssetState> {
+        self.collection_mut(collection).remove(id)
+    }
+
+    /// Loaded assets of the collection that depend on `id`.
+    pub fn dependents_in(&self, collection: Option<&str>, id: &AssetId) -> HashSet<AssetId> {
         let mut dependents = HashSet::new();
+        let states = match self.collection(collection) {
+            Some(states) => states,
+            None => return dependents,
+        };
 
-        for (state_id, state) in self.states.iter() {
+        for (state_id, state) in states.iter() {
             if state.dependencies().contains(id) {
                 dependents.insert(*state_id);
             }
@@ -70,4 +119,38 @@ impl AssetStates {
 
         dependents
     }
+
+    /// Collections the asset is loaded in, with `None` for the default `Assets<A>`.
+    pub fn collections_of(&self, id: &AssetId) -> Vec<Option<&str>> {
+        let named = self
+            .collections
+            .iter()
+            .filter(|(_, states)| states.contains(id));
+        let named = named.map(|(name, _)| Some(name.as_str()));
+
+        match self.states.contains(id) {
+            true => std::iter::once(None).chain(named).collect(),
+            false => named.collect(),
+        }
+    }
+
+    fn collection(&self, collection: Option<&str>) -> Option<&DenseMap<AssetId, AssetState>> {
+        match collection {
+            Some(name) => self.collections.get(&name.to_string()),
+            None => Some(&self.states),
+        }
+    }
+
+    fn collection_mut(&mut self, collection: Option<&str>) -> &mut DenseMap<AssetId, AssetState> {
+        let name = match collection {
+            Some(name) => name.to_string(),
+            None => return &mut self.states,
+        };
+
+        if !self.collections.contains(&name) {
+            self.collections.insert(name.clone(), DenseMap::new());
+        }
+
+        self.collections.get_mut(&name).unwrap()
+    }
 }
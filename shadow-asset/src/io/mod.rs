@@ -5,6 +5,7 @@ use std::{
 };
 
 pub mod local;
+pub mod path;
 pub mod vfs;
 
 #[derive(Debug)]
@@ -0,0 +1,184 @@
+use crate::bytes::IntoBytes;
+use std::{
+    error::Error,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    Absolute(String),
+    Traversal(String),
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathError::Absolute(path) => write!(f, "Asset path is absolute: {}", path),
+            PathError::Traversal(path) => write!(f, "Asset path leaves the assets root: {}", path),
+        }
+    }
+}
+
+impl Error for PathError {}
+
+/// A source asset path relative to the assets root, used to key the library and
+/// folder metas so they match across platforms.
+///
+/// Both `/` and `\` are read as separators and stored as `/`, and `.` segments are
+/// dropped. Absolute paths and `..` segments are rejected. Case is kept as is and
+/// compared exactly on every platform, so `Player.png` and `player.png` are
+/// different assets even on case-insensitive filesystems.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SourcePath(String);
+
+impl SourcePath {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, PathError> {
+        Self::parse(&path.as_ref().to_string_lossy())
+    }
+
+    pub fn parse(path: &str) -> Result<Self, PathError> {
+        let is_drive = |segment: &str| segment.len() == 2 && segment.ends_with(':');
+        if path.starts_with(['/', '\\']) || path.split(['/', '\\']).next().is_some_and(is_drive) {
+            return Err(PathError::Absolute(path.to_string()));
+        }
+
+        let mut segments = vec![];
+        for segment in path.split(['/', '\\']) {
+            match segment {
+                "" | "." => continue,
+                ".." => return Err(PathError::Traversal(path.to_string())),
+                segment => segments.push(segment),
+            }
+        }
+
+        Ok(Self(segments.join("/")))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn as_path(&self) -> &Path {
+        Path::new(&self.0)
+    }
+
+    /// Converts to a filesystem path. Only the io layer should need this.
+    pub fn to_path_buf(&self) -> PathBuf {
+        self.0.split('/').collect()
+    }
+}
+
+impl AsRef<Path> for SourcePath {
+    fn as_ref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl Display for SourcePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Libraries written before paths were normalized stored native paths, so loading
+/// normalizes them too.
+impl IntoBytes for SourcePath {
+    fn into_bytes(&self) -> Vec<u8> {
+        self.0.as_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Self::parse(&String::from_utf8_lossy(bytes)).ok()
+    }
+}
+
+impl serde::Serialize for SourcePath {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for SourcePath {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let path = String::deserialize(deserializer)?;
+        Self::parse(&path).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathError, SourcePath};
+    use crate::{
+        asset::{AssetId, AssetKind},
+        bytes::IntoBytes,
+        database::library::AssetLibrary,
+    };
+    use shadow_ecs::core::DenseMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn normalize() {
+        let path = SourcePath::parse("textures\\ui/./button.png").unwrap();
+        assert_eq!(path.as_str(), "textures/ui/button.png");
+        assert_eq!(
+            SourcePath::parse("textures//ui/").unwrap().as_str(),
+            "textures/ui"
+        );
+        assert_eq!(SourcePath::parse("").unwrap().as_str(), "");
+    }
+
+    #[test]
+    fn reject_invalid() {
+        let absolute = |path: &str| PathError::Absolute(path.to_string());
+        let traversal = |path: &str| PathError::Traversal(path.to_string());
+
+        assert_eq!(
+            SourcePath::parse("/etc/passwd"),
+            Err(absolute("/etc/passwd"))
+        );
+        assert_eq!(SourcePath::parse("C:\\assets"), Err(absolute("C:\\assets")));
+        assert_eq!(
+            SourcePath::parse("\\\\server\\share"),
+            Err(absolute("\\\\server\\share"))
+        );
+        assert_eq!(SourcePath::parse("a/../../b"), Err(traversal("a/../../b")));
+        assert_eq!(SourcePath::parse("..\\b"), Err(traversal("..\\b")));
+    }
+
+    #[test]
+    fn library_lookup() {
+        let id = AssetId::gen();
+        let path = SourcePath::parse("textures\\wall.png").unwrap();
+        let mut library = AssetLibrary::new();
+        library.add_asset(id, path, AssetKind::Main);
+
+        assert_eq!(library.id("textures/wall.png"), Some(&id));
+        assert_eq!(library.id("textures\\wall.png"), Some(&id));
+        assert_eq!(library.id("../textures/wall.png"), None);
+        assert_eq!(library.remove_path("./textures\\wall.png"), Some(id));
+    }
+
+    #[test]
+    fn load_windows_library() {
+        let id = AssetId::gen();
+        let path = PathBuf::from("textures\\wall.png");
+        let mut ids = DenseMap::new();
+        ids.insert(id, path.clone());
+        let mut paths = DenseMap::new();
+        paths.insert(path, id);
+
+        let ids = ids.into_bytes();
+        let mut bytes = ids.len().into_bytes();
+        bytes.extend(ids);
+        bytes.extend(paths.into_bytes());
+
+        let mut library = AssetLibrary::new();
+        assert!(library.load(&bytes).is_some());
+        assert_eq!(library.id("textures/wall.png"), Some(&id));
+        assert_eq!(
+            library.path(&id).map(SourcePath::as_str),
+            Some("textures/wall.png")
+        );
+    }
+}
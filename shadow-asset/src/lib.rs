@@ -4,4 +4,6 @@ pub mod bytes;
 pub mod database;
 pub mod io;
 pub mod loader;
+pub mod loading;
 pub mod plugin;
+pub mod validation;
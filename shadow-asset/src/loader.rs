@@ -1,13 +1,15 @@
 use crate::{
-    artifact::ArtifactMeta,
+    artifact::{ArtifactMeta, AssetStats, StatValue},
     asset::{Asset, AssetId, AssetPath, AssetSettings, Settings},
     io::{AssetIoError, AssetReader},
 };
 use shadow_ecs::{
     core::{internal::blob::BlobCell, DenseMap},
-    world::event::Event,
+    system::panic_message,
+    world::{event::Event, World},
 };
 use std::{
+    any::Any,
     collections::HashSet,
     error::Error,
     path::{Path, PathBuf},
@@ -16,6 +18,7 @@ use std::{
 pub struct LoadContext<'a, S: Settings> {
     settings: &'a AssetSettings<S>,
     dependencies: HashSet<AssetId>,
+    stats: AssetStats,
 }
 
 impl<'a, S: Settings> LoadContext<'a, S> {
@@ -23,6 +26,7 @@ impl<'a, S: Settings> LoadContext<'a, S> {
         Self {
             settings,
             dependencies: HashSet::new(),
+            stats: AssetStats::new(),
         }
     }
 
@@ -38,11 +42,31 @@ impl<'a, S: Settings> LoadContext<'a, S> {
         self.dependencies.insert(id);
     }
 
-    pub fn finish(self) -> HashSet<AssetId> {
-        self.dependencies
+    pub fn stats(&self) -> &AssetStats {
+        &self.stats
+    }
+
+    /// Records an import statistic, stored in the artifact's meta and checked
+    /// against `AssetConfig::validation`.
+    pub fn add_stat(&mut self, key: impl Into<String>, value: impl Into<StatValue>) {
+        self.stats.set(key, value);
+    }
+
+    pub fn finish(self) -> (HashSet<AssetId>, AssetStats) {
+        (self.dependencies, self.stats)
     }
 }
 
+/// Order in which queued loads run, and in which loaded assets are finalized.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LoadPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Critical,
+}
+
 pub trait AssetLoader: 'static {
     type Asset: Asset;
     type Settings: Settings;
@@ -54,6 +78,24 @@ pub trait AssetLoader: 'static {
         reader: &mut dyn AssetReader,
     ) -> Result<Self::Asset, Self::Error>;
     fn extensions() -> &'static [&'static str];
+
+    /// Bump to force assets imported with an older version to be reimported.
+    fn version() -> u32 {
+        0
+    }
+
+    fn priority() -> LoadPriority {
+        LoadPriority::Normal
+    }
+
+    /// If true, loaded assets are passed to `finalize` on the main thread before they
+    /// are added to `Assets`, a few per frame within `AssetConfig::finalize_budget`.
+    fn finalize_on_main() -> bool {
+        false
+    }
+
+    /// Turns the decoded asset into its final form, e.g. by creating GPU resources.
+    fn finalize(_asset: &mut Self::Asset, _world: &World) {}
 }
 
 pub struct ProcessContext<'a, S: Settings> {
@@ -145,7 +187,7 @@ impl Error for AssetError {}
 impl Event for AssetError {
     type Output = Self;
 
-    fn invoke(self, _: &mut shadow_ecs::world::World) -> Option<Self::Output> {
+    fn invoke(self, _: &mut World) -> Option<Self::Output> {
         Some(self)
     }
 }
@@ -163,6 +205,14 @@ pub enum LoadErrorKind {
     NoLoader,
     NoSerializer,
     InvalidExtension(String),
+    Panicked(String),
+    Quarantined,
+}
+
+impl LoadErrorKind {
+    pub fn panicked(payload: Box<dyn Any + Send>) -> Self {
+        LoadErrorKind::Panicked(panic_message(&*payload))
+    }
 }
 
 impl std::fmt::Display for LoadErrorKind {
@@ -173,6 +223,8 @@ impl std::fmt::Display for LoadErrorKind {
             LoadErrorKind::InvalidExtension(ext) => write!(f, "Invalid extension: {}", ext),
             LoadErrorKind::NoExtension => write!(f, "No extension found"),
             LoadErrorKind::NoSerializer => write!(f, "No serializer found"),
+            LoadErrorKind::Panicked(message) => write!(f, "Loader panicked: {}", message),
+            LoadErrorKind::Quarantined => write!(f, "Skipped quarantined asset"),
         }
     }
 }
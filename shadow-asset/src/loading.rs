@@ -0,0 +1,369 @@
+use crate::{
+    asset::{AssetId, AssetPath},
+    database::{events::LoadAssets, state::AssetStates, AssetDatabase},
+    loader::{AssetError, AssetErrorKind, LoadPriority},
+};
+use shadow_ecs::{
+    core::{DenseMap, DenseSet, Resource},
+    world::World,
+};
+use shadow_game::{
+    game::Game,
+    phases::First,
+    state::{State, States},
+};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    Failed,
+}
+
+impl LoadState {
+    pub fn of(states: &AssetStates, failed: &DenseSet<AssetId>, id: &AssetId) -> Self {
+        if failed.contains(id) {
+            LoadState::Failed
+        } else if states.is_loaded(id) {
+            LoadState::Loaded
+        } else {
+            LoadState::Loading
+        }
+    }
+
+    pub fn all_loaded<'a>(
+        states: &AssetStates,
+        ids: impl IntoIterator<Item = &'a AssetId>,
+    ) -> bool {
+        ids.into_iter().all(|id| states.is_loaded(id))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LoadProgress {
+    pub loaded: usize,
+    pub failed: usize,
+    pub total: usize,
+}
+
+impl LoadProgress {
+    pub fn fraction(&self) -> f32 {
+        match self.total {
+            0 => 1.0,
+            total => self.loaded as f32 / total as f32,
+        }
+    }
+}
+
+/// Assets that must be loaded before entering a state of type `S`.
+pub struct LoadingAssets<S: States> {
+    required: DenseMap<S, DenseSet<AssetId>>,
+    /// The queued state and when it was first queued. The timeout counts from there.
+    queued: Option<(S, Instant)>,
+    failed: DenseSet<AssetId>,
+    timeout: Option<Duration>,
+}
+
+impl<S: States> LoadingAssets<S> {
+    pub fn new() -> Self {
+        Self {
+            required: DenseMap::new(),
+            queued: None,
+            failed: DenseSet::new(),
+            timeout: None,
+        }
+    }
+
+    pub fn require(&mut self, state: S, id: AssetId) -> &mut Self {
+        self.failed.remove(&id);
+        match self.required.get_mut(&state) {
+            Some(ids) => ids.insert(id),
+            None => {
+                let mut ids = DenseSet::new();
+                ids.insert(id);
+                self.required.insert(state, ids);
+            }
+        }
+
+        self
+    }
+
+    pub fn require_all(&mut self, state: S, ids: impl IntoIterator<Item = AssetId>) -> &mut Self {
+        for id in ids {
+            self.require(state, id);
+        }
+
+        self
+    }
+
+    pub fn required(&self, state: &S) -> &[AssetId] {
+        self.required
+            .get(state)
+            .map(|ids| ids.keys())
+            .unwrap_or(&[])
+    }
+
+    /// Loads the assets required by `state` ahead of other queued loads.
+    pub fn load(&self, state: &S) -> LoadAssets {
+        LoadAssets::hard(self.required(state).to_vec()).with_priority(LoadPriority::Critical)
+    }
+
+    pub fn clear(&mut self, state: &S) {
+        self.required.remove(state);
+        if self
+            .queued
+            .as_ref()
+            .is_some_and(|(queued, _)| queued == state)
+        {
+            self.queued = None;
+        }
+    }
+
+    /// Starts the timeout clock when `next` is first queued, and stops it when the
+    /// transition is applied or replaced.
+    pub fn queue(&mut self, next: Option<S>) {
+        match next {
+            Some(next)
+                if self
+                    .queued
+                    .as_ref()
+                    .is_some_and(|(queued, _)| *queued == next) => {}
+            Some(next) => self.queued = Some((next, Instant::now())),
+            None => self.queued = None,
+        }
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub fn fail(&mut self, id: AssetId) {
+        self.failed.insert(id);
+    }
+
+    pub fn progress(&self, state: &S, states: &AssetStates) -> LoadProgress {
+        let mut progress = LoadProgress::default();
+        for id in self.required(state) {
+            progress.total += 1;
+            match LoadState::of(states, &self.failed, id) {
+                LoadState::Loaded => progress.loaded += 1,
+                LoadState::Failed => progress.failed += 1,
+                LoadState::Loading => {}
+            }
+        }
+
+        progress
+    }
+
+    pub fn state(&self, state: &S, states: &AssetStates) -> LoadState {
+        let progress = self.progress(state, states);
+        if progress.failed > 0 {
+            LoadState::Failed
+        } else if progress.loaded == progress.total {
+            LoadState::Loaded
+        } else if self.is_timed_out(state) {
+            LoadState::Failed
+        } else {
+            LoadState::Loading
+        }
+    }
+
+    fn is_timed_out(&self, state: &S) -> bool {
+        match (self.timeout, &self.queued) {
+            (Some(timeout), Some((queued, started))) if queued == state => {
+                started.elapsed() > timeout
+            }
+            _ => false,
+        }
+    }
+
+    pub fn on_error(errors: &[AssetError], database: &AssetDatabase, loading: &mut Self) {
+        for error in errors {
+            let id = match error.kind() {
+                AssetErrorKind::Load(AssetPath::Id(id)) => Some(*id),
+                AssetErrorKind::Load(AssetPath::Path(path)) => database.library().id(path).copied(),
+                AssetErrorKind::Import(_) => None,
+            };
+
+            if let Some(id) = id {
+                loading.fail(id);
+            }
+        }
+    }
+}
+
+impl<S: States> Default for LoadingAssets<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: States> Resource for LoadingAssets<S> {}
+
+fn track_queued_state<S: States>(state: &State<S>, loading: &mut LoadingAssets<S>) {
+    loading.queue(state.next());
+}
+
+fn queued_load_state<S: States>(world: &World) -> Option<LoadState> {
+    let next = world.try_resource::<State<S>>()?.next()?;
+    let loading = world.resource::<LoadingAssets<S>>();
+    let database = world.resource::<AssetDatabase>();
+    let states = database.states();
+    Some(loading.state(&next, &states))
+}
+
+/// True unless the queued `S` transition is still waiting on, or failed, its required assets.
+pub fn assets_ready<S: States>() -> impl Fn(&World) -> bool + Send + Sync + 'static {
+    |world| queued_load_state::<S>(world).is_none_or(|state| state == LoadState::Loaded)
+}
+
+/// True when the queued `S` transition can never complete because a required asset failed.
+pub fn assets_failed<S: States>() -> impl Fn(&World) -> bool + Send + Sync + 'static {
+    |world| queued_load_state::<S>(world) == Some(LoadState::Failed)
+}
+
+pub trait LoadingExt {
+    fn track_loading<S: States>(&mut self) -> &mut Self;
+}
+
+impl LoadingExt for Game {
+    fn track_loading<S: States>(&mut self) -> &mut Self {
+        self.init_resource::<LoadingAssets<S>>()
+            .observe::<AssetError, _>(LoadingAssets::<S>::on_error)
+            .add_system(First, track_queued_state::<S>)
+            .add_state_guard::<S>(assets_ready::<S>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assets_failed, LoadingAssets, LoadingExt};
+    use crate::{
+        asset::{Asset, AssetId},
+        database::{state::AssetState, AssetConfig, AssetDatabase},
+        io::vfs::VirtualFileSystem,
+        loader::{AssetError, LoadErrorKind},
+    };
+    use shadow_ecs::system::IntoSystem;
+    use shadow_game::{
+        game::Game,
+        phases::Update,
+        state::{State, States},
+    };
+    use std::{collections::HashSet, thread::sleep, time::Duration};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum AppState {
+        Loading,
+        Playing,
+        Error,
+    }
+
+    impl States for AppState {}
+
+    struct Texture;
+    impl Asset for Texture {}
+
+    fn game(ids: [AssetId; 3]) -> Game {
+        let config = AssetConfig::new(VirtualFileSystem::new(""));
+        let mut game = Game::new();
+        game.add_resource(AssetDatabase::new(config))
+            .register_event::<AssetError>()
+            .add_state(AppState::Loading)
+            .track_loading::<AppState>()
+            .add_system(
+                Update,
+                (|state: &mut State<AppState>| state.set(AppState::Error))
+                    .run_if(assets_failed::<AppState>()),
+            );
+        game.resource_mut::<LoadingAssets<AppState>>()
+            .require_all(AppState::Playing, ids);
+        game
+    }
+
+    fn current(game: &Game) -> Option<AppState> {
+        game.resource::<State<AppState>>().current()
+    }
+
+    #[test]
+    fn transition_waits_for_assets() {
+        let ids = [AssetId::gen(), AssetId::gen(), AssetId::gen()];
+        let mut game = game(ids);
+        game.set_runner(move |game: &mut Game| {
+            game.start();
+            game.resource_mut::<State<AppState>>()
+                .set(AppState::Playing);
+
+            for id in ids {
+                game.update();
+                assert_eq!(current(game), Some(AppState::Loading));
+
+                let state = AssetState::new::<Texture>(HashSet::new());
+                game.resource::<AssetDatabase>()
+                    .states_mut()
+                    .load(id, state);
+            }
+
+            let loading = game.resource::<LoadingAssets<AppState>>();
+            let database = game.resource::<AssetDatabase>();
+            let progress = loading.progress(&AppState::Playing, &database.states());
+            assert_eq!((progress.loaded, progress.total), (3, 3));
+
+            game.update();
+        });
+        game.run();
+
+        assert_eq!(current(&game), Some(AppState::Playing));
+    }
+
+    #[test]
+    fn failed_load_routes_to_error_state() {
+        let ids = [AssetId::gen(), AssetId::gen(), AssetId::gen()];
+        let mut game = game(ids);
+        game.set_runner(move |game: &mut Game| {
+            game.start();
+            game.resource_mut::<State<AppState>>()
+                .set(AppState::Playing);
+
+            let state = AssetState::new::<Texture>(HashSet::new());
+            game.resource::<AssetDatabase>()
+                .states_mut()
+                .load(ids[0], state);
+            game.dispatch_event(AssetError::load(ids[1], LoadErrorKind::NoLoader));
+
+            game.update();
+        });
+        game.run();
+
+        assert_eq!(current(&game), Some(AppState::Error));
+    }
+
+    #[test]
+    fn timeout_counts_from_queued_transition() {
+        let ids = [AssetId::gen(), AssetId::gen(), AssetId::gen()];
+        let mut game = game(ids);
+        let timeout = Duration::from_millis(20);
+        game.resource_mut::<LoadingAssets<AppState>>()
+            .set_timeout(Some(timeout));
+        game.set_runner(move |game: &mut Game| {
+            game.start();
+            sleep(timeout * 2);
+            game.resource_mut::<State<AppState>>()
+                .set(AppState::Playing);
+
+            game.update();
+            assert_eq!(current(game), Some(AppState::Loading));
+
+            sleep(timeout * 2);
+            game.update();
+        });
+        game.run();
+
+        assert_eq!(current(&game), Some(AppState::Error));
+    }
+}
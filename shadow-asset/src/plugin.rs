@@ -1,16 +1,24 @@
 use crate::{
-    asset::{Asset, Assets},
+    asset::{Asset, AssetCollections, AssetId, Assets, RetentionPolicy},
     database::{
         events::{
             AssetImported, AssetLoaded, AssetUnloaded, ImportAsset, ImportAssets, ImportFolder,
-            LoadAsset, LoadAssets, RemoveAsset, RemoveAssets, StartAssetEvent, UnloadAsset,
+            ImportPlan, LoadAsset, LoadAssets, RemoveAsset, RemoveAssets, StartAssetEvent,
+            UnloadAsset,
         },
+        finalize::finalize_assets,
+        retention::{discard_assets, discard_collection_assets},
         AssetConfig, AssetDatabase,
     },
     loader::{AssetError, AssetLoader, AssetProcessor, AssetSerializer},
 };
 use shadow_ecs::world::{event::Events, World};
-use shadow_game::{game::Game, phases::Init, plugin::Plugin};
+use shadow_game::{
+    console::{CommandArgs, ConsoleCommands, ConsoleError},
+    game::Game,
+    phases::{Init, Last, PreRender},
+    plugin::Plugin,
+};
 
 pub struct AssetPlugin;
 
@@ -23,7 +31,9 @@ impl Plugin for AssetPlugin {
 
         game.add_resource(AssetDatabase::new(config))
             .add_system(Init, asset_config_init)
+            .add_system(PreRender, finalize_assets)
             .register_event::<ImportFolder>()
+            .register_event::<ImportPlan>()
             .register_event::<ImportAsset>()
             .register_event::<ImportAssets>()
             .register_event::<AssetImported>()
@@ -39,12 +49,47 @@ impl Plugin for AssetPlugin {
             .observe::<RemoveAsset, _>(RemoveAsset::observer)
             .observe::<AssetError, _>(AssetError::observer)
             .observe::<StartAssetEvent, _>(StartAssetEvent::on_start);
+
+        add_console_commands(game.try_init_resource::<ConsoleCommands>());
     }
 }
 
+fn add_console_commands(commands: &mut ConsoleCommands) {
+    fn database<'a>(
+        args: &CommandArgs,
+        world: &'a World,
+    ) -> Result<&'a AssetDatabase, ConsoleError> {
+        world
+            .try_resource::<AssetDatabase>()
+            .ok_or_else(|| args.fail("asset database not added"))
+    }
+
+    commands
+        .register("asset_loaders", "", |args, world| {
+            let loaders = database(args.expect(0)?, world)?.list_loaders();
+            let lines = loaders.iter().map(|loader| loader.to_string());
+            Ok(lines.collect::<Vec<_>>().join("\n"))
+        })
+        .register("asset_path", "<path>", |args, world| {
+            let path = args.expect(1)?.string(0)?;
+            match database(args, world)?.describe_path(path) {
+                Ok(report) => Ok(report.to_string()),
+                Err(error) => Err(args.fail(error.to_string())),
+            }
+        })
+        .register("asset_info", "<id>", |args, world| {
+            let id = AssetId::raw(args.expect(1)?.get(0)?);
+            Ok(database(args, world)?.describe_asset(id).to_string())
+        });
+}
+
 pub trait AssetExt: Sized {
     fn config(&mut self) -> &mut AssetConfig;
     fn register_asset<A: Asset>(&mut self) -> &mut Self;
+    /// Retention is applied by `discard_assets` and `discard_collection_assets`, which
+    /// `Game` runs in its `Last` phase. A bare `World` has no phases of its own, so
+    /// add those systems to whichever phase the world runs each frame.
+    fn set_retention<A: Asset>(&mut self, policy: RetentionPolicy) -> &mut Self;
     fn register_loader<L: AssetLoader>(&mut self) -> &mut Self;
     fn register_processor<P: AssetProcessor>(&mut self) -> &mut Self;
     fn register_serializer<C: AssetSerializer>(&mut self) -> &mut Self;
@@ -60,14 +105,23 @@ impl AssetExt for Game {
             self.config().register::<A>();
             self.register_event::<AssetLoaded<A>>()
                 .register_event::<AssetUnloaded<A>>()
-                .observe::<AssetLoaded<A>, _>(AssetLoaded::<A>::observer)
                 .observe::<AssetUnloaded<A>, _>(AssetUnloaded::<A>::observer)
-                .init_resource::<Assets<A>>();
+                .init_resource::<Assets<A>>()
+                .init_resource::<AssetCollections<A>>()
+                .add_system(Last, discard_assets::<A>)
+                .add_system(Last, discard_collection_assets::<A>);
         }
 
         self
     }
 
+    fn set_retention<A: Asset>(&mut self, policy: RetentionPolicy) -> &mut Self {
+        self.register_asset::<A>();
+        self.resource_mut::<Assets<A>>().set_retention(policy);
+
+        self
+    }
+
     fn register_loader<L: AssetLoader>(&mut self) -> &mut Self {
         self.register_asset::<L::Asset>();
         self.config().set_loader::<L>();
@@ -100,14 +154,21 @@ impl AssetExt for World {
             self.config().register::<A>();
             self.register_event::<AssetLoaded<A>>()
                 .register_event::<AssetUnloaded<A>>()
-                .observe::<AssetLoaded<A>, _>(AssetLoaded::<A>::observer)
                 .observe::<AssetUnloaded<A>, _>(AssetUnloaded::<A>::observer)
-                .init_resource::<Assets<A>>();
+                .init_resource::<Assets<A>>()
+                .init_resource::<AssetCollections<A>>();
         }
 
         self
     }
 
+    fn set_retention<A: Asset>(&mut self, policy: RetentionPolicy) -> &mut Self {
+        self.register_asset::<A>();
+        self.resource_mut::<Assets<A>>().set_retention(policy);
+
+        self
+    }
+
     fn register_loader<L: AssetLoader>(&mut self) -> &mut Self {
         self.register_asset::<L::Asset>();
         self.config().set_loader::<L>();
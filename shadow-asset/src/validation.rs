@@ -0,0 +1,258 @@
+use crate::artifact::{AssetStats, StatValue};
+use std::{
+    error::Error,
+    fmt::Display,
+    mem::discriminant,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatCheck {
+    Max(f64),
+    Min(f64),
+    /// The stat must be recorded, and be true or non-zero.
+    Required,
+    PowerOfTwo,
+}
+
+impl StatCheck {
+    fn passes(&self, value: Option<&StatValue>) -> bool {
+        let number = value.and_then(|value| value.as_f64());
+        match self {
+            StatCheck::Max(max) => number.is_none_or(|value| value <= *max),
+            StatCheck::Min(min) => number.is_none_or(|value| value >= *min),
+            StatCheck::Required => match value {
+                Some(StatValue::Text(_)) => true,
+                _ => number.is_some_and(|value| value != 0.0),
+            },
+            StatCheck::PowerOfTwo => number.is_none_or(|value| {
+                value >= 1.0 && value.fract() == 0.0 && (value as u64).is_power_of_two()
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub stat: String,
+    pub check: StatCheck,
+    pub value: Option<StatValue>,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match &self.value {
+            Some(value) => value.to_string(),
+            None => String::from("missing"),
+        };
+
+        match &self.check {
+            StatCheck::Max(max) => write!(f, "{} is {}, max {}", self.stat, value, max),
+            StatCheck::Min(min) => write!(f, "{} is {}, min {}", self.stat, value, min),
+            StatCheck::Required => write!(f, "{} is required, found {}", self.stat, value),
+            StatCheck::PowerOfTwo => {
+                write!(f, "{} is {}, expected a power of two", self.stat, value)
+            }
+        }
+    }
+}
+
+/// Returned by an import that violates the validation rules in strict mode.
+#[derive(Debug, Clone)]
+pub struct ValidationError {
+    violations: Vec<Violation>,
+}
+
+impl ValidationError {
+    pub fn new(violations: Vec<Violation>) -> Self {
+        Self { violations }
+    }
+
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Validation failed: ")?;
+        for (index, violation) in self.violations.iter().enumerate() {
+            match index {
+                0 => write!(f, "{}", violation)?,
+                _ => write!(f, "; {}", violation)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ValidationError {}
+
+struct ValidationRule {
+    folder: PathBuf,
+    stat: String,
+    check: StatCheck,
+}
+
+/// Checks run against the stats of each imported asset. A rule added for a folder
+/// overrides rules of the same stat and kind of check added for its parent folders.
+/// Violations are reported as warnings, or fail the import in strict mode.
+#[derive(Default)]
+pub struct ValidationRules {
+    rules: Vec<ValidationRule>,
+    strict: bool,
+}
+
+impl ValidationRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn set_strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn add(&mut self, stat: impl Into<String>, check: StatCheck) -> &mut Self {
+        self.add_for("", stat, check)
+    }
+
+    /// Adds a rule for the assets in `folder`, relative to the assets directory.
+    pub fn add_for(
+        &mut self,
+        folder: impl AsRef<Path>,
+        stat: impl Into<String>,
+        check: StatCheck,
+    ) -> &mut Self {
+        let folder = folder.as_ref().to_path_buf();
+        let stat = stat.into();
+        let rule = self.rules.iter_mut().find(|rule| {
+            rule.folder == folder
+                && rule.stat == stat
+                && discriminant(&rule.check) == discriminant(&check)
+        });
+
+        match rule {
+            Some(rule) => rule.check = check,
+            None => self.rules.push(ValidationRule {
+                folder,
+                stat,
+                check,
+            }),
+        }
+
+        self
+    }
+
+    /// The rules that apply to the asset at `path`.
+    pub fn rules_for(&self, path: &Path) -> Vec<(&str, &StatCheck)> {
+        let mut rules: Vec<&ValidationRule> = vec![];
+        for rule in self
+            .rules
+            .iter()
+            .filter(|rule| path.starts_with(&rule.folder))
+        {
+            let overridden = rules.iter().position(|other| {
+                other.stat == rule.stat && discriminant(&other.check) == discriminant(&rule.check)
+            });
+
+            match overridden {
+                Some(index) if rules[index].folder.starts_with(&rule.folder) => {}
+                Some(index) => rules[index] = rule,
+                None => rules.push(rule),
+            }
+        }
+
+        rules
+            .into_iter()
+            .map(|rule| (rule.stat.as_str(), &rule.check))
+            .collect()
+    }
+
+    pub fn check(&self, path: &Path, stats: &AssetStats) -> Vec<Violation> {
+        let mut violations = vec![];
+        for (stat, check) in self.rules_for(path) {
+            let value = stats.get(stat);
+            if !check.passes(value) {
+                violations.push(Violation {
+                    stat: stat.to_string(),
+                    check: check.clone(),
+                    value: value.cloned(),
+                });
+            }
+        }
+
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StatCheck, ValidationRules};
+    use crate::artifact::{AssetStats, StatValue};
+    use std::path::Path;
+
+    #[test]
+    fn folder_overrides() {
+        let mut rules = ValidationRules::new();
+        rules
+            .add("triangles", StatCheck::Max(10_000.0))
+            .add("normals", StatCheck::Required)
+            .add_for("props/small", "triangles", StatCheck::Max(500.0))
+            .add_for("props", "triangles", StatCheck::Max(2_000.0))
+            .add_for("props", "triangles", StatCheck::Min(4.0));
+
+        let rules_for = |path: &str| {
+            let rules = rules.rules_for(Path::new(path));
+            rules
+                .into_iter()
+                .map(|(stat, check)| (stat.to_string(), check.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            rules_for("props/small/pebble.mesh"),
+            [
+                ("triangles".to_string(), StatCheck::Max(500.0)),
+                ("normals".to_string(), StatCheck::Required),
+                ("triangles".to_string(), StatCheck::Min(4.0)),
+            ]
+        );
+        assert_eq!(
+            rules_for("props/rock.mesh")[0],
+            ("triangles".to_string(), StatCheck::Max(2_000.0))
+        );
+        assert_eq!(
+            rules_for("levels/castle.mesh")[0],
+            ("triangles".to_string(), StatCheck::Max(10_000.0))
+        );
+    }
+
+    #[test]
+    fn check_stats() {
+        let mut rules = ValidationRules::new();
+        rules
+            .add("width", StatCheck::PowerOfTwo)
+            .add("width", StatCheck::Max(2048.0))
+            .add("uvs", StatCheck::Required);
+
+        let mut stats = AssetStats::new();
+        stats.set("width", 4096u32);
+        stats.set("uvs", true);
+        let violations = rules.check(Path::new("pebble.png"), &stats);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].check, StatCheck::Max(2048.0));
+        assert_eq!(violations[0].value, Some(StatValue::Int(4096)));
+
+        stats.set("width", 1000u32);
+        stats.set("uvs", false);
+        let violations = rules.check(Path::new("pebble.png"), &stats);
+        let checks = violations.iter().map(|v| &v.check).collect::<Vec<_>>();
+        assert_eq!(checks, [&StatCheck::PowerOfTwo, &StatCheck::Required]);
+    }
+}
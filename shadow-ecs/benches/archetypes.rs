@@ -0,0 +1,79 @@
+//! Add/remove churn over cached archetype edges. Run with `cargo bench -p shadow-ecs`.
+
+use shadow_ecs::{
+    core::{Component, ComponentId, Entity},
+    world::World,
+};
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+#[allow(dead_code)]
+struct Health(u32);
+impl Component for Health {}
+
+#[allow(dead_code)]
+struct Speed(u32);
+impl Component for Speed {}
+
+const ENTITIES: u32 = 10_000;
+const ROUNDS: u32 = 20;
+
+fn setup() -> (World, Vec<Entity>) {
+    let mut world = World::new();
+    let entities = (0..ENTITIES)
+        .map(|index| {
+            let entity = world.spawn(None);
+            world.add_component(&entity, Speed(index));
+            entity
+        })
+        .collect();
+
+    (world, entities)
+}
+
+fn bench(name: &str, mut f: impl FnMut(&mut World, &[Entity])) {
+    let (mut world, entities) = setup();
+    f(&mut world, &entities);
+
+    let mut total = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let started = Instant::now();
+        f(&mut world, &entities);
+        total += started.elapsed();
+    }
+
+    let per_op = total / (ROUNDS * ENTITIES);
+    println!("{name:<32} {per_op:>10.2?} per entity");
+}
+
+fn main() {
+    let health = ComponentId::new::<Health>();
+
+    bench("add/remove, reported", |world, entities| {
+        for entity in entities {
+            black_box(world.add_component(entity, Health(1)));
+        }
+
+        for entity in entities {
+            black_box(world.remove_component(entity, &health));
+        }
+    });
+
+    bench("add/remove, unreported", |world, entities| {
+        for entity in entities {
+            black_box(world.set_component(entity, Health(1)));
+        }
+
+        for entity in entities {
+            black_box(world.discard_component(entity, &health));
+        }
+    });
+
+    bench("overwrite in place", |world, entities| {
+        for entity in entities {
+            black_box(world.set_component(entity, Speed(1)));
+        }
+    });
+}
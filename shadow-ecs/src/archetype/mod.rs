@@ -1,11 +1,13 @@
-use crate::core::{Component, ComponentId, Entity};
+use crate::core::{ColumnCell, Component, ComponentId, Entity, Tick};
 use crate::core::{DenseMap, DenseSet};
+use sparse::SparseMarkers;
 use std::{
     collections::{HashMap, HashSet},
     hash::{Hash, Hasher},
 };
-use table::{EntityRow, EntityTable};
+use table::{EntityRow, EntityTable, TableBuilder};
 
+pub mod sparse;
 pub mod table;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -137,14 +139,22 @@ impl Archetype {
         self.table.get_component(entity)
     }
 
-    pub fn component_mut<C: Component>(&self, entity: &Entity) -> Option<&mut C> {
-        self.table.get_component_mut(entity)
+    pub fn component_mut<C: Component>(&self, entity: &Entity, tick: Tick) -> Option<&mut C> {
+        self.table.get_component_mut(entity, tick)
+    }
+
+    pub fn changed_tick(&self, id: &ComponentId, entity: &Entity) -> Option<Tick> {
+        self.table.changed_tick(id, entity)
     }
 
     pub fn entities(&self) -> &[Entity] {
         self.table.entities()
     }
 
+    pub fn memory(&self) -> usize {
+        self.table.memory()
+    }
+
     pub fn edge(&self, id: impl Into<EdgeId>, ty: EdgeType) -> Option<&ArchetypeId> {
         match ty {
             EdgeType::Add => self.add_edges.get(&id.into()),
@@ -163,14 +173,25 @@ impl Archetype {
         }
     }
 
-    pub fn insert(&mut self, entity: &Entity, row: EntityRow) {
-        self.table.add_entity(*entity, row)
+    pub fn insert(&mut self, entity: &Entity, row: EntityRow, tick: Tick) {
+        self.table.add_entity(*entity, row, tick)
     }
 
     pub fn remove(&mut self, entity: &Entity) -> Option<EntityRow> {
         self.table.remove_entity(entity)
     }
 
+    pub fn replace(&mut self, entity: &Entity, mut row: EntityRow, tick: Tick) -> EntityRow {
+        let mut removed = EntityRow::new();
+        for (id, cell) in row.drain() {
+            if let Some(old) = self.table.replace_cell(entity, &id, cell, tick) {
+                removed.add_cell(id, old);
+            }
+        }
+
+        removed
+    }
+
     pub fn contains(&self, entity: &Entity) -> bool {
         self.table.contains(entity)
     }
@@ -178,6 +199,18 @@ impl Archetype {
     pub fn has_component(&self, id: &ComponentId) -> bool {
         self.table.has_component(id)
     }
+
+    fn append(&mut self, other: Archetype) {
+        for (edge, target) in other.add_edges {
+            self.add_edges.entry(edge).or_insert(target);
+        }
+
+        for (edge, target) in other.remove_edges {
+            self.remove_edges.entry(edge).or_insert(target);
+        }
+
+        self.table.append(other.table);
+    }
 }
 
 pub struct Archetypes {
@@ -185,6 +218,12 @@ pub struct Archetypes {
     entities: DenseMap<Entity, ArchetypeId>,
     archetypes: DenseMap<ArchetypeId, Archetype>,
     components: DenseMap<ComponentId, DenseSet<ArchetypeId>>,
+    sparse: SparseMarkers,
+    tick: Tick,
+    added: DenseMap<ComponentId, HashMap<Entity, Tick>>,
+    /// Entities moved between archetypes, counted so tests can check bulk paths.
+    #[cfg(test)]
+    pub(crate) moves: usize,
 }
 
 impl Archetypes {
@@ -200,6 +239,11 @@ impl Archetypes {
             archetypes,
             entities: DenseMap::new(),
             components: DenseMap::new(),
+            sparse: SparseMarkers::new(),
+            tick: Tick::default(),
+            added: DenseMap::new(),
+            #[cfg(test)]
+            moves: 0,
         }
     }
 
@@ -207,10 +251,128 @@ impl Archetypes {
         self.root_id
     }
 
+    /// The current change tick. Components added or borrowed mutably are stamped with it.
+    pub fn tick(&self) -> Tick {
+        self.tick
+    }
+
+    /// Advances the change tick, clamping stored ticks every `Tick::CLAMP_INTERVAL`
+    /// ticks so they never wrap around to look recent.
+    pub(crate) fn advance_tick(&mut self) -> Tick {
+        self.tick = self.tick.next();
+        if self.tick.should_clamp() {
+            self.clamp_ticks();
+        }
+
+        let previous = self.tick.prev();
+        for entities in self.added.values_mut() {
+            entities.retain(|_, tick| *tick == previous);
+        }
+
+        self.tick
+    }
+
+    pub fn clamp_ticks(&mut self) {
+        let tick = self.tick;
+        for archetype in self.archetypes.values_mut() {
+            archetype.table.clamp_ticks(tick);
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_tick(&mut self, tick: Tick) {
+        self.tick = tick;
+    }
+
+    /// True if the component was added to the entity during the previous frame, matching
+    /// `Changed`. Replacing a component the entity already has doesn't count.
+    pub fn is_added(&self, id: &ComponentId, entity: &Entity) -> bool {
+        self.added
+            .get(id)
+            .and_then(|entities| entities.get(entity))
+            .is_some_and(|tick| *tick == self.tick.prev())
+    }
+
     pub fn get(&self, id: &ArchetypeId) -> Option<&Archetype> {
         self.archetypes.get(id)
     }
 
+    pub fn len(&self) -> usize {
+        self.archetypes.len()
+    }
+
+    /// Every archetype, including empty ones.
+    pub fn iter(&self) -> impl Iterator<Item = &Archetype> {
+        self.archetypes.values().iter()
+    }
+
+    /// Archetypes that have every component in `components`, including empty ones.
+    pub fn iter_with<'a>(
+        &'a self,
+        components: &'a [ComponentId],
+    ) -> impl Iterator<Item = &'a Archetype> + 'a {
+        let candidates = components
+            .iter()
+            .map(|id| self.components.get(id))
+            .min_by_key(|ids| ids.map_or(0, |ids| ids.len()));
+        let ids = match candidates {
+            Some(Some(ids)) => ids.keys(),
+            Some(None) => &[],
+            None => self.archetypes.keys(),
+        };
+
+        ids.iter()
+            .filter_map(|id| self.archetypes.get(id))
+            .filter(|archetype| components.iter().all(|id| archetype.has_component(id)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.archetypes.is_empty()
+    }
+
+    pub fn sparse(&self) -> &SparseMarkers {
+        &self.sparse
+    }
+
+    /// Stores `id` in sparse storage instead of the archetype tables.
+    pub fn register_sparse(&mut self, id: ComponentId) {
+        self.sparse.register(id);
+    }
+
+    /// Creates the archetype for the builder's components, along with the archetypes and
+    /// edges leading to it from the root, so entities don't pay for them on first use.
+    pub fn register(&mut self, mut builder: TableBuilder) -> ArchetypeId {
+        let mut ids = builder.components().to_vec();
+        ids.retain(|id| !self.sparse.is_sparse(id));
+        ids.sort_unstable();
+        builder = builder.subset(&ids);
+
+        let root_id = self.root_id;
+        let mut from = root_id;
+        for index in 0..ids.len() {
+            let components = &ids[..=index];
+            let to = ArchetypeId::new(components);
+            if !self.archetypes.contains(&to) {
+                let table = builder.subset(components).build();
+                self.add_archetypes(components, to);
+                self.archetypes.insert(to, Archetype::new(to, table));
+            }
+
+            self.link(&from, &to, EdgeId::from(ids[index]));
+            from = to;
+        }
+
+        if ids.len() > 1 {
+            self.link(&root_id, &from, EdgeId::new(&ids));
+        }
+
+        from
+    }
+
+    pub fn entity_archetype(&self, entity: &Entity) -> Option<ArchetypeId> {
+        self.entities.get(entity).copied()
+    }
+
     pub fn query(&self, ids: &[ComponentId], exclude: &HashSet<ComponentId>) -> Vec<ArchetypeId> {
         let mut archetypes = DenseMap::new();
         for id in ids {
@@ -237,13 +399,43 @@ impl Archetypes {
         archetypes.into_keys()
     }
 
+    /// Moves every archetype in `other` into this set, renaming entities through `map`.
+    /// Tables with a matching archetype are appended whole rather than moved row by row.
+    /// Merged components count as added and changed at the current tick.
+    pub fn merge(&mut self, mut other: Archetypes, map: &DenseMap<Entity, Entity>) {
+        for (id, entity) in other.sparse.entries() {
+            let entity = map.get(entity).copied().unwrap_or(*entity);
+            self.mark_added_id(&entity, id);
+        }
+
+        self.sparse.merge(other.sparse, map);
+        for (id, mut archetype) in other.archetypes.drain() {
+            archetype.table.remap(map);
+            archetype.table.mark_changed(self.tick);
+            for entity in archetype.entities() {
+                self.entities.insert(*entity, id);
+                for component in archetype.components() {
+                    self.mark_added_id(entity, component);
+                }
+            }
+
+            match self.archetypes.get_mut(&id) {
+                Some(existing) => existing.append(archetype),
+                None => {
+                    self.add_archetypes(archetype.components(), id);
+                    self.archetypes.insert(id, archetype);
+                }
+            }
+        }
+    }
+
     pub fn add_entity(&mut self, entity: &Entity) {
         let root_id = self.root_id;
         self.entities.insert(*entity, root_id);
         self.archetypes
             .get_mut(&root_id)
             .unwrap()
-            .insert(entity, EntityRow::new());
+            .insert(entity, EntityRow::new(), self.tick);
     }
 
     pub fn remove_entity(&mut self, entity: &Entity) -> Option<(ArchetypeId, EntityRow)> {
@@ -253,7 +445,21 @@ impl Archetypes {
         Some((archetype.id(), components))
     }
 
+    /// Removes the entity along with its sparse markers.
+    pub fn despawn_entity(&mut self, entity: &Entity) -> Option<(ArchetypeId, EntityRow)> {
+        let (id, mut components) = self.remove_entity(entity)?;
+        for (marker, cell) in self.sparse.remove_entity(entity).drain() {
+            components.add_cell(marker, cell);
+        }
+
+        Some((id, components))
+    }
+
     pub fn has_component(&self, entity: &Entity, component: &ComponentId) -> bool {
+        if self.sparse.is_sparse(component) {
+            return self.sparse.contains(component, entity);
+        }
+
         self.entities.get(entity).map_or(false, |id| {
             self.archetypes.get(id).unwrap().has_component(component)
         })
@@ -262,7 +468,10 @@ impl Archetypes {
     pub fn has_components(&self, entity: &Entity, ids: DenseSet<ComponentId>) -> bool {
         self.entities.get(entity).map_or(false, |id| {
             let archetype = self.archetypes.get(id).unwrap();
-            ids.iter().all(|id| archetype.has_component(id))
+            ids.iter().all(|id| match self.sparse.is_sparse(id) {
+                true => self.sparse.contains(id, entity),
+                false => archetype.has_component(id),
+            })
         })
     }
 
@@ -272,52 +481,168 @@ impl Archetypes {
         id: &ComponentId,
         component: C,
     ) -> Option<ArchetypeMove> {
-        let (archetype, mut components) = self.remove_entity(entity)?;
+        let _move = self.insert_component(entity, id, component, true)?;
+        self.mark_added(entity, &_move);
+        Some(_move)
+    }
+
+    /// Adds the component like `add_component`, but drops any value it replaces and
+    /// reports no move. Moving along an edge that is already cached allocates nothing.
+    pub fn set_component<C: Component>(
+        &mut self,
+        entity: &Entity,
+        id: &ComponentId,
+        component: C,
+    ) -> bool {
+        let replaced = self.has_component(entity, id);
+        if self
+            .insert_component(entity, id, component, false)
+            .is_none()
+        {
+            return false;
+        }
+
+        if !replaced {
+            self.mark_added_id(entity, id);
+        }
+
+        true
+    }
+
+    pub fn add_components(&mut self, entity: &Entity, row: EntityRow) -> Option<ArchetypeMove> {
+        let _move = self.insert_components(entity, row)?;
+        self.mark_added(entity, &_move);
+        Some(_move)
+    }
+
+    /// Adds or replaces the component. The returned move lists the added id and the
+    /// replaced value only when `report` is set, as nothing reads them otherwise.
+    fn insert_component<C: Component>(
+        &mut self,
+        entity: &Entity,
+        id: &ComponentId,
+        component: C,
+        report: bool,
+    ) -> Option<ArchetypeMove> {
+        let current = *self.entities.get(entity)?;
         let mut added = DenseSet::new();
-        let mut removed = EntityRow::new();
-        components.add_component(component).map(|c| {
-            removed.add_cell(*id, c);
-        });
+        if report {
+            added.insert(*id);
+        }
+
+        if self.sparse.is_sparse(id) {
+            let mut removed = EntityRow::new();
+            let cell = ColumnCell::from(component);
+            if let Some(old) = self.sparse.insert(*id, *entity, cell) {
+                if report {
+                    removed.add_cell(*id, old);
+                }
+            }
+
+            let _move = ArchetypeMove::new(current, current)
+                .with_added(added)
+                .with_removed(removed);
+            return Some(_move);
+        }
+
+        let archetype = self.archetypes.get(&current)?;
+        if archetype.has_component(id) {
+            if !report {
+                *archetype.component_mut::<C>(entity, self.tick)? = component;
+                return Some(ArchetypeMove::new(current, current));
+            }
+
+            let mut row = EntityRow::new();
+            row.add_component(component);
+            return self.replace_components(entity, &current, row, added);
+        }
+
+        if let Some(next) = archetype.edge(*id, EdgeType::Add).copied() {
+            let tick = self.tick;
+            let target = self.move_row(entity, &current, &next, None)?;
+            target.push_component(id, component, tick);
+            return Some(ArchetypeMove::new(current, next).with_added(added));
+        }
+
+        let (archetype, mut components) = self.remove_entity(entity)?;
+        let removed = EntityRow::new();
+        components.add_component(component);
         components.sort();
-        added.insert(*id);
 
         let edge = EdgeId::from(id);
         let ty = MoveType::Add(added, removed);
         self.move_entity(entity, &archetype, &edge, components, ty)
     }
 
-    pub fn add_components(&mut self, entity: &Entity, mut row: EntityRow) -> Option<ArchetypeMove> {
+    fn insert_components(&mut self, entity: &Entity, mut row: EntityRow) -> Option<ArchetypeMove> {
         if row.is_empty() {
             return None;
         }
 
-        let (archetype, mut components) = self.remove_entity(entity)?;
-        let mut added = DenseSet::<ComponentId>::new();
-        let mut removed = EntityRow::new();
-        let mut unique = DenseSet::new();
-        row.sort();
-        for (id, cell) in row.drain() {
-            added.insert(id);
-            if components.contains_id(&id) {
-                unique.insert(id);
-            }
-            components.add_cell(id, cell).map(|c| {
-                removed.add_cell(id, c);
-            });
+        let current = *self.entities.get(entity)?;
+        let (added, mut removed) = self.add_sparse(entity, &mut row);
+        if added.is_empty() {
+            return self.add_table_components(entity, row);
         }
-        components.sort();
 
-        let edge = EdgeId::from(unique.keys());
-        let ty = MoveType::Add(added, removed);
-        self.move_entity(entity, &archetype, &edge, components, ty)
+        let mut _move = match row.is_empty() {
+            true => ArchetypeMove::new(current, current),
+            false => self.add_table_components(entity, row)?,
+        };
+
+        _move.added.extend(added);
+        for (id, cell) in removed.drain() {
+            _move.removed.add_cell(id, cell);
+        }
+
+        Some(_move)
     }
 
     pub fn remove_component(&mut self, entity: &Entity, id: &ComponentId) -> Option<ArchetypeMove> {
+        self.take_component(entity, id, true)
+    }
+
+    /// Removes the component like `remove_component`, dropping the value in place.
+    /// Moving along an edge that is already cached allocates nothing.
+    pub fn discard_component(&mut self, entity: &Entity, id: &ComponentId) -> bool {
+        self.take_component(entity, id, false).is_some()
+    }
+
+    /// Removes the component, keeping its value in the returned move only when `report` is set.
+    fn take_component(
+        &mut self,
+        entity: &Entity,
+        id: &ComponentId,
+        report: bool,
+    ) -> Option<ArchetypeMove> {
+        let current = *self.entities.get(entity)?;
+        if self.sparse.is_sparse(id) {
+            let mut removed = EntityRow::new();
+            let cell = self.sparse.remove(id, entity)?;
+            if report {
+                removed.add_cell(*id, cell);
+            }
+            return Some(ArchetypeMove::new(current, current).with_removed(removed));
+        }
+
+        let archetype = self.archetypes.get(&current)?;
+        if !archetype.has_component(id) {
+            return None;
+        }
+
+        if let Some(next) = archetype.edge(*id, EdgeType::Remove).copied() {
+            let mut removed = EntityRow::new();
+            self.move_row(entity, &current, &next, report.then_some(&mut removed))?;
+            return Some(ArchetypeMove::new(current, next).with_removed(removed));
+        }
+
         let (archetype, mut components) = self.remove_entity(entity)?;
         let mut removed = EntityRow::new();
-        components.remove_cell(id).map(|c| {
-            removed.add_cell(*id, c);
-        });
+        if let Some(cell) = components.remove_cell(id) {
+            if report {
+                removed.add_cell(*id, cell);
+            }
+        }
 
         let edge = EdgeId::from(id);
         let ty = MoveType::Remove(removed);
@@ -333,6 +658,80 @@ impl Archetypes {
             return None;
         }
 
+        let current = *self.entities.get(entity)?;
+        if !ids.iter().any(|id| self.sparse.is_sparse(id)) {
+            return self.remove_table_components(entity, ids);
+        }
+
+        let mut table_ids = DenseSet::new();
+        let mut removed = EntityRow::new();
+        for id in ids {
+            match self.sparse.is_sparse(&id) {
+                true => {
+                    if let Some(cell) = self.sparse.remove(&id, entity) {
+                        removed.add_cell(id, cell);
+                    }
+                }
+                false => {
+                    table_ids.insert(id);
+                }
+            }
+        }
+
+        let mut _move = match table_ids.is_empty() {
+            true => ArchetypeMove::new(current, current),
+            false => self.remove_table_components(entity, table_ids)?,
+        };
+
+        for (id, cell) in removed.drain() {
+            _move.removed.add_cell(id, cell);
+        }
+
+        Some(_move)
+    }
+}
+
+impl Archetypes {
+    fn add_table_components(
+        &mut self,
+        entity: &Entity,
+        mut row: EntityRow,
+    ) -> Option<ArchetypeMove> {
+        let current = *self.entities.get(entity)?;
+        let archetype = self.archetypes.get(&current)?;
+        row.sort();
+        let added = row.components().iter().copied().collect::<DenseSet<_>>();
+        if row
+            .components()
+            .iter()
+            .all(|id| archetype.has_component(id))
+        {
+            return self.replace_components(entity, &current, row, added);
+        }
+
+        let (archetype, mut components) = self.remove_entity(entity)?;
+        let mut removed = EntityRow::new();
+        let mut unique = DenseSet::new();
+        for (id, cell) in row.drain() {
+            if !components.contains_id(&id) {
+                unique.insert(id);
+            }
+            components.add_cell(id, cell).map(|c| {
+                removed.add_cell(id, c);
+            });
+        }
+        components.sort();
+
+        let edge = EdgeId::from(unique.keys());
+        let ty = MoveType::Add(added, removed);
+        self.move_entity(entity, &archetype, &edge, components, ty)
+    }
+
+    fn remove_table_components(
+        &mut self,
+        entity: &Entity,
+        ids: DenseSet<ComponentId>,
+    ) -> Option<ArchetypeMove> {
         let (archetype, mut components) = self.remove_entity(entity)?;
         let mut removed = EntityRow::new();
         for id in ids {
@@ -345,9 +744,63 @@ impl Archetypes {
         let ty = MoveType::Remove(removed);
         self.move_entity(entity, &archetype, &edge, components, ty)
     }
-}
 
-impl Archetypes {
+    fn mark_added(&mut self, entity: &Entity, _move: &ArchetypeMove) {
+        for id in _move.added().iter() {
+            if !_move.removed().contains_id(id) {
+                self.mark_added_id(entity, id);
+            }
+        }
+    }
+
+    fn mark_added_id(&mut self, entity: &Entity, id: &ComponentId) {
+        match self.added.get_mut(id) {
+            Some(entities) => {
+                entities.insert(*entity, self.tick);
+            }
+            None => {
+                self.added
+                    .insert(*id, HashMap::from([(*entity, self.tick)]));
+            }
+        }
+    }
+
+    /// Moves the sparse markers in `row` into sparse storage.
+    fn add_sparse(
+        &mut self,
+        entity: &Entity,
+        row: &mut EntityRow,
+    ) -> (DenseSet<ComponentId>, EntityRow) {
+        let mut added = DenseSet::new();
+        let mut removed = EntityRow::new();
+        let ids = row
+            .components()
+            .iter()
+            .copied()
+            .filter(|id| self.sparse.is_sparse(id))
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            let cell = row.remove_cell(&id).unwrap();
+            if let Some(old) = self.sparse.insert(id, *entity, cell) {
+                removed.add_cell(id, old);
+            }
+            added.insert(id);
+        }
+
+        (added, removed)
+    }
+
+    fn link(&mut self, from: &ArchetypeId, to: &ArchetypeId, edge: EdgeId) {
+        if let Some(archetype) = self.archetypes.get_mut(from) {
+            archetype.insert_edge(edge, *to, EdgeType::Add);
+        }
+
+        if let Some(archetype) = self.archetypes.get_mut(to) {
+            archetype.insert_edge(edge, *from, EdgeType::Remove);
+        }
+    }
+
     fn add_archetypes(&mut self, components: &[ComponentId], archetype_id: ArchetypeId) {
         for component in components.iter() {
             if let Some(types) = self.components.get_mut(component) {
@@ -360,6 +813,64 @@ impl Archetypes {
         }
     }
 
+    fn replace_components(
+        &mut self,
+        entity: &Entity,
+        archetype: &ArchetypeId,
+        row: EntityRow,
+        added: DenseSet<ComponentId>,
+    ) -> Option<ArchetypeMove> {
+        let tick = self.tick;
+        let target = self.archetypes.get_mut(archetype)?;
+        let removed = target.replace(entity, row, tick);
+        let _move = ArchetypeMove::new(*archetype, *archetype)
+            .with_removed(removed)
+            .with_added(added);
+
+        Some(_move)
+    }
+
+    /// Moves the entity's row between two existing archetypes column by column, returning
+    /// the target table for the caller to fill any columns only it has.
+    fn move_row(
+        &mut self,
+        entity: &Entity,
+        from: &ArchetypeId,
+        to: &ArchetypeId,
+        removed: Option<&mut EntityRow>,
+    ) -> Option<&mut EntityTable> {
+        let from_index = self.archetypes.index_of(from)?;
+        let to_index = self.archetypes.index_of(to)?;
+        if from_index == to_index {
+            return None;
+        }
+
+        let archetypes = self.archetypes.values_mut();
+        let (source, target) = match from_index < to_index {
+            true => {
+                let (head, tail) = archetypes.split_at_mut(to_index);
+                (&mut head[from_index], &mut tail[0])
+            }
+            false => {
+                let (head, tail) = archetypes.split_at_mut(from_index);
+                (&mut tail[0], &mut head[to_index])
+            }
+        };
+
+        if !source.table.move_entity(entity, &mut target.table, removed) {
+            return None;
+        }
+
+        self.entities.insert(*entity, *to);
+
+        #[cfg(test)]
+        {
+            self.moves += 1;
+        }
+
+        Some(&mut target.table)
+    }
+
     fn next_archetype(
         &mut self,
         id: &ArchetypeId,
@@ -379,9 +890,16 @@ impl Archetypes {
         ty: EdgeType,
     ) -> ArchetypeId {
         let id = ArchetypeId::new(row.components());
+        let tick = self.tick;
+        if let Some(next) = self.archetypes.get_mut(&id) {
+            next.insert(entity, row, tick);
+            next.insert_edge(*edge, *from, ty.reverse());
+            return id;
+        }
+
         self.add_archetypes(row.components(), id);
 
-        let mut next = Archetype::new(id, row.into_table(*entity));
+        let mut next = Archetype::new(id, row.into_table(*entity, tick));
         let reverse = ty.reverse();
         next.insert_edge(*edge, *from, reverse);
 
@@ -402,8 +920,9 @@ impl Archetypes {
             MoveType::Remove(removed) => (DenseSet::new(), removed, EdgeType::Remove),
         };
 
+        let tick = self.tick;
         let next = if let Some(next) = self.next_archetype(from, edge, ty) {
-            next.insert(entity, components.into());
+            next.insert(entity, components, tick);
             next.id()
         } else {
             let next_id = self.new_edge(entity, from, &edge, components, ty);
@@ -419,6 +938,11 @@ impl Archetypes {
 
         self.entities.insert(*entity, next);
 
+        #[cfg(test)]
+        {
+            self.moves += 1;
+        }
+
         Some(_move)
     }
 }
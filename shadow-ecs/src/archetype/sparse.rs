@@ -0,0 +1,99 @@
+use super::table::EntityRow;
+use crate::core::{ColumnCell, ComponentId, DenseMap, Entity};
+use std::collections::HashMap;
+
+/// Marker components stored beside the archetype tables, keyed by entity, so adding
+/// or removing them never moves an entity between archetypes.
+///
+/// Queries can't skip whole archetypes for sparse markers. `With` and `Not` filters
+/// on them are checked per entity, which is slower for queries over many entities.
+pub struct SparseMarkers {
+    markers: DenseMap<ComponentId, HashMap<Entity, ColumnCell>>,
+}
+
+impl SparseMarkers {
+    pub fn new() -> Self {
+        Self {
+            markers: DenseMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, id: ComponentId) {
+        if !self.markers.contains(&id) {
+            self.markers.insert(id, HashMap::new());
+        }
+    }
+
+    /// The number of registered markers.
+    pub fn len(&self) -> usize {
+        self.markers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty()
+    }
+
+    pub fn is_sparse(&self, id: &ComponentId) -> bool {
+        self.markers.contains(id)
+    }
+
+    pub fn contains(&self, id: &ComponentId, entity: &Entity) -> bool {
+        self.markers
+            .get(id)
+            .is_some_and(|entities| entities.contains_key(entity))
+    }
+
+    /// Entities that have every marker in `with` and none in `without`.
+    pub fn matches(&self, entity: &Entity, with: &[ComponentId], without: &[ComponentId]) -> bool {
+        with.iter().all(|id| self.contains(id, entity))
+            && !without.iter().any(|id| self.contains(id, entity))
+    }
+
+    pub fn insert(
+        &mut self,
+        id: ComponentId,
+        entity: Entity,
+        cell: ColumnCell,
+    ) -> Option<ColumnCell> {
+        self.markers.get_mut(&id)?.insert(entity, cell)
+    }
+
+    pub fn remove(&mut self, id: &ComponentId, entity: &Entity) -> Option<ColumnCell> {
+        self.markers.get_mut(id)?.remove(entity)
+    }
+
+    pub fn remove_entity(&mut self, entity: &Entity) -> EntityRow {
+        let mut row = EntityRow::new();
+        for (id, entities) in self.markers.iter_mut() {
+            if let Some(cell) = entities.remove(entity) {
+                row.add_cell(*id, cell);
+            }
+        }
+
+        row
+    }
+
+    /// Every marker id paired with each entity that has it.
+    pub fn entries(&self) -> impl Iterator<Item = (&ComponentId, &Entity)> {
+        self.markers
+            .iter()
+            .flat_map(|(id, entities)| entities.keys().map(move |entity| (id, entity)))
+    }
+
+    pub fn merge(&mut self, mut other: SparseMarkers, map: &DenseMap<Entity, Entity>) {
+        for (id, mut entities) in other.markers.drain() {
+            self.register(id);
+            let markers = self.markers.get_mut(&id).unwrap();
+            for (entity, cell) in entities.drain() {
+                let entity = map.get(&entity).copied().unwrap_or(entity);
+                markers.insert(entity, cell);
+            }
+        }
+    }
+}
+
+impl Default for SparseMarkers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
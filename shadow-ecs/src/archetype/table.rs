@@ -1,18 +1,27 @@
 use crate::core::{
-    Column, ColumnCell, ColumnKey, Component, ComponentId, DenseMap, DenseSet, Entity, Row,
+    Column, ColumnCell, ColumnKey, Component, ComponentId, DenseMap, DenseSet, Entity, Row, Tick,
 };
+use std::sync::atomic::{AtomicU32, Ordering};
 
 pub struct EntityRow {
     components: DenseMap<ComponentId, ColumnCell>,
+    ticks: DenseMap<ComponentId, Tick>,
 }
 
 impl EntityRow {
     pub fn new() -> Self {
         EntityRow {
             components: DenseMap::new(),
+            ticks: DenseMap::new(),
         }
     }
 
+    pub fn from_components(components: impl ComponentSet) -> Self {
+        let mut row = EntityRow::new();
+        components.add_to(&mut row);
+        row
+    }
+
     pub fn components(&self) -> &[ComponentId] {
         self.components.keys()
     }
@@ -30,24 +39,34 @@ impl EntityRow {
     }
 
     pub fn add_component<C: Component>(&mut self, component: C) -> Option<ColumnCell> {
-        self.components
-            .insert(ComponentId::new::<C>(), ColumnCell::from(component))
+        self.add_cell(ComponentId::new::<C>(), ColumnCell::from(component))
     }
 
     pub fn remove_component<C: Component>(&mut self) -> Option<C> {
-        self.components
-            .remove(&ComponentId::new::<C>())
+        self.remove_cell(&ComponentId::new::<C>())
             .and_then(|cell| Some(cell.take()))
     }
 
+    /// Adding a cell clears its change tick, so the table stamps it as changed.
     pub fn add_cell(&mut self, id: ComponentId, cell: ColumnCell) -> Option<ColumnCell> {
+        self.ticks.remove(&id);
         self.components.insert(id, cell)
     }
 
     pub fn remove_cell(&mut self, id: &ComponentId) -> Option<ColumnCell> {
+        self.ticks.remove(id);
         self.components.remove(id)
     }
 
+    /// The tick a component last changed, kept while the entity moves between tables.
+    pub fn tick(&self, id: &ComponentId) -> Option<Tick> {
+        self.ticks.get(id).copied()
+    }
+
+    pub fn set_tick(&mut self, id: ComponentId, tick: Tick) {
+        self.ticks.insert(id, tick);
+    }
+
     pub fn contains<C: Component>(&self) -> bool {
         self.components.contains(&ComponentId::new::<C>())
     }
@@ -82,15 +101,20 @@ impl EntityRow {
 
     pub fn clear(&mut self) {
         self.components.clear();
+        self.ticks.clear();
     }
 
-    pub fn into_table(mut self, entity: Entity) -> EntityTable {
+    pub fn into_table(mut self, entity: Entity, tick: Tick) -> EntityTable {
         let mut builder = TableBuilder::new();
         for (id, cell) in self.components.drain() {
             builder.add_column(id, Column::from(cell));
         }
         let mut table = builder.build();
         table.rows.insert(entity);
+        for (id, ticks) in table.ticks.iter_mut() {
+            let tick = self.ticks.get(id).copied().unwrap_or(tick);
+            ticks.push(AtomicU32::new(tick.get()));
+        }
 
         table
     }
@@ -105,6 +129,44 @@ impl EntityRow {
     }
 }
 
+pub trait ComponentSet: Send + Sync + 'static {
+    fn add_to(self, row: &mut EntityRow);
+    fn table_builder() -> TableBuilder
+    where
+        Self: Sized;
+}
+
+macro_rules! impl_component_set {
+    ($($name:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<$($name: Component),*> ComponentSet for ($($name,)*) {
+            fn add_to(self, row: &mut EntityRow) {
+                let ($($name,)*) = self;
+                $(
+                    if row.add_component($name).is_some() {
+                        panic!("Duplicate component: {}", std::any::type_name::<$name>());
+                    }
+                )*
+            }
+
+            fn table_builder() -> TableBuilder {
+                let mut builder = TableBuilder::new();
+                $(builder.add_component::<$name>();)*
+                builder
+            }
+        }
+    };
+}
+
+impl_component_set!(A);
+impl_component_set!(A, B);
+impl_component_set!(A, B, C);
+impl_component_set!(A, B, C, D);
+impl_component_set!(A, B, C, D, E);
+impl_component_set!(A, B, C, D, E, F);
+impl_component_set!(A, B, C, D, E, F, G);
+impl_component_set!(A, B, C, D, E, F, G, H);
+
 impl From<Row> for EntityRow {
     fn from(mut row: Row) -> Self {
         let mut components = DenseMap::new();
@@ -112,7 +174,10 @@ impl From<Row> for EntityRow {
             components.insert(ComponentId::raw(*id), cell);
         });
 
-        EntityRow { components }
+        EntityRow {
+            components,
+            ticks: DenseMap::new(),
+        }
     }
 }
 
@@ -191,18 +256,39 @@ impl TableBuilder {
         self.components.remove(id);
     }
 
+    /// A builder with empty columns for the `ids` this builder has.
+    pub fn subset(&self, ids: &[ComponentId]) -> TableBuilder {
+        let mut builder = TableBuilder::new();
+        for id in ids {
+            if let Some(column) = self.components.get(id) {
+                builder.add_column(*id, Column::copy(column));
+            }
+        }
+
+        builder
+    }
+
     pub fn build(mut self) -> EntityTable {
         self.components.sort(|a, b| a.cmp(b));
+        let mut ticks = DenseMap::new();
+        for id in self.components.keys() {
+            ticks.insert(*id, Vec::new());
+        }
+
         EntityTable {
             rows: DenseSet::new(),
             components: self.components,
+            ticks,
         }
     }
 }
 
+/// Rows of entities with the same components. Each cell has the tick it last
+/// changed, set when it is added, replaced or borrowed mutably.
 pub struct EntityTable {
     rows: DenseSet<Entity>,
     components: DenseMap<ComponentId, Column>,
+    ticks: DenseMap<ComponentId, Vec<AtomicU32>>,
 }
 
 impl EntityTable {
@@ -232,14 +318,49 @@ impl EntityTable {
         column.get(index)
     }
 
-    pub fn get_component_mut<C: Component>(&self, entity: &Entity) -> Option<&mut C> {
-        let column = self.components.get(&ComponentId::new::<C>())?;
+    /// Borrows the component mutably, marking it changed at `tick`.
+    pub fn get_component_mut<C: Component>(&self, entity: &Entity, tick: Tick) -> Option<&mut C> {
+        let id = ComponentId::new::<C>();
+        let column = self.components.get(&id)?;
         let index = self.rows.index_of(entity)?;
+        self.ticks[&id][index].store(tick.get(), Ordering::Relaxed);
         column.get_mut(index)
     }
 
-    pub fn add_entity(&mut self, entity: Entity, mut row: EntityRow) {
+    /// Bytes reserved by the columns and their change ticks.
+    pub fn memory(&self) -> usize {
+        let columns = self.components.values().iter().map(Column::allocated);
+        let ticks = self
+            .ticks
+            .values()
+            .iter()
+            .map(|ticks| ticks.capacity() * std::mem::size_of::<AtomicU32>());
+
+        columns.chain(ticks).sum()
+    }
+
+    pub fn changed_tick(&self, id: &ComponentId, entity: &Entity) -> Option<Tick> {
+        let index = self.rows.index_of(entity)?;
+        let tick = self.ticks.get(id)?.get(index)?;
+        Some(Tick::new(tick.load(Ordering::Relaxed)))
+    }
+
+    /// Clamps every change tick to at most `Tick::MAX_AGE` behind `current`.
+    pub fn clamp_ticks(&mut self, current: Tick) {
+        for ticks in self.ticks.values_mut() {
+            for cell in ticks.iter_mut() {
+                let mut tick = Tick::new(*cell.get_mut());
+                if tick.clamp(current) {
+                    *cell.get_mut() = tick.get();
+                }
+            }
+        }
+    }
+
+    /// Adds the entity's row. Cells without a tick from a previous table are stamped with `tick`.
+    pub fn add_entity(&mut self, entity: Entity, mut row: EntityRow, tick: Tick) {
         self.rows.insert(entity);
+        let ticks = std::mem::take(&mut row.ticks);
         for (id, cell) in row.drain() {
             let column = match self.components.get_mut(&id) {
                 Some(column) => column,
@@ -247,6 +368,103 @@ impl EntityTable {
             };
 
             column.push_cell(cell);
+            let tick = ticks.get(&id).copied().unwrap_or(tick);
+            self.ticks[&id].push(AtomicU32::new(tick.get()));
+        }
+    }
+
+    pub fn replace_cell(
+        &mut self,
+        entity: &Entity,
+        id: &ComponentId,
+        cell: ColumnCell,
+        tick: Tick,
+    ) -> Option<ColumnCell> {
+        let index = self.rows.index_of(entity)?;
+        let column = self.components.get_mut(id)?;
+        *self.ticks[id][index].get_mut() = tick.get();
+        Some(column.replace_cell(index, cell))
+    }
+
+    pub fn remap(&mut self, map: &DenseMap<Entity, Entity>) {
+        let rows = self
+            .rows
+            .drain()
+            .map(|entity| map.get(&entity).copied().unwrap_or(entity))
+            .collect::<Vec<_>>();
+        self.rows.extend(rows);
+    }
+
+    /// Marks every cell changed at `tick`.
+    pub fn mark_changed(&mut self, tick: Tick) {
+        for ticks in self.ticks.values_mut() {
+            ticks
+                .iter_mut()
+                .for_each(|cell| *cell.get_mut() = tick.get());
+        }
+    }
+
+    /// Moves all rows of `other` to the end of this table. Both tables must share the same components.
+    pub fn append(&mut self, mut other: EntityTable) {
+        self.rows.extend(other.rows.drain());
+        for (id, column) in other.components.drain() {
+            if let Some(existing) = self.components.get_mut(&id) {
+                existing.extend(column);
+            }
+        }
+
+        for (id, ticks) in other.ticks.drain() {
+            if let Some(existing) = self.ticks.get_mut(&id) {
+                existing.extend(ticks);
+            }
+        }
+    }
+
+    /// Moves the entity's cells straight into the matching columns of `target`, filling
+    /// its old row with the last one. Cells `target` has no column for go to `removed`,
+    /// or are dropped in place without it. Columns only `target` has are left for the
+    /// caller to fill with `push_component`.
+    pub(crate) fn move_entity(
+        &mut self,
+        entity: &Entity,
+        target: &mut EntityTable,
+        mut removed: Option<&mut EntityRow>,
+    ) -> bool {
+        let Some(index) = self.rows.index_of(entity) else {
+            return false;
+        };
+
+        self.rows.swap_remove_at(index);
+        for (id, column) in self.components.iter_mut() {
+            let tick = self.ticks[id].swap_remove(index);
+            match target.components.get_mut(id) {
+                Some(next) => {
+                    column.swap_move_cell(index, next);
+                    target.ticks[id].push(tick);
+                }
+                None => match removed.as_deref_mut() {
+                    Some(row) => {
+                        row.add_cell(*id, column.swap_remoe_cell(index));
+                        row.set_tick(*id, Tick::new(tick.into_inner()));
+                    }
+                    None => column.swap_drop_cell(index),
+                },
+            }
+        }
+
+        target.rows.insert(*entity);
+        true
+    }
+
+    pub(crate) fn push_component<C: Component>(
+        &mut self,
+        id: &ComponentId,
+        component: C,
+        tick: Tick,
+    ) {
+        if let Some(column) = self.components.get_mut(id) {
+            column.push(component);
+            self.ticks[id].push(AtomicU32::new(tick.get()));
         }
     }
 
@@ -256,6 +474,9 @@ impl EntityTable {
         for (id, column) in self.components.iter_mut() {
             let cell = column.remove_cell(index);
             row.add_cell(*id, cell);
+
+            let tick = Tick::new(self.ticks[id].remove(index).into_inner());
+            row.set_tick(*id, tick);
         }
 
         Some(row)
@@ -61,7 +61,7 @@ impl Allocator {
         if let Some(gen) = self.generations.get(id) {
             if *gen == id.gen {
                 self.free.push(**id);
-                self.generations.insert(**id, gen + 1);
+                self.generations.insert(**id, gen.wrapping_add(1));
             }
         }
     }
@@ -1,4 +1,4 @@
-use super::internal::DenseMap;
+use super::{internal::DenseMap, StableNames};
 use std::{
     alloc::Layout,
     any::{Any, TypeId},
@@ -7,7 +7,17 @@ use std::{
     sync::Arc,
 };
 
-pub trait Component: Send + Sync + 'static {}
+pub trait Component: Send + Sync + 'static {
+    /// The name used to refer to this component across builds. Defaults to the type path.
+    fn stable_name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Former stable names that still resolve to this component.
+    fn aliases() -> &'static [&'static str] {
+        &[]
+    }
+}
 impl Component for () {}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -89,23 +99,44 @@ impl ComponentMeta {
 
 pub struct Components {
     metas: DenseMap<ComponentId, ComponentMeta>,
+    names: StableNames<ComponentId>,
 }
 
 impl Components {
     pub fn new() -> Self {
         Components {
             metas: DenseMap::new(),
+            names: StableNames::new(),
         }
     }
 
     pub fn register<C: Component>(&mut self) -> ComponentId {
         let id = ComponentId::new::<C>();
         let meta = ComponentMeta::new::<C>();
+        self.names
+            .register(id, meta.name(), C::stable_name(), C::aliases());
         self.metas.insert(id, meta);
 
         id
     }
 
+    pub fn stable_name(&self, id: &ComponentId) -> Option<&'static str> {
+        self.names.name(id)
+    }
+
+    /// Resolves a stable name, or an alias of one, to the registered component.
+    pub fn id_by_name(&self, name: &str) -> Option<ComponentId> {
+        self.names.id(name)
+    }
+
+    pub fn contains(&self, id: &ComponentId) -> bool {
+        self.metas.contains(id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ComponentId, &ComponentMeta)> {
+        self.metas.iter()
+    }
+
     pub fn meta(&self, id: &ComponentId) -> &ComponentMeta {
         self.metas.get(id).expect("Component not found")
     }
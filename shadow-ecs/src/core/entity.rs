@@ -1,4 +1,7 @@
-use super::allocator::{Allocator, GenId};
+use super::{
+    allocator::{Allocator, GenId},
+    internal::DenseMap,
+};
 use std::collections::HashMap;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
@@ -36,6 +39,10 @@ impl Into<GenId> for Entity {
 pub struct EntityNode {
     parent: Option<Entity>,
     children: Vec<Entity>,
+    enabled: bool,
+    /// True if any ancestor is disabled. Kept up to date by `Entities` when a
+    /// flag or a parent changes, so queries don't walk the hierarchy.
+    inherited_disabled: bool,
 }
 
 impl EntityNode {
@@ -43,9 +50,23 @@ impl EntityNode {
         EntityNode {
             parent,
             children: vec![],
+            enabled: true,
+            inherited_disabled: false,
         }
     }
 
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.enabled && !self.inherited_disabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
     pub fn parent(&self) -> Option<&Entity> {
         self.parent.as_ref()
     }
@@ -71,6 +92,8 @@ impl EntityNode {
 pub struct Entities {
     allocator: Allocator,
     nodes: HashMap<Entity, EntityNode>,
+    /// Entities whose own flag is disabled. While it's zero every entity is active.
+    disabled: usize,
 }
 
 impl Entities {
@@ -78,6 +101,7 @@ impl Entities {
         Entities {
             allocator: Allocator::new(),
             nodes: HashMap::new(),
+            disabled: 0,
         }
     }
 
@@ -93,16 +117,29 @@ impl Entities {
     }
 
     pub fn despawn(&mut self, entity: &Entity) -> Vec<Entity> {
+        let parent = self.parent(entity).copied();
+        if let Some(parent) = parent.and_then(|parent| self.nodes.get_mut(&parent)) {
+            parent.remove_child(*entity);
+        }
+
         let mut dead = vec![];
-        self.set_parent(entity, None);
+        self.remove_tree(entity, &mut dead);
+        dead
+    }
+
+    /// Removes `entity` and its descendants. The whole tree goes, so inherited
+    /// flags don't need updating.
+    fn remove_tree(&mut self, entity: &Entity, dead: &mut Vec<Entity>) {
         if let Some(node) = self.nodes.remove(entity) {
+            if !node.is_enabled() {
+                self.disabled -= 1;
+            }
+
             dead.push(*entity);
             for child in node.children() {
-                dead.append(&mut self.despawn(child));
+                self.remove_tree(child, dead);
             }
         }
-
-        dead
     }
 
     pub fn set_parent(&mut self, child: &Entity, parent: Option<&Entity>) -> Option<Entity> {
@@ -124,6 +161,7 @@ impl Entities {
             self.nodes.get_mut(child).unwrap().set_parent(None);
         }
 
+        self.update_inherited(child);
         old_parent
     }
 
@@ -138,7 +176,8 @@ impl Entities {
         }
 
         self.nodes.get_mut(parent).unwrap().add_child(*child);
-        self.nodes.get_mut(child).unwrap().set_parent(Some(*parent))
+        self.nodes.get_mut(child).unwrap().set_parent(Some(*parent));
+        self.update_inherited(child);
     }
 
     pub fn remove_child(&mut self, parent: &Entity, child: &Entity) -> bool {
@@ -153,6 +192,7 @@ impl Entities {
 
         self.nodes.get_mut(parent).unwrap().remove_child(*child);
         self.nodes.get_mut(child).unwrap().set_parent(None);
+        self.update_inherited(child);
 
         true
     }
@@ -161,6 +201,113 @@ impl Entities {
         self.nodes.keys()
     }
 
+    pub fn contains(&self, entity: &Entity) -> bool {
+        self.nodes.contains_key(entity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Moves every entity in `other` into this set under newly allocated ids,
+    /// keeping hierarchy and enabled flags. Returns the old to new id mapping.
+    pub fn merge(&mut self, other: Entities) -> DenseMap<Entity, Entity> {
+        let mut map = DenseMap::new();
+        for entity in other.nodes.keys() {
+            map.insert(*entity, Entity::from(self.allocator.allocate()));
+        }
+
+        for (entity, node) in other.nodes {
+            let remap = |entity: &Entity| map.get(entity).copied();
+            let mut merged = EntityNode::new(node.parent().and_then(remap));
+            merged.children = node.children().iter().filter_map(remap).collect();
+            merged.enabled = node.is_enabled();
+            merged.inherited_disabled = node.inherited_disabled;
+            if !merged.enabled {
+                self.disabled += 1;
+            }
+
+            if let Some(entity) = remap(&entity) {
+                self.nodes.insert(entity, merged);
+            }
+        }
+
+        map
+    }
+
+    /// Sets the entity's own enabled flag. Returns false if the entity
+    /// doesn't exist or the flag was already set to `enabled`.
+    pub fn set_enabled(&mut self, entity: &Entity, enabled: bool) -> bool {
+        match self.nodes.get_mut(entity) {
+            Some(node) if node.is_enabled() != enabled => {
+                let active = node.is_active();
+                node.set_enabled(enabled);
+                match enabled {
+                    true => self.disabled -= 1,
+                    false => self.disabled += 1,
+                }
+
+                if node.is_active() != active {
+                    self.update_children(entity);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_enabled(&self, entity: &Entity) -> bool {
+        self.nodes.get(entity).is_some_and(|n| n.is_enabled())
+    }
+
+    /// True if any entity is disabled, so queries have to check each row.
+    pub fn any_disabled(&self) -> bool {
+        self.disabled > 0
+    }
+
+    /// An entity is active when it and all of its ancestors are enabled.
+    pub fn is_active(&self, entity: &Entity) -> bool {
+        self.nodes.get(entity).is_some_and(|n| n.is_active())
+    }
+
+    /// Recomputes the inherited disabled flag of `entity` from its parent. The walk
+    /// only continues into descendants whose active state changed.
+    fn update_inherited(&mut self, entity: &Entity) {
+        let inherited_disabled = self
+            .parent(entity)
+            .and_then(|parent| self.nodes.get(parent))
+            .is_some_and(|parent| !parent.is_active());
+
+        let Some(node) = self.nodes.get_mut(entity) else {
+            return;
+        };
+
+        if node.inherited_disabled == inherited_disabled {
+            return;
+        }
+
+        let active = node.is_active();
+        node.inherited_disabled = inherited_disabled;
+        if node.is_active() != active {
+            self.update_children(entity);
+        }
+    }
+
+    fn update_children(&mut self, entity: &Entity) {
+        let count = self
+            .nodes
+            .get(entity)
+            .map_or(0, |node| node.children().len());
+        for index in 0..count {
+            let child = self.nodes[entity].children()[index];
+            self.update_inherited(&child);
+        }
+    }
+
     pub fn children(&self, entity: &Entity) -> Option<&[Entity]> {
         self.nodes.get(entity).and_then(|n| Some(n.children()))
     }
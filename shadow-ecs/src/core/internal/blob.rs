@@ -84,6 +84,11 @@ impl Blob {
         self.capacity
     }
 
+    /// Bytes reserved for elements, including unused capacity.
+    pub fn allocated(&self) -> usize {
+        self.data.capacity()
+    }
+
     pub fn drop(&self) -> Option<&fn(*mut u8)> {
         self.drop.as_ref()
     }
@@ -254,6 +259,36 @@ impl Blob {
         }
     }
 
+    pub fn replace_blob(&mut self, index: usize, mut blob: Blob) -> Blob {
+        if blob.aligned_layout != self.aligned_layout || blob.layout != self.layout {
+            panic!("Layouts are different")
+        }
+
+        if index >= self.length {
+            panic!("Index out of bounds.")
+        }
+
+        let size = self.aligned_layout.size();
+        let start = index * size;
+        let replaced = blob.data.drain(..size).collect::<Vec<_>>();
+        let data = self
+            .data
+            .splice(start..start + size, replaced)
+            .collect::<Vec<_>>();
+
+        blob.length = 0;
+        blob.capacity = 0;
+
+        Blob {
+            aligned_layout: self.aligned_layout,
+            layout: self.layout,
+            drop: self.drop,
+            capacity: 1,
+            length: 1,
+            data,
+        }
+    }
+
     pub fn remove_blob(&mut self, index: usize) -> Blob {
         if index >= self.length {
             panic!("Index out of bounds.")
@@ -289,12 +324,13 @@ impl Blob {
 
         let start = (self.length - 1) * self.aligned_layout.size();
         let end = start + self.aligned_layout.size();
-        let data = self.data.drain(start..end).collect::<Vec<_>>();
+        let mut data = self.data.drain(start..end).collect::<Vec<_>>();
 
-        let start = index * self.aligned_layout.size();
-        let end = start + self.aligned_layout().size();
-
-        let data = self.data.splice(start..end, data).collect::<Vec<_>>();
+        if index + 1 < self.length {
+            let start = index * self.aligned_layout.size();
+            let end = start + self.aligned_layout().size();
+            data = self.data.splice(start..end, data).collect::<Vec<_>>();
+        }
 
         self.length -= 1;
         unsafe {
@@ -315,6 +351,44 @@ impl Blob {
         }
     }
 
+    /// Moves the element at `index` to the end of `blob` without an intermediate cell,
+    /// filling the gap with the last element. The capacity it leaves behind is kept, as
+    /// rows tend to move back and forth.
+    pub fn swap_move_to(&mut self, index: usize, blob: &mut Blob) {
+        if blob.aligned_layout != self.aligned_layout || blob.layout != self.layout {
+            panic!("Layouts are different")
+        }
+
+        if index >= self.length {
+            panic!("Index out of bounds.")
+        }
+
+        if blob.length == blob.capacity {
+            blob.reserve(blob.capacity.max(1));
+        }
+
+        unsafe {
+            let size = self.aligned_layout.size();
+            std::ptr::copy_nonoverlapping(self.offset(index), blob.offset(blob.length), size);
+            blob.length += 1;
+            blob.data.set_len(blob.length * size);
+            self.swap_close(index);
+        }
+    }
+
+    /// Drops the element at `index` in place, filling the gap with the last element.
+    pub fn swap_drop(&mut self, index: usize) {
+        if index >= self.length {
+            panic!("Index out of bounds.")
+        }
+
+        if let Some(drop) = self.drop {
+            drop(self.offset(index));
+        }
+
+        unsafe { self.swap_close(index) }
+    }
+
     pub fn clear(&mut self) {
         if let Some(drop) = self.drop {
             for index in 0..self.length {
@@ -367,6 +441,18 @@ impl Blob {
 }
 
 impl Blob {
+    /// Moves the last element over `index`, which must already be moved out or dropped.
+    unsafe fn swap_close(&mut self, index: usize) {
+        let size = self.aligned_layout.size();
+        let last = self.length - 1;
+        if index < last {
+            std::ptr::copy_nonoverlapping(self.offset(last), self.offset(index), size);
+        }
+
+        self.length = last;
+        self.data.set_len(self.length * size);
+    }
+
     fn offset(&self, offset: usize) -> *mut u8 {
         let count: isize = (offset * self.aligned_layout.size()).try_into().unwrap();
         let bounds: isize = (self.capacity * self.aligned_layout.size()
@@ -384,7 +470,7 @@ impl From<BlobCell> for Blob {
     fn from(mut cell: BlobCell) -> Self {
         let data = std::mem::take(&mut cell.data);
         let layout = cell.layout;
-        let drop = cell.drop;
+        let drop = cell.drop.take();
         let aligned_layout = layout.pad_to_align();
 
         Self {
@@ -406,7 +492,9 @@ impl From<Blob> for BlobCell {
 
         let data = std::mem::take(&mut blob.data);
         let layout = blob.layout;
-        let drop = blob.drop;
+        let drop = blob.drop.take();
+        blob.length = 0;
+        blob.capacity = 0;
 
         Self { data, layout, drop }
     }
@@ -431,6 +519,7 @@ impl BlobCell {
             let ptr = std::ptr::addr_of!(value) as *mut u8;
             let mut data = Vec::with_capacity(layout.size());
             std::ptr::copy(ptr, data.as_mut_ptr(), layout.size());
+            data.set_len(layout.size());
             std::mem::forget(value);
             data
         };
@@ -1,5 +1,5 @@
 use super::hash_value;
-use std::{collections::HashMap, hash::Hash};
+use std::{borrow::Borrow, collections::HashMap, hash::Hash};
 
 pub struct DenseMap<K: Hash + Eq, V> {
     keys: Vec<K>,
@@ -16,17 +16,26 @@ impl<K: Hash + Eq, V> DenseMap<K, V> {
         }
     }
 
-    pub fn contains(&self, key: &K) -> bool {
+    pub fn contains<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+    {
         let key = hash_value(key);
         self.map.contains_key(&key)
     }
 
-    pub fn get(&self, key: &K) -> Option<&V> {
+    pub fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
         let key = hash_value(key);
         self.map.get(&key).map(|&index| &self.values[index])
     }
 
-    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+    pub fn get_mut<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
         let key = hash_value(key);
         self.map.get(&key).map(|&index| &mut self.values[index])
     }
@@ -85,7 +94,10 @@ impl<K: Hash + Eq, V> DenseMap<K, V> {
         }
     }
 
-    pub fn remove(&mut self, key: &K) -> Option<V> {
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
         let hash = hash_value(key);
         if let Some(index) = self.map.remove(&hash) {
             let value = self.values.remove(index);
@@ -176,15 +188,17 @@ impl<K: Hash + Eq, V> DenseMap<K, V> {
     }
 
     pub fn sort(&mut self, mut sorter: impl FnMut(&K, &K) -> std::cmp::Ordering) {
-        let mut keys = std::mem::take(&mut self.keys);
+        let keys = std::mem::take(&mut self.keys);
         let values = std::mem::take(&mut self.values);
-        keys.sort_by(|a, b| sorter(a, b));
-        for (index, key) in keys.iter().enumerate() {
-            let hash = hash_value(key);
-            self.map.insert(hash, index);
+        let mut entries = keys.into_iter().zip(values).collect::<Vec<_>>();
+        entries.sort_by(|(a, _), (b, _)| sorter(a, b));
+
+        self.map.clear();
+        for (index, (key, value)) in entries.into_iter().enumerate() {
+            self.map.insert(hash_value(&key), index);
+            self.keys.push(key);
+            self.values.push(value);
         }
-        self.keys = keys;
-        self.values = values;
     }
 
     pub fn iter(&self) -> std::iter::Zip<std::slice::Iter<K>, std::slice::Iter<V>> {
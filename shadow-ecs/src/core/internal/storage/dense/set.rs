@@ -57,7 +57,8 @@ impl<K: Hash + Eq> DenseSet<K> {
     pub fn remove(&mut self, value: &K) -> Option<usize> {
         let key = hash_value(value);
         if let Some(index) = self.map.remove(&key) {
-            for index in index..(self.keys.len().max(index)) {
+            self.keys.remove(index);
+            for index in index..self.keys.len() {
                 let key = hash_value(&self.keys[index]);
                 self.map.insert(key, index);
             }
@@ -92,8 +93,10 @@ impl<K: Hash + Eq> DenseSet<K> {
 
     pub fn swap_remove_at(&mut self, index: usize) -> K {
         let value = self.keys.swap_remove(index);
-        let key = hash_value(&self.keys[index]);
-        self.map.insert(key, index);
+        self.map.remove(&hash_value(&value));
+        if let Some(moved) = self.keys.get(index) {
+            self.map.insert(hash_value(moved), index);
+        }
         value
     }
 
@@ -88,6 +88,10 @@ impl Column {
         self.data.extend(column.data)
     }
 
+    pub fn allocated(&self) -> usize {
+        self.data.allocated()
+    }
+
     pub fn remove<T: 'static>(&mut self, index: usize) -> T {
         self.data.remove(index)
     }
@@ -112,11 +116,25 @@ impl Column {
         self.data.insert_blob(index, cell.data.into())
     }
 
+    pub fn replace_cell(&mut self, index: usize, cell: ColumnCell) -> ColumnCell {
+        let data = self.data.replace_blob(index, cell.data.into()).into();
+        ColumnCell { data }
+    }
+
     pub fn remove_cell(&mut self, index: usize) -> ColumnCell {
         let data = self.data.remove_blob(index).into();
         ColumnCell { data }
     }
 
+    /// Moves the cell at `index` to the end of `column`, filling the gap with the last cell.
+    pub fn swap_move_cell(&mut self, index: usize, column: &mut Column) {
+        self.data.swap_move_to(index, &mut column.data)
+    }
+
+    pub fn swap_drop_cell(&mut self, index: usize) {
+        self.data.swap_drop(index)
+    }
+
     pub fn swap_remoe_cell(&mut self, index: usize) -> ColumnCell {
         let data = self.data.swap_remove_blob(index).into();
         ColumnCell { data }
@@ -2,9 +2,13 @@ pub mod allocator;
 pub mod component;
 pub mod entity;
 pub mod internal;
+pub mod name;
 pub mod resource;
+pub mod tick;
 
 pub use component::*;
 pub use entity::*;
 pub use internal::storage::*;
+pub use name::*;
 pub use resource::*;
+pub use tick::*;
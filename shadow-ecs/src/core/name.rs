@@ -0,0 +1,122 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// Maps stable names, and the aliases of renamed types, to ids. Unlike `TypeId` and
+/// the ids hashed from it, stable names don't change across builds.
+pub struct StableNames<K> {
+    ids: HashMap<&'static str, K>,
+    names: HashMap<K, (&'static str, &'static str)>,
+}
+
+impl<K: Copy + Eq + Hash> StableNames<K> {
+    pub fn new() -> Self {
+        Self {
+            ids: HashMap::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// Panics if `name` or one of `aliases` is already used by a different type.
+    pub fn register(
+        &mut self,
+        id: K,
+        type_name: &'static str,
+        name: &'static str,
+        aliases: &[&'static str],
+    ) {
+        for name in std::iter::once(&name).chain(aliases) {
+            match self.ids.get(name) {
+                Some(existing) if *existing != id => {
+                    let (_, other) = self.names[existing];
+                    panic!(
+                        "Stable name {} is used by both {} and {}",
+                        name, other, type_name
+                    );
+                }
+                _ => {
+                    self.ids.insert(name, id);
+                }
+            }
+        }
+
+        self.names.insert(id, (name, type_name));
+    }
+
+    /// Resolves a stable name or an alias.
+    pub fn id(&self, name: &str) -> Option<K> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: &K) -> Option<&'static str> {
+        self.names.get(id).map(|(name, _)| *name)
+    }
+}
+
+impl<K: Copy + Eq + Hash> Default for StableNames<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::{Component, ComponentId, Resource, ResourceType},
+        world::World,
+    };
+
+    struct Health;
+    impl Component for Health {}
+
+    struct Armor;
+    impl Component for Armor {
+        fn stable_name() -> &'static str {
+            "combat::Armor"
+        }
+
+        fn aliases() -> &'static [&'static str] {
+            &["game::Shield"]
+        }
+    }
+
+    struct Shield;
+    impl Component for Shield {
+        fn stable_name() -> &'static str {
+            "game::Shield"
+        }
+    }
+
+    struct Score;
+    impl Resource for Score {
+        fn stable_name() -> &'static str {
+            "game::Score"
+        }
+    }
+
+    #[test]
+    fn stable_names() {
+        let mut world = World::new();
+        world.register::<Health>().register::<Armor>();
+        world.add_resource(Score);
+
+        let components = world.components();
+        let health = ComponentId::new::<Health>();
+        let name = components.stable_name(&health).unwrap();
+        assert_eq!(name, std::any::type_name::<Health>());
+        assert_eq!(components.id_by_name(name), Some(health));
+
+        let armor = ComponentId::new::<Armor>();
+        assert_eq!(components.stable_name(&armor), Some("combat::Armor"));
+        assert_eq!(components.id_by_name("combat::Armor"), Some(armor));
+        assert_eq!(components.id_by_name("game::Shield"), Some(armor));
+
+        let score = world.resource_type("game::Score");
+        assert_eq!(score, Some(ResourceType::new::<Score>()));
+    }
+
+    #[test]
+    #[should_panic(expected = "is used by both")]
+    fn duplicate_stable_names() {
+        let mut world = World::new();
+        world.register::<Armor>().register::<Shield>();
+    }
+}
@@ -1,7 +1,20 @@
-use super::internal::{blob::BlobCell, DenseMap};
+use super::{
+    internal::{blob::BlobCell, DenseMap},
+    StableNames,
+};
 use std::hash::Hash;
 
-pub trait Resource: 'static {}
+pub trait Resource: 'static {
+    /// The name used to refer to this resource across builds. Defaults to the type path.
+    fn stable_name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Former stable names that still resolve to this resource.
+    fn aliases() -> &'static [&'static str] {
+        &[]
+    }
+}
 pub trait LocalResource: 'static {}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -68,7 +81,7 @@ impl BaseResouces {
         let res = self
             .resources
             .get(&ty)
-            .expect(format!("Resource doesn't exist. {}", std::any::type_name::<R>()).as_str());
+            .unwrap_or_else(|| panic!("Resource doesn't exist. {}", std::any::type_name::<R>()));
         res.get::<R>()
     }
 
@@ -77,24 +90,37 @@ impl BaseResouces {
         let res = self
             .resources
             .get(&ty)
-            .expect(format!("Resource doesn't exist. {}", std::any::type_name::<R>()).as_str());
+            .unwrap_or_else(|| panic!("Resource doesn't exist. {}", std::any::type_name::<R>()));
 
         res.get_mut::<R>()
     }
 }
 
-pub struct Resources(BaseResouces);
+pub struct Resources(BaseResouces, StableNames<ResourceType>);
 
 impl Resources {
     pub fn new() -> Self {
-        Self(BaseResouces::new())
+        Self(BaseResouces::new(), StableNames::new())
     }
 
     pub fn add<R: Resource>(&mut self, resource: R) -> &mut Self {
+        let ty = ResourceType::new::<R>();
+        let type_name = std::any::type_name::<R>();
+        self.1
+            .register(ty, type_name, R::stable_name(), R::aliases());
         self.0.add(resource);
         self
     }
 
+    pub fn stable_name(&self, ty: &ResourceType) -> Option<&'static str> {
+        self.1.name(ty)
+    }
+
+    /// Resolves a stable name, or an alias of one, to a resource that was added.
+    pub fn type_by_name(&self, name: &str) -> Option<ResourceType> {
+        self.1.id(name)
+    }
+
     pub fn get<R: Resource>(&self) -> &R {
         self.0.cast::<R>()
     }
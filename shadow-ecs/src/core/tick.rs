@@ -0,0 +1,97 @@
+/// A change tick. The world advances it once per `World::run` and lets it wrap,
+/// so ticks are only ordered relative to the current tick, never with `<`.
+///
+/// Other counters stay plain `u64`: frame indices and system timings grow by one
+/// per run and can't overflow in practice, and entity generations are only
+/// compared for equality.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Tick(u32);
+
+impl Tick {
+    /// Stored ticks are never left further behind the current tick than this.
+    pub const MAX_AGE: u32 = u32::MAX / 2;
+
+    /// How many ticks pass between clamps. Ages grow by at most this much between
+    /// two clamps, so they stay short of wrapping around to look new again.
+    pub const CLAMP_INTERVAL: u32 = 1 << 29;
+
+    pub const fn new(tick: u32) -> Self {
+        Self(tick)
+    }
+
+    pub const fn get(&self) -> u32 {
+        self.0
+    }
+
+    pub fn next(&self) -> Tick {
+        Tick(self.0.wrapping_add(1))
+    }
+
+    pub fn prev(&self) -> Tick {
+        Tick(self.0.wrapping_sub(1))
+    }
+
+    /// Ticks elapsed from this tick to `current`.
+    pub fn age(&self, current: Tick) -> u32 {
+        current.0.wrapping_sub(self.0)
+    }
+
+    /// Whether this tick is more recent than `other`, as seen from `current`.
+    /// Both must be at most `MAX_AGE + CLAMP_INTERVAL` behind `current`.
+    pub fn is_newer_than(&self, other: Tick, current: Tick) -> bool {
+        self.age(current) < other.age(current)
+    }
+
+    /// Moves the tick up to `MAX_AGE` behind `current` if it is older. Returns
+    /// true if the tick was clamped.
+    pub fn clamp(&mut self, current: Tick) -> bool {
+        if self.age(current) > Self::MAX_AGE {
+            self.0 = current.0.wrapping_sub(Self::MAX_AGE);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether stored ticks are due to be clamped at this tick.
+    pub fn should_clamp(&self) -> bool {
+        self.0.is_multiple_of(Self::CLAMP_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tick;
+
+    #[test]
+    fn newer_across_wrap() {
+        let current = Tick::new(5);
+        let old = Tick::new(u32::MAX - 2);
+        let recent = Tick::new(3);
+
+        assert_eq!(old.age(current), 8);
+        assert!(recent.is_newer_than(old, current));
+        assert!(!old.is_newer_than(recent, current));
+        assert!(current.is_newer_than(recent, current));
+        assert_eq!(Tick::new(u32::MAX).next(), Tick::new(0));
+        assert_eq!(Tick::new(0).prev(), Tick::new(u32::MAX));
+    }
+
+    #[test]
+    fn clamp_keeps_order() {
+        let current = Tick::new(10);
+        let unclamped = Tick::new(current.get().wrapping_sub(Tick::MAX_AGE + 100));
+        let mut ancient = unclamped;
+        let mut recent = Tick::new(9);
+
+        assert!(ancient.clamp(current));
+        assert!(!recent.clamp(current));
+        assert_eq!(ancient.age(current), Tick::MAX_AGE);
+        assert_eq!(recent, Tick::new(9));
+        assert!(recent.is_newer_than(ancient, current));
+
+        let later = Tick::new(current.get().wrapping_add(Tick::MAX_AGE + 1));
+        assert!(unclamped.is_newer_than(recent, later));
+        assert!(recent.is_newer_than(ancient, later));
+    }
+}
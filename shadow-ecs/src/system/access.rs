@@ -9,6 +9,18 @@ pub enum WorldAccessType {
     LocalResource(ResourceType),
 }
 
+impl WorldAccessType {
+    /// True if both types can reach the same data. `World` reaches everything
+    /// except `None`.
+    pub fn overlaps(&self, other: &WorldAccessType) -> bool {
+        match (self, other) {
+            (WorldAccessType::None, _) | (_, WorldAccessType::None) => false,
+            (WorldAccessType::World, _) | (_, WorldAccessType::World) => true,
+            _ => self == other,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Access {
     Read,
@@ -58,3 +70,62 @@ impl WorldAccess {
         }
     }
 }
+
+impl WorldAccess {
+    /// The first type one side writes that overlaps a type the other side reads or writes.
+    pub fn conflict(
+        reads: &[WorldAccessType],
+        writes: &[WorldAccessType],
+        other_reads: &[WorldAccessType],
+        other_writes: &[WorldAccessType],
+    ) -> Option<WorldAccessType> {
+        let overlaps = |ty: &WorldAccessType, others: &[WorldAccessType]| {
+            others.iter().any(|other| ty.overlaps(other))
+        };
+
+        writes
+            .iter()
+            .find(|ty| overlaps(ty, other_reads) || overlaps(ty, other_writes))
+            .or_else(|| other_writes.iter().find(|ty| overlaps(ty, reads)))
+            .copied()
+    }
+
+    /// The first type two of a system's args conflict on. Accesses inside one arg,
+    /// such as the members of a `ParamSet`, are not compared with each other.
+    pub fn arg_conflict(args: &[Vec<WorldAccess>]) -> Option<WorldAccessType> {
+        let split = |access: &[WorldAccess]| {
+            let (mut reads, mut writes) = (vec![], vec![]);
+            Self::pick(&mut reads, &mut writes, access);
+            (reads, writes)
+        };
+
+        args.iter().enumerate().find_map(|(index, first)| {
+            let (reads, writes) = split(first);
+            args[index + 1..].iter().find_map(|second| {
+                let (other_reads, other_writes) = split(second);
+                Self::conflict(&reads, &writes, &other_reads, &other_writes)
+            })
+        })
+    }
+}
+
+/// A system with two args that access the same type, at least one of them mutably.
+/// The scheduler orders conflicting systems, but it can't split one system's args.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessConflict {
+    pub phase: &'static str,
+    pub system: &'static str,
+    pub ty: WorldAccessType,
+}
+
+impl std::fmt::Display for AccessConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "System {} in phase {} has two args that access {:?}, and at least one writes it; use a ParamSet",
+            self.system, self.phase, self.ty
+        )
+    }
+}
+
+impl std::error::Error for AccessConflict {}
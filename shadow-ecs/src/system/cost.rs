@@ -0,0 +1,104 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+thread_local! {
+    static MATCHED: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Adds `count` matched entities to the cost of the system running on this thread.
+pub fn record_matched(count: usize) {
+    MATCHED.with(|matched| matched.set(Some(matched.get().unwrap_or(0) + count)));
+}
+
+/// Collects the matched entity count recorded while running `f`.
+pub(crate) fn measure(f: impl FnOnce()) -> Option<usize> {
+    let previous = MATCHED.with(|matched| matched.replace(None));
+    f();
+    MATCHED.with(|matched| matched.replace(previous))
+}
+
+/// An exponentially smoothed estimate of how many entities a system touches per run.
+/// Systems that never run a query have no estimate.
+pub struct SystemCost {
+    estimate: AtomicU32,
+}
+
+impl SystemCost {
+    pub const SMOOTHING: f32 = 0.25;
+    pub const INLINE_THRESHOLD: f32 = 256.0;
+
+    pub fn new() -> Self {
+        Self {
+            estimate: AtomicU32::new(f32::NAN.to_bits()),
+        }
+    }
+
+    pub fn smooth(previous: Option<f32>, sample: f32) -> f32 {
+        match previous {
+            Some(previous) => previous + (sample - previous) * Self::SMOOTHING,
+            None => sample,
+        }
+    }
+
+    pub fn estimate(&self) -> Option<f32> {
+        let estimate = f32::from_bits(self.estimate.load(Ordering::Relaxed));
+        (!estimate.is_nan()).then_some(estimate)
+    }
+
+    pub fn record(&self, matched: usize) {
+        let estimate = Self::smooth(self.estimate(), matched as f32);
+        self.estimate.store(estimate.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Systems with a known, small estimate aren't worth dispatching to the pool.
+    pub fn should_inline(&self) -> bool {
+        self.estimate()
+            .is_some_and(|estimate| estimate < Self::INLINE_THRESHOLD)
+    }
+
+    /// Sort key for longest-first ordering. Unknown costs sort first.
+    pub fn priority(&self) -> f32 {
+        self.estimate().unwrap_or(f32::INFINITY)
+    }
+}
+
+impl Default for SystemCost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SystemCost;
+
+    #[test]
+    fn smoothing() {
+        let cost = SystemCost::new();
+        assert_eq!(cost.estimate(), None);
+
+        cost.record(100);
+        assert_eq!(cost.estimate(), Some(100.0));
+
+        cost.record(500);
+        assert_eq!(cost.estimate(), Some(200.0));
+
+        cost.record(200);
+        assert_eq!(cost.estimate(), Some(200.0));
+    }
+
+    #[test]
+    fn inline_threshold() {
+        let cost = SystemCost::new();
+        assert!(!cost.should_inline());
+
+        cost.record(3);
+        assert!(cost.should_inline());
+
+        let cost = SystemCost::new();
+        cost.record(300_000);
+        assert!(!cost.should_inline());
+    }
+}
@@ -68,15 +68,12 @@ impl<Node: GraphNode> Graph<Node> {
 
         let mut dependencies = self.dependencies.clone();
         let mut ids = HashSet::new();
-        for (id, node) in self.nodes[0..(self.nodes.len() / 2)].iter().enumerate() {
+        for (id, node) in self.nodes.iter().enumerate() {
             ids.insert(id);
-            for (other_id, other_node) in self.nodes[id..].iter().enumerate() {
-                if id != other_id {
-                    ids.insert(other_id);
-                    if node.is_dependency(other_node) {
-                        let dependencies = dependencies.entry(other_id).or_insert(HashSet::new());
-                        dependencies.insert(id);
-                    };
+            for (other_id, other_node) in self.nodes.iter().enumerate().skip(id + 1) {
+                if node.is_dependency(other_node) {
+                    let dependencies = dependencies.entry(other_id).or_insert(HashSet::new());
+                    dependencies.insert(id);
                 }
             }
         }
@@ -149,15 +146,33 @@ impl<'a, Node: GraphNode> GraphIter<'a, Node> {
 }
 
 impl<'a, Node: GraphNode> Iterator for GraphIter<'a, Node> {
-    type Item = Vec<&'a Node>;
+    type Item = GraphRow<'a, Node>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(group) = self.hierarchy.get(self.index) {
-            self.index += 1;
-            let group = group.iter().map(|i| &self.nodes[*i]).collect::<Vec<_>>();
-            Some(group)
-        } else {
-            None
-        }
+        let group = self.hierarchy.get(self.index)?;
+        self.index += 1;
+        Some(GraphRow {
+            nodes: self.nodes,
+            ids: group.iter(),
+        })
     }
 }
+
+pub struct GraphRow<'a, Node: GraphNode> {
+    nodes: &'a [Node],
+    ids: std::slice::Iter<'a, NodeId>,
+}
+
+impl<'a, Node: GraphNode> Iterator for GraphRow<'a, Node> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.ids.next().map(|id| &self.nodes[*id])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ids.size_hint()
+    }
+}
+
+impl<'a, Node: GraphNode> ExactSizeIterator for GraphRow<'a, Node> {}
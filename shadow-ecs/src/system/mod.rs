@@ -4,41 +4,132 @@ use super::{
     world::World,
 };
 use crate::{
+    archetype::Archetypes,
     core::ResourceType,
-    system::access::{Access, WorldAccessType},
-    world::event::Events,
+    system::{
+        access::{Access, WorldAccessType},
+        cost::SystemCost,
+        timing::SystemTiming,
+    },
+    world::{
+        attachments::EntityAttachments,
+        commands,
+        event::{Events, SystemPanicked},
+    },
+};
+use std::{
+    any::Any,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard, PoisonError,
+    },
+    time::Instant,
 };
 
 pub mod access;
+pub mod cost;
 pub mod graph;
 pub mod observer;
+pub mod param_set;
 pub mod runner;
 pub mod schedule;
+pub mod timing;
 
 pub use runner::*;
 
+pub type RunCondition = Box<dyn Fn(&World) -> bool + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    #[default]
+    Propagate,
+    Isolate,
+    DisableSystem,
+}
+
+/// The message of a caught panic, if it was raised with a string.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<String>() {
+        Some(message) => message.clone(),
+        None => match payload.downcast_ref::<&'static str>() {
+            Some(message) => message.to_string(),
+            None => String::from("unknown panic"),
+        },
+    }
+}
+
+/// Runs `f` under the world's panic policy.
+/// Returns false if `f` panicked and the panic was caught.
+pub(crate) fn run_guarded(name: &'static str, world: &World, f: impl FnOnce()) -> bool {
+    if world.panic_policy() == PanicPolicy::Propagate {
+        f();
+        return true;
+    }
+
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(()) => true,
+        Err(payload) => {
+            world
+                .events()
+                .add(SystemPanicked::from_payload(name, payload));
+            false
+        }
+    }
+}
+
+/// Locks the state a system keeps for its args. A panic caught by the panic policy
+/// poisons the lock, but the state is still valid for the next run.
+pub(crate) fn lock_state<S>(state: &Mutex<S>) -> MutexGuard<'_, S> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 pub struct System {
+    name: &'static str,
+    /// The system's position in its phase graph, in insertion order across stages.
+    index: usize,
+    enabled: AtomicBool,
+    cost: SystemCost,
+    timing: SystemTiming,
     function: Box<dyn for<'a> Fn(&'a World) + Send + Sync>,
     reads: Vec<WorldAccessType>,
     writes: Vec<WorldAccessType>,
+    arg_conflict: Option<WorldAccessType>,
     before: Vec<System>,
     after: Vec<System>,
+    conditions: Vec<RunCondition>,
 }
 
 impl System {
-    fn new<F>(function: F, reads: Vec<WorldAccessType>, writes: Vec<WorldAccessType>) -> Self
+    fn new<F>(
+        name: &'static str,
+        function: F,
+        reads: Vec<WorldAccessType>,
+        writes: Vec<WorldAccessType>,
+    ) -> Self
     where
         F: for<'a> Fn(&'a World) + Send + Sync + 'static,
     {
         Self {
+            name,
+            index: 0,
+            enabled: AtomicBool::new(true),
+            cost: SystemCost::new(),
+            timing: SystemTiming::new(),
             function: Box::new(function),
             reads,
             writes,
+            arg_conflict: None,
             before: vec![],
             after: vec![],
+            conditions: vec![],
         }
     }
 
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
     pub fn reads(&self) -> &[WorldAccessType] {
         &self.reads
     }
@@ -47,14 +138,59 @@ impl System {
         &self.writes
     }
 
+    /// The type two of the system's args conflict on, if any.
+    pub fn arg_conflict(&self) -> Option<WorldAccessType> {
+        self.arg_conflict
+    }
+
+    pub fn cost(&self) -> &SystemCost {
+        &self.cost
+    }
+
+    pub fn timing(&self) -> &SystemTiming {
+        &self.timing
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Release);
+    }
+
     pub(crate) fn systems(&mut self) -> (Vec<System>, Vec<System>) {
         let before = std::mem::take(&mut self.before);
         let after = std::mem::take(&mut self.after);
         (before, after)
     }
 
+    pub fn should_run(&self, world: &World) -> bool {
+        self.conditions.iter().all(|condition| condition(world))
+    }
+
     pub fn run(&self, world: &World) {
-        (self.function)(world);
+        if !self.is_enabled() || !self.should_run(world) {
+            return;
+        }
+
+        let mut completed = true;
+        let started = Instant::now();
+        let matched = cost::measure(|| {
+            completed = commands::run_as(self.index, || {
+                run_guarded(self.name, world, || (self.function)(world))
+            });
+        });
+        let elapsed = started.elapsed();
+        self.timing.record(world.timings().frame(), elapsed);
+
+        if let Some(matched) = matched {
+            self.cost.record(matched);
+        }
+
+        if !completed && world.panic_policy() == PanicPolicy::DisableSystem {
+            self.set_enabled(false);
+        }
     }
 }
 
@@ -62,6 +198,15 @@ pub trait IntoSystem<M> {
     fn into_system(self) -> System;
     fn before<Marker>(self, system: impl IntoSystem<Marker>) -> System;
     fn after<Marker>(self, system: impl IntoSystem<Marker>) -> System;
+
+    fn run_if(self, condition: impl Fn(&World) -> bool + Send + Sync + 'static) -> System
+    where
+        Self: Sized,
+    {
+        let mut system = self.into_system();
+        system.conditions.push(Box::new(condition));
+        system
+    }
 }
 
 impl IntoSystem<()> for System {
@@ -83,6 +228,7 @@ impl IntoSystem<()> for System {
 impl<F: Fn() + Send + Sync + 'static> IntoSystem<F> for F {
     fn into_system(self) -> System {
         let system = System::new(
+            std::any::type_name::<F>(),
             move |_| {
                 (self)();
             },
@@ -95,6 +241,7 @@ impl<F: Fn() + Send + Sync + 'static> IntoSystem<F> for F {
 
     fn before<Marker>(self, other: impl IntoSystem<Marker>) -> System {
         let mut system = System::new(
+            std::any::type_name::<F>(),
             move |_| {
                 (self)();
             },
@@ -109,6 +256,7 @@ impl<F: Fn() + Send + Sync + 'static> IntoSystem<F> for F {
 
     fn after<Marker>(self, other: impl IntoSystem<Marker>) -> System {
         let mut system = System::new(
+            std::any::type_name::<F>(),
             move |_| {
                 (self)();
             },
@@ -166,6 +314,7 @@ impl IntoSystem<()> for SystemSet {
         }
 
         let system = System::new(
+            std::any::type_name::<Self>(),
             move |world| {
                 for system in &self.systems {
                     system.run(world);
@@ -188,6 +337,7 @@ impl IntoSystem<()> for SystemSet {
         }
 
         let mut system = System::new(
+            std::any::type_name::<Self>(),
             move |world| {
                 for system in &self.systems {
                     system.run(world);
@@ -212,6 +362,7 @@ impl IntoSystem<()> for SystemSet {
         }
 
         let mut system = System::new(
+            std::any::type_name::<Self>(),
             move |world| {
                 for system in &self.systems {
                     system.run(world);
@@ -229,15 +380,18 @@ impl IntoSystem<()> for SystemSet {
 
 pub trait SystemArg {
     type Item<'a>;
+    /// Kept by the system between runs, such as a query's matched archetypes.
+    type State: Default + Send + 'static;
 
-    fn get<'a>(world: &'a World) -> Self::Item<'a>;
+    fn get<'a>(world: &'a World, state: &'a mut Self::State) -> Self::Item<'a>;
     fn access() -> Vec<WorldAccess>;
 }
 
 impl SystemArg for &World {
     type Item<'a> = &'a World;
+    type State = ();
 
-    fn get<'a>(world: &'a World) -> Self::Item<'a> {
+    fn get<'a>(world: &'a World, _: &'a mut ()) -> Self::Item<'a> {
         world
     }
 
@@ -249,8 +403,9 @@ impl SystemArg for &World {
 
 impl<R: Resource> SystemArg for &R {
     type Item<'a> = &'a R;
+    type State = ();
 
-    fn get<'a>(world: &'a World) -> Self::Item<'a> {
+    fn get<'a>(world: &'a World, _: &'a mut ()) -> Self::Item<'a> {
         world.resource::<R>()
     }
 
@@ -262,8 +417,9 @@ impl<R: Resource> SystemArg for &R {
 
 impl<R: Resource> SystemArg for &mut R {
     type Item<'a> = &'a mut R;
+    type State = ();
 
-    fn get<'a>(world: &'a World) -> Self::Item<'a> {
+    fn get<'a>(world: &'a World, _: &'a mut ()) -> Self::Item<'a> {
         world.resource_mut::<R>()
     }
 
@@ -275,8 +431,9 @@ impl<R: Resource> SystemArg for &mut R {
 
 impl SystemArg for &Events {
     type Item<'a> = &'a Events;
+    type State = ();
 
-    fn get<'a>(world: &'a World) -> Self::Item<'a> {
+    fn get<'a>(world: &'a World, _: &'a mut ()) -> Self::Item<'a> {
         world.events()
     }
 
@@ -286,13 +443,28 @@ impl SystemArg for &Events {
     }
 }
 
+impl SystemArg for &EntityAttachments {
+    type Item<'a> = &'a EntityAttachments;
+    type State = ();
+
+    fn get<'a>(world: &'a World, _: &'a mut ()) -> Self::Item<'a> {
+        world.attachments()
+    }
+
+    fn access() -> Vec<WorldAccess> {
+        let ty = WorldAccessType::Resource(ResourceType::new::<EntityAttachments>());
+        vec![WorldAccess::new(ty, Access::Read)]
+    }
+}
+
 pub struct Local<R: LocalResource> {
     _marker: std::marker::PhantomData<R>,
 }
 impl<R: LocalResource> SystemArg for &Local<R> {
     type Item<'a> = &'a R;
+    type State = ();
 
-    fn get<'a>(world: &'a World) -> Self::Item<'a> {
+    fn get<'a>(world: &'a World, _: &'a mut ()) -> Self::Item<'a> {
         world.local_resource::<R>()
     }
 
@@ -304,8 +476,9 @@ impl<R: LocalResource> SystemArg for &Local<R> {
 
 impl<R: LocalResource> SystemArg for &mut Local<R> {
     type Item<'a> = &'a mut R;
+    type State = ();
 
-    fn get<'a>(world: &'a World) -> Self::Item<'a> {
+    fn get<'a>(world: &'a World, _: &'a mut ()) -> Self::Item<'a> {
         world.local_resource_mut::<R>()
     }
 
@@ -317,8 +490,9 @@ impl<R: LocalResource> SystemArg for &mut Local<R> {
 
 impl SystemArg for &Entities {
     type Item<'a> = &'a Entities;
+    type State = ();
 
-    fn get<'a>(world: &'a World) -> Self::Item<'a> {
+    fn get<'a>(world: &'a World, _: &'a mut ()) -> Self::Item<'a> {
         world.entities()
     }
 
@@ -327,12 +501,28 @@ impl SystemArg for &Entities {
     }
 }
 
+/// Archetype layout only changes when the world flushes, so reading it doesn't
+/// conflict with systems writing component values.
+impl SystemArg for &Archetypes {
+    type Item<'a> = &'a Archetypes;
+    type State = ();
+
+    fn get<'a>(world: &'a World, _: &'a mut ()) -> Self::Item<'a> {
+        world.archetypes()
+    }
+
+    fn access() -> Vec<WorldAccess> {
+        vec![WorldAccess::new(WorldAccessType::None, Access::Read)]
+    }
+}
+
 pub struct Cloned<C: Clone + Resource>(std::marker::PhantomData<C>);
 
 impl<C: Clone + Resource> SystemArg for Cloned<C> {
     type Item<'a> = C;
+    type State = ();
 
-    fn get<'a>(world: &'a World) -> Self::Item<'a> {
+    fn get<'a>(world: &'a World, _: &'a mut ()) -> Self::Item<'a> {
         world.resource::<C>().clone()
     }
 
@@ -352,61 +542,44 @@ macro_rules! impl_into_system {
             fn into_system(self) -> System {
                 let mut reads = vec![];
                 let mut writes = vec![];
-                let mut metas = vec![];
-
-                $(metas.extend($arg::access());)*
-
-                WorldAccess::pick(&mut reads, &mut writes, &metas);
+                let args = [$($arg::access()),*];
+                for access in &args {
+                    WorldAccess::pick(&mut reads, &mut writes, access);
+                }
 
-                let system = System::new(move |world| {
-                    (self)($($arg::get(world)),*);
+                let state = Mutex::new(<($($arg,)*) as SystemArg>::State::default());
+                let mut system = System::new(std::any::type_name::<F>(), move |world| {
+                    let mut state = lock_state(&state);
+                    #[allow(non_snake_case)]
+                    let ($($arg,)*) = <($($arg,)*) as SystemArg>::get(world, &mut state);
+                    (self)($($arg),*);
                 }, reads, writes);
 
+                system.arg_conflict = WorldAccess::arg_conflict(&args);
                 system
             }
 
             fn before<Marker>(self, other: impl IntoSystem<Marker>) -> System {
-                let mut reads = vec![];
-                let mut writes = vec![];
-                let mut metas = vec![];
-
-                $(metas.extend($arg::access());)*
-
-                WorldAccess::pick(&mut reads, &mut writes, &metas);
-
-                let mut system = System::new(move |world| {
-                    (self)($($arg::get(world)),*);
-                }, reads, writes);
-
+                let mut system = self.into_system();
                 system.before.push(other.into_system());
-
                 system
             }
 
             fn after<Marker>(self, other: impl IntoSystem<Marker>) -> System {
-                let mut reads = vec![];
-                let mut writes = vec![];
-                let mut metas = vec![];
-
-                $(metas.extend($arg::access());)*
-
-                WorldAccess::pick(&mut reads, &mut writes, &metas);
-
-                let mut system = System::new(move |world| {
-                    (self)($($arg::get(world)),*);
-                }, reads, writes);
-
+                let mut system = self.into_system();
                 system.after.push(other.into_system());
-
                 system
             }
         }
 
         impl<$($arg: SystemArg),*> SystemArg for ($($arg,)*) {
             type Item<'a> = ($($arg::Item<'a>,)*);
+            type State = ($($arg::State,)*);
 
-            fn get<'a>(world: &'a World) -> Self::Item<'a> {
-                ($($arg::get(world),)*)
+            #[allow(non_snake_case)]
+            fn get<'a>(world: &'a World, state: &'a mut Self::State) -> Self::Item<'a> {
+                let ($($arg,)*) = state;
+                ($(<$arg as SystemArg>::get(world, $arg),)*)
             }
 
             fn access() -> Vec<WorldAccess> {
@@ -427,3 +600,71 @@ impl_into_system!(A, B, C, D, E, F2);
 impl_into_system!(A, B, C, D, E, F2, G);
 impl_into_system!(A, B, C, D, E, F2, G, H);
 impl_into_system!(A, B, C, D, E, F2, G, H, I);
+
+#[cfg(test)]
+mod tests {
+    use super::PanicPolicy;
+    use crate::{
+        core::Resource,
+        system::schedule::Root,
+        world::{event::SystemPanicked, World},
+    };
+
+    #[derive(Default)]
+    struct Counts {
+        ran: usize,
+        panicked: Vec<&'static str>,
+    }
+
+    impl Resource for Counts {}
+
+    fn explode(counts: &mut Counts) {
+        counts.ran += 1;
+        panic!("boom");
+    }
+
+    fn world(policy: PanicPolicy) -> World {
+        let mut world = World::new();
+        world
+            .set_panic_policy(policy)
+            .init_resource::<Counts>()
+            .add_system(Root, explode)
+            .observe::<SystemPanicked, _>(|panics: &[SystemPanicked], counts: &mut Counts| {
+                counts.panicked.extend(panics.iter().map(|p| p.name()));
+            })
+            .build();
+        world
+    }
+
+    #[test]
+    #[should_panic(expected = "boom")]
+    fn propagate_panics() {
+        world(PanicPolicy::Propagate).run(Root);
+    }
+
+    #[test]
+    fn isolate_panics() {
+        let mut world = world(PanicPolicy::Isolate);
+        world.run(Root).run(Root);
+
+        let counts = world.resource::<Counts>();
+        assert_eq!(counts.ran, 2);
+        assert_eq!(
+            counts.panicked,
+            vec![std::any::type_name_of_val(&explode); 2]
+        );
+    }
+
+    #[test]
+    fn disable_panicking_system() {
+        let name = std::any::type_name_of_val(&explode);
+        let mut world = world(PanicPolicy::DisableSystem);
+        world.run(Root).run(Root);
+        assert_eq!(world.resource::<Counts>().ran, 1);
+        assert_eq!(world.resource::<Counts>().panicked, vec![name]);
+
+        assert!(world.enable_system(name));
+        world.run(Root);
+        assert_eq!(world.resource::<Counts>().ran, 2);
+    }
+}
@@ -1,12 +1,15 @@
 use super::{
     access::{WorldAccess, WorldAccessType},
-    ArgItem, SystemArg,
+    lock_state, run_guarded, ArgItem, SystemArg,
 };
 use crate::{
     core::{internal::blob::BlobCell, DenseMap},
-    world::{event::{Event, EventOutputs, EventType}, World},
+    world::{
+        event::{Event, EventOutputs, EventType},
+        World,
+    },
 };
-use std::any::TypeId;
+use std::{any::TypeId, sync::Mutex};
 
 pub struct Observer<E: Event> {
     function: Box<dyn Fn(&[E::Output], &World) + Send + Sync + 'static>,
@@ -36,7 +39,9 @@ impl<E: Event> Observer<E> {
     }
 
     pub fn run(&self, output: &[E::Output], world: &World) {
-        (self.function)(output, world);
+        run_guarded(std::any::type_name::<E>(), world, || {
+            (self.function)(output, world)
+        });
     }
 }
 
@@ -135,6 +140,10 @@ impl EventObservers {
         }
     }
 
+    pub fn contains<E: Event>(&self) -> bool {
+        self.observers.contains(&TypeId::of::<E>())
+    }
+
     pub fn run(&self, world: &World) {
         for invocation in world.events().invocations() {
             if let Some(observers) = self.observers.get(&invocation.event()) {
@@ -196,8 +205,12 @@ macro_rules! impl_into_observer {
 
                 WorldAccess::pick(&mut reads, &mut writes, &metas);
 
+                let state = Mutex::new(<($($arg,)*) as SystemArg>::State::default());
                 let system = Observer::<Ev>::new(move |outputs: &[Ev::Output], world: &World| {
-                    (self)(outputs, $($arg::get(world)),*);
+                    let mut state = lock_state(&state);
+                    #[allow(non_snake_case)]
+                    let ($($arg,)*) = <($($arg,)*) as SystemArg>::get(world, &mut state);
+                    (self)(outputs, $($arg),*);
                 }, reads, writes);
 
                 system
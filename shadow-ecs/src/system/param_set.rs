@@ -0,0 +1,132 @@
+use super::{access::WorldAccess, ArgItem, SystemArg};
+use crate::world::World;
+
+/// Holds system args whose accesses may conflict, such as two queries writing the same
+/// component, and hands out one at a time. The scheduler sees the union of their accesses.
+///
+/// Each arg borrows the set mutably, so only one can be in use at a time:
+///
+/// ```compile_fail
+/// use shadow_ecs::{
+///     core::Component,
+///     system::param_set::ParamSet,
+///     world::{query::Query, World},
+/// };
+///
+/// struct Position(i32);
+/// impl Component for Position {}
+///
+/// let world = World::new();
+/// let mut state = Default::default();
+/// let mut positions = ParamSet::<(
+///     Query<&mut Position>,
+///     Query<&mut Position>,
+/// )>::new(&world, &mut state);
+///
+/// let first = positions.p0();
+/// let second = positions.p1();
+/// drop((first, second));
+/// ```
+pub struct ParamSet<'w, P: SystemArg> {
+    world: &'w World,
+    state: &'w mut P::State,
+}
+
+impl<'w, P: SystemArg> ParamSet<'w, P> {
+    pub fn new(world: &'w World, state: &'w mut P::State) -> Self {
+        Self { world, state }
+    }
+}
+
+impl<P: SystemArg> SystemArg for ParamSet<'_, P> {
+    type Item<'a> = ParamSet<'a, P>;
+    type State = P::State;
+
+    fn get<'a>(world: &'a World, state: &'a mut Self::State) -> Self::Item<'a> {
+        ParamSet::new(world, state)
+    }
+
+    fn access() -> Vec<WorldAccess> {
+        P::access()
+    }
+}
+
+macro_rules! impl_param_set {
+    ($(($method:ident, $arg:ident, $index:tt)),*) => {
+        impl<'w, $($arg: SystemArg),*> ParamSet<'w, ($($arg,)*)> {
+            $(
+                pub fn $method(&mut self) -> ArgItem<'_, $arg> {
+                    $arg::get(self.world, &mut self.state.$index)
+                }
+            )*
+        }
+    };
+}
+
+impl_param_set!((p0, A, 0), (p1, B, 1));
+impl_param_set!((p0, A, 0), (p1, B, 1), (p2, C, 2));
+impl_param_set!((p0, A, 0), (p1, B, 1), (p2, C, 2), (p3, D, 3));
+
+#[cfg(test)]
+mod tests {
+    use super::ParamSet;
+    use crate::{
+        core::{Component, ComponentId},
+        system::{access::WorldAccessType, schedule::Root, IntoSystem},
+        world::{
+            query::{Query, With},
+            World,
+        },
+    };
+
+    struct Position(i32);
+    struct Player;
+    struct Camera;
+
+    impl Component for Position {}
+    impl Component for Player {}
+    impl Component for Camera {}
+
+    type Positions<'w, 'q> = ParamSet<
+        'w,
+        (
+            Query<'q, &'q mut Position, With<Player>>,
+            Query<'q, &'q mut Position, With<Camera>>,
+        ),
+    >;
+
+    fn follow_player(mut positions: Positions) {
+        let target = positions.p0().map(|position| position.0).sum::<i32>();
+        for position in positions.p1() {
+            position.0 = target;
+        }
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        let player = world.spawn(None);
+        world.add_component(&player, Position(5));
+        world.add_component(&player, Player);
+        let camera = world.spawn(None);
+        world.add_component(&camera, Position(0));
+        world.add_component(&camera, Camera);
+        world
+    }
+
+    #[test]
+    fn param_set() {
+        let mut world = world();
+        world.add_system(Root, follow_player).build();
+        world.run(Root);
+
+        let positions = Query::<&Position>::new(&world).map(|position| position.0);
+        assert_eq!(positions.collect::<Vec<_>>(), [5, 5]);
+    }
+
+    #[test]
+    fn access_union() {
+        let system = follow_player.into_system();
+        let position = WorldAccessType::Component(ComponentId::new::<Position>());
+        assert_eq!(system.writes(), [position, position]);
+    }
+}
@@ -1,4 +1,5 @@
 use super::{
+    access::{AccessConflict, WorldAccess, WorldAccessType},
     graph::{Graph, GraphNode},
     IntoSystem, System,
 };
@@ -9,25 +10,27 @@ use crate::{
 
 impl GraphNode for System {
     fn is_dependency(&self, other: &Self) -> bool {
-        let mut writes = other.writes().iter();
-        if writes.any(|ty| self.reads().contains(ty) || self.writes().contains(ty)) {
-            true
-        } else if other.reads().iter().any(|ty| self.writes().contains(ty)) {
-            true
-        } else {
-            false
-        }
+        self.conflict(other).is_some()
     }
 }
 
+impl System {
+    /// The type this system and `other` can't access at the same time, if any.
+    pub fn conflict(&self, other: &System) -> Option<WorldAccessType> {
+        WorldAccess::conflict(self.reads(), self.writes(), other.reads(), other.writes())
+    }
+}
+
+/// Systems of a phase, split into stages by barriers. Structural events queued
+/// by one stage are applied before the next stage runs.
 pub struct SystemGraph {
-    graph: Graph<System>,
+    stages: Vec<Graph<System>>,
 }
 
 impl SystemGraph {
     pub fn new() -> Self {
         Self {
-            graph: Graph::new(),
+            stages: vec![Graph::new()],
         }
     }
 
@@ -41,36 +44,75 @@ impl SystemGraph {
             .map(|s| self.add_system(s))
             .collect::<Vec<_>>();
 
-        let id = self.graph.insert(system);
+        let mut index = self.systems().count();
+        let graph = self.stages.last_mut().unwrap();
+        system.index = index;
+        let id = graph.insert(system);
 
-        after_ids
-            .iter()
-            .for_each(|a| self.graph.add_dependency(id, *a));
+        after_ids.iter().for_each(|a| graph.add_dependency(id, *a));
 
-        for before in before {
-            let before_id = self.graph.insert(before);
-            self.graph.add_dependency(before_id, id);
+        for mut before in before {
+            index += 1;
+            before.index = index;
+            let before_id = graph.insert(before);
+            graph.add_dependency(before_id, id);
         }
 
         id
     }
 
-    pub fn build(&mut self) {
-        self.graph.build();
+    /// Systems added after the barrier see the structural changes of systems added before it.
+    pub fn add_barrier(&mut self) {
+        if !self.stages.last().unwrap().nodes().is_empty() {
+            self.stages.push(Graph::new());
+        }
     }
-}
 
-impl std::ops::Deref for SystemGraph {
-    type Target = Graph<System>;
+    pub fn stages(&self) -> &[Graph<System>] {
+        &self.stages
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.graph
+    pub fn systems(&self) -> impl Iterator<Item = &System> {
+        self.stages.iter().flat_map(|graph| graph.nodes())
+    }
+
+    pub fn build(&mut self) {
+        self.stages.iter_mut().for_each(|graph| graph.build());
+    }
+
+    /// Checks that no system has two args that conflict. Conflicts between systems
+    /// are ordered by `build`, but the args of one system are always fetched together.
+    pub fn validate(&self, phase: &'static str) -> Result<(), AccessConflict> {
+        match self
+            .systems()
+            .find_map(|system| Some((system, system.arg_conflict()?)))
+        {
+            Some((system, ty)) => Err(AccessConflict {
+                phase,
+                system: system.name(),
+                ty,
+            }),
+            None => Ok(()),
+        }
     }
 }
 
 impl std::fmt::Display for SystemGraph {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.graph.fmt(f)
+        for (stage, graph) in self.stages.iter().enumerate() {
+            writeln!(f, "Stage {}", stage)?;
+            graph.fmt(f)?;
+
+            writeln!(f, "Systems")?;
+            for (id, system) in graph.nodes().iter().enumerate() {
+                match system.cost().estimate() {
+                    Some(cost) => writeln!(f, "{} {} (cost: {:.1})", id, system.name(), cost)?,
+                    None => writeln!(f, "{} {} (cost: unknown)", id, system.name())?,
+                }
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -96,18 +138,41 @@ impl Runner for SequentialRunner {
 
 pub struct ParallelRunner;
 
+impl ParallelRunner {
+    /// Splits a row into systems worth dispatching to the pool, ordered
+    /// longest-first, and cheap systems to run inline on the calling thread.
+    pub fn plan<'a>(row: impl Iterator<Item = &'a System>) -> (Vec<&'a System>, Vec<&'a System>) {
+        let (inline, mut pooled): (Vec<_>, Vec<_>) =
+            row.partition(|system| system.cost().should_inline());
+        pooled.sort_by(|a, b| b.cost().priority().total_cmp(&a.cost().priority()));
+
+        (pooled, inline)
+    }
+}
+
 impl Runner for ParallelRunner {
     fn run(&self, graph: &Graph<System>, world: &World) {
-        let available_threads = max_thread_count();
         for row in graph.iter() {
-            let num_threads = row.len().min(available_threads);
+            if row.len() == 1 {
+                row.for_each(|system| system.run(world));
+                continue;
+            }
+
+            let (pooled, inline) = Self::plan(row);
+            if pooled.len() > 1 {
+                let num_threads = pooled.len().min(max_thread_count());
+
+                let mut pool = ScopedTaskPool::new(num_threads);
+                for system in pooled {
+                    pool.spawn(move || system.run(world));
+                }
 
-            let mut pool = ScopedTaskPool::new(num_threads);
-            for system in row {
-                pool.spawn(move || system.run(world));
+                pool.run();
+            } else {
+                pooled.iter().for_each(|system| system.run(world));
             }
 
-            pool.run();
+            inline.iter().for_each(|system| system.run(world));
         }
     }
 }
@@ -119,7 +184,157 @@ impl SystemRunner {
         Self(Box::new(runner))
     }
 
-    pub fn run(&self, systems: &SystemGraph, world: &World) {
-        self.0.run(&systems.graph, world)
+    /// Runs each stage of `systems`, flushing the world between stages.
+    pub fn run(&self, systems: &SystemGraph, world: &mut World) {
+        for (index, graph) in systems.stages().iter().enumerate() {
+            if index > 0 {
+                world.flush();
+            }
+
+            self.0.run(graph, world);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParallelRunner;
+    use crate::{
+        core::{Entities, Resource, ResourceType},
+        system::{
+            access::WorldAccessType,
+            schedule::{BuildError, Root},
+            IntoSystem, System, SystemGraph,
+        },
+        world::{
+            event::{Events, Spawn},
+            World,
+        },
+    };
+
+    #[derive(Default)]
+    struct Seen(Vec<usize>);
+    impl Resource for Seen {}
+
+    fn spawner(events: &Events) {
+        events.add(Spawn::new());
+    }
+
+    fn reader(entities: &Entities, seen: &mut Seen) {
+        seen.0.push(entities.len());
+    }
+
+    #[test]
+    fn plan_orders_longest_first() {
+        let systems: Vec<System> = (0..4).map(|_| (|| {}).into_system()).collect();
+        systems[0].cost().record(3);
+        systems[1].cost().record(300_000);
+        systems[2].cost().record(3_000);
+
+        let (pooled, inline) = ParallelRunner::plan(systems.iter());
+        let order = |list: &[&System]| {
+            list.iter()
+                .map(|s| systems.iter().position(|o| std::ptr::eq(o, *s)).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(order(&pooled), vec![3, 1, 2]);
+        assert_eq!(order(&inline), vec![0]);
+    }
+
+    #[test]
+    fn structural_changes_apply_between_phases() {
+        for spawner_first in [true, false] {
+            let mut world = World::new();
+            world.init_resource::<Seen>();
+            match spawner_first {
+                true => world.add_system(Root, spawner).add_system(Root, reader),
+                false => world.add_system(Root, reader).add_system(Root, spawner),
+            };
+            world.build();
+
+            for _ in 0..100 {
+                world.run(Root);
+            }
+
+            let expected = (0..100).collect::<Vec<_>>();
+            assert_eq!(world.resource::<Seen>().0, expected);
+        }
+    }
+
+    #[test]
+    fn barrier_applies_structural_changes() {
+        let mut world = World::new();
+        world
+            .init_resource::<Seen>()
+            .add_system(Root, spawner)
+            .add_barrier(Root)
+            .add_system(Root, reader)
+            .build();
+
+        for _ in 0..3 {
+            world.run(Root);
+        }
+
+        assert_eq!(world.resource::<Seen>().0, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn conflicting_systems_get_separate_rows() {
+        fn write_seen(_: &mut Seen) {}
+
+        let mut graph = SystemGraph::new();
+        graph.add_system(|| {});
+        graph.add_system(write_seen);
+        graph.add_system(write_seen);
+        graph.add_system(reader);
+        graph.build();
+        assert_eq!(graph.stages()[0].iter().count(), 3);
+        assert_eq!(graph.validate("Root"), Ok(()));
+
+        let seen = WorldAccessType::Resource(ResourceType::new::<Seen>());
+        let (empty, writer) = ((|| {}).into_system(), write_seen.into_system());
+        assert_eq!(writer.conflict(&reader.into_system()), Some(seen));
+        assert_eq!(empty.conflict(&writer), None);
+    }
+
+    #[test]
+    fn world_reader_and_resource_writer_get_separate_rows() {
+        fn inspect(_: &World) {}
+        fn write_seen(_: &mut Seen) {}
+
+        let mut graph = SystemGraph::new();
+        graph.add_system(inspect);
+        graph.add_system(write_seen);
+        graph.add_system(|_: &World, _: &Seen| {});
+        graph.build();
+
+        let rows = graph.stages()[0]
+            .iter()
+            .map(|row| row.map(|system| system.name()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let write_seen = std::any::type_name_of_val(&write_seen);
+        assert_eq!(rows.len(), 3);
+        assert!(rows
+            .iter()
+            .filter(|row| row.contains(&write_seen))
+            .all(|row| row.len() == 1));
+    }
+
+    #[test]
+    fn conflicting_args_fail_validation() {
+        fn aliased(_: &World, _: &mut Seen) {}
+
+        let mut world = World::new();
+        world.init_resource::<Seen>().add_system(Root, aliased);
+
+        let seen = WorldAccessType::Resource(ResourceType::new::<Seen>());
+        match world.try_build() {
+            Err(BuildError::Access(conflict)) => {
+                assert_eq!(conflict.system, std::any::type_name_of_val(&aliased));
+                assert_eq!(conflict.ty, seen);
+            }
+            _ => panic!("expected an access conflict"),
+        }
     }
 }
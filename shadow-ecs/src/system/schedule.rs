@@ -1,9 +1,12 @@
-use super::{IntoSystem, ParallelRunner, RunMode, SequentialRunner, SystemGraph, SystemRunner};
+use super::{
+    access::AccessConflict, IntoSystem, ParallelRunner, RunMode, SequentialRunner, System,
+    SystemGraph, SystemRunner,
+};
 use crate::{
     core::{DenseMap, DenseSet},
     world::World,
 };
-use std::{any::TypeId, hash::Hash};
+use std::{any::TypeId, fmt::Display, hash::Hash, time::Instant};
 
 pub trait Phase: Sized + 'static {
     fn id(&self) -> ScheduleId {
@@ -17,26 +20,22 @@ pub trait Phase: Sized + 'static {
 
 pub struct RunContext<'a> {
     world: &'a mut World,
-    systems: Vec<&'a SystemGraph>,
-    runner: &'a SystemRunner,
+    systems: &'a Systems,
+    phase: ScheduleId,
 }
 
 impl<'a> RunContext<'a> {
-    pub fn new(
-        world: &'a mut World,
-        systems: Vec<&'a SystemGraph>,
-        runner: &'a SystemRunner,
-    ) -> Self {
+    pub fn new(world: &'a mut World, systems: &'a Systems, phase: ScheduleId) -> Self {
         Self {
             world,
             systems,
-            runner,
+            phase,
         }
     }
 
-    pub fn run(&self) {
-        for system in self.systems.iter() {
-            self.runner.run(system, self.world);
+    pub fn run(&mut self) {
+        for graph in self.systems.systems(&self.phase) {
+            self.systems.runner.run(graph, self.world);
         }
     }
 }
@@ -48,7 +47,7 @@ pub trait PhaseRunner: Send + Sync + 'static {
 pub struct DefaultPhaseRunner;
 
 impl PhaseRunner for DefaultPhaseRunner {
-    fn run(&self, ctx: RunContext) {
+    fn run(&self, mut ctx: RunContext) {
         ctx.run();
     }
 }
@@ -71,6 +70,7 @@ impl ScheduleId {
 
 pub struct Schedule {
     id: ScheduleId,
+    name: &'static str,
     children: DenseMap<ScheduleId, Schedule>,
 }
 
@@ -78,22 +78,44 @@ impl Schedule {
     pub fn new(id: ScheduleId) -> Self {
         Self {
             id,
+            name: "",
             children: DenseMap::new(),
         }
     }
 
     pub fn from<P: Phase>() -> Self {
-        Self::new(ScheduleId::new::<P>())
+        let mut schedule = Self::new(ScheduleId::new::<P>());
+        schedule.name = std::any::type_name::<P>();
+        schedule
     }
 
     pub fn id(&self) -> ScheduleId {
         self.id
     }
 
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
     pub fn has(&self, id: &ScheduleId) -> bool {
         self.children.contains(id)
     }
 
+    /// True if `id` is this schedule or any schedule below it.
+    pub fn contains(&self, id: &ScheduleId) -> bool {
+        self.id == *id || self.get(id).is_some()
+    }
+
+    /// Every schedule in the order they run, starting with this one.
+    pub fn phase_order(&self) -> Vec<&Schedule> {
+        let mut order = vec![self];
+        for child in self.children.values() {
+            order.extend(child.phase_order());
+        }
+
+        order
+    }
+
     pub fn get(&self, id: &ScheduleId) -> Option<&Schedule> {
         match self.children.get(id) {
             Some(child) => Some(child),
@@ -184,17 +206,25 @@ impl Schedule {
         }
     }
 
+    /// Structural events queued while a phase runs are applied once its systems
+    /// finish, before any child phase runs, so systems in the same phase (or stage,
+    /// see `World::add_barrier`) never observe each other's structural changes.
     pub fn run(&self, world: &mut World, systems: &Systems) {
-        let system_runner = &systems.runner;
+        world.flush_deferred(self.id);
+
         let phase_runner = systems
             .phase_runner(&self.id)
             .unwrap_or(&DefaultPhaseRunner);
 
-        let graphs = systems.systems(&self.id);
-        let ctx = RunContext::new(world, graphs, system_runner);
-        phase_runner.run(ctx);
+        let started = Instant::now();
+        phase_runner.run(RunContext::new(world, systems, self.id));
+        let ran = started.elapsed();
 
         world.flush();
+        let flushed = started.elapsed() - ran;
+        world
+            .timings_mut()
+            .record_phase(self.id, self.name, ran, flushed);
 
         for child in self.children.values() {
             child.run(world, systems);
@@ -202,6 +232,221 @@ impl Schedule {
     }
 }
 
+impl Schedule {
+    fn fmt_depth(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        writeln!(f, "{:indent$}{}", "", self.name, indent = depth * 2)?;
+        for child in self.children.values() {
+            child.fmt_depth(f, depth + 1)?;
+        }
+
+        Ok(())
+    }
+
+    fn add_child_once<Main: Phase, Sub: Phase>(&mut self) -> bool {
+        self.contains(&ScheduleId::new::<Sub>()) || self.add_child::<Main, Sub>()
+    }
+
+    fn insert_before_once<Main: Phase, Before: Phase>(&mut self) -> bool {
+        self.contains(&ScheduleId::new::<Main>()) || self.insert_before::<Main, Before>()
+    }
+
+    fn insert_after_once<Main: Phase, After: Phase>(&mut self) -> bool {
+        self.contains(&ScheduleId::new::<Main>()) || self.insert_after::<Main, After>()
+    }
+
+    /// The schedule `id` is a child of.
+    fn parent(&self, id: &ScheduleId) -> Option<&Schedule> {
+        match self.has(id) {
+            true => Some(self),
+            false => self.children.values().iter().find_map(|c| c.parent(id)),
+        }
+    }
+
+    /// Reorders the children of every schedule so each order's first phase runs
+    /// before its second. Unconstrained children keep their relative order.
+    fn sort_phases(&mut self, orders: &[PhaseOrder]) -> Result<(), PhaseOrderError> {
+        let ids = self.children.keys();
+        let edges = orders
+            .iter()
+            .filter_map(|order| {
+                Some((
+                    ids.iter().position(|id| *id == order.first)?,
+                    ids.iter().position(|id| *id == order.second)?,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let mut incoming = vec![0; ids.len()];
+        edges.iter().for_each(|(_, second)| incoming[*second] += 1);
+
+        let mut sorted = Vec::with_capacity(ids.len());
+        while sorted.len() < ids.len() {
+            let next =
+                (0..ids.len()).find(|index| incoming[*index] == 0 && !sorted.contains(index));
+            let next = match next {
+                Some(next) => next,
+                None => {
+                    let cycle = (0..ids.len()).filter(|index| !sorted.contains(index));
+                    let names = cycle.map(|index| self.children.values()[index].name);
+                    return Err(PhaseOrderError::Cycle(names.collect()));
+                }
+            };
+
+            sorted.push(next);
+            for (_, second) in edges.iter().filter(|(first, _)| *first == next) {
+                incoming[*second] -= 1;
+            }
+        }
+
+        let sorted = sorted
+            .into_iter()
+            .map(|index| ids[index])
+            .collect::<Vec<_>>();
+        let rank = |id: &ScheduleId| sorted.iter().position(|other| other == id);
+        self.children.sort(|a, b| rank(a).cmp(&rank(b)));
+
+        for child in self.children.values_mut() {
+            child.sort_phases(orders)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_depth(f, 0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PhaseRelation {
+    Under,
+    Before,
+    After,
+}
+
+/// A phase registered relative to another phase that was never added.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PhaseError {
+    pub phase: &'static str,
+    pub anchor: &'static str,
+    pub relation: PhaseRelation,
+}
+
+impl Display for PhaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let relation = match self.relation {
+            PhaseRelation::Under => "under",
+            PhaseRelation::Before => "before",
+            PhaseRelation::After => "after",
+        };
+
+        write!(
+            f,
+            "Phase {} was registered {} {}, which was never added",
+            self.phase, relation, self.anchor
+        )
+    }
+}
+
+impl std::error::Error for PhaseError {}
+
+/// `first` runs before `second`. Both must be children of the same phase.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct PhaseOrder {
+    first: ScheduleId,
+    second: ScheduleId,
+    names: (&'static str, &'static str),
+}
+
+/// Phase ordering constraints that can't be satisfied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PhaseOrderError {
+    /// The phases have different parents, so they never run side by side.
+    NotSiblings {
+        first: &'static str,
+        second: &'static str,
+    },
+    /// The phases are ordered in a cycle.
+    Cycle(Vec<&'static str>),
+}
+
+impl Display for PhaseOrderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PhaseOrderError::NotSiblings { first, second } => write!(
+                f,
+                "Phase {} is ordered before {}, but they have different parents",
+                first, second
+            ),
+            PhaseOrderError::Cycle(phases) => {
+                write!(f, "Phases are ordered in a cycle: {}", phases.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for PhaseOrderError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    Phase(PhaseError),
+    Order(PhaseOrderError),
+    /// Only checked in debug builds.
+    Access(AccessConflict),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::Phase(error) => error.fmt(f),
+            BuildError::Order(error) => error.fmt(f),
+            BuildError::Access(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl From<PhaseError> for BuildError {
+    fn from(error: PhaseError) -> Self {
+        BuildError::Phase(error)
+    }
+}
+
+impl From<PhaseOrderError> for BuildError {
+    fn from(error: PhaseOrderError) -> Self {
+        BuildError::Order(error)
+    }
+}
+
+impl From<AccessConflict> for BuildError {
+    fn from(error: AccessConflict) -> Self {
+        BuildError::Access(error)
+    }
+}
+
+struct PendingPhase {
+    error: PhaseError,
+    apply: fn(&mut Schedule) -> bool,
+}
+
+impl PendingPhase {
+    fn new<P: Phase, Anchor: Phase>(
+        relation: PhaseRelation,
+        apply: fn(&mut Schedule) -> bool,
+    ) -> Self {
+        let error = PhaseError {
+            phase: std::any::type_name::<P>(),
+            anchor: std::any::type_name::<Anchor>(),
+            relation,
+        };
+
+        Self { error, apply }
+    }
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum SystemTag {
     Global,
@@ -250,6 +495,12 @@ impl SystemGraphs {
         }
     }
 
+    pub fn add_barrier(&mut self, phase: impl Phase) {
+        if let Some(graph) = self.graphs.get_mut(&phase.id()) {
+            graph.add_barrier();
+        }
+    }
+
     pub fn build(&mut self) {
         for graph in self.graphs.values_mut() {
             graph.build();
@@ -286,6 +537,8 @@ impl PhaseRunners {
 
 pub struct Systems {
     schedule: Schedule,
+    pending: Vec<PendingPhase>,
+    orders: Vec<PhaseOrder>,
     phases: PhaseRunners,
     active: DenseMap<SystemTag, SystemGraphs>,
     mode: RunMode,
@@ -302,10 +555,12 @@ impl Systems {
             RunMode::Parallel => SystemRunner::new(ParallelRunner),
         };
 
-        let schedule = Schedule::new(ScheduleId::new::<Root>());
+        let schedule = Schedule::from::<Root>();
 
         Self {
             active,
+            pending: vec![],
+            orders: vec![],
             phases: PhaseRunners::new(),
             mode,
             runner,
@@ -325,15 +580,30 @@ impl Systems {
         self.active.keys()
     }
 
-    pub fn systems(&self, id: &ScheduleId) -> Vec<&SystemGraph> {
-        let mut systems = vec![];
-        for group in self.active.values() {
-            if let Some(graph) = group.get(id) {
-                systems.push(graph);
-            }
+    pub fn systems<'a>(&'a self, id: &'a ScheduleId) -> impl Iterator<Item = &'a SystemGraph> {
+        self.active
+            .values()
+            .iter()
+            .filter_map(move |group| group.get(id))
+    }
+
+    /// Every system of the active tags, across all phases.
+    pub fn all(&self) -> impl Iterator<Item = &System> {
+        self.active
+            .values()
+            .iter()
+            .flat_map(|graphs| graphs.graphs.values())
+            .flat_map(|graph| graph.systems())
+    }
+
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> bool {
+        let mut found = false;
+        for system in self.all().filter(|s| s.name() == name) {
+            system.set_enabled(enabled);
+            found = true;
         }
 
-        systems
+        found
     }
 
     pub fn phase_runner(&self, id: &ScheduleId) -> Option<&dyn PhaseRunner> {
@@ -344,20 +614,93 @@ impl Systems {
         &self.schedule
     }
 
+    pub fn phase_order(&self) -> Vec<&Schedule> {
+        self.schedule.phase_order()
+    }
+
     pub fn add_phase<P: Phase>(&mut self) {
-        self.schedule.add_schedule(P::schedule());
+        if !self.schedule.contains(&ScheduleId::new::<P>()) {
+            self.schedule.add_schedule(P::schedule());
+            self.resolve_pending();
+        }
     }
 
+    /// Adds `Sub` under `Main`. If `Main` doesn't exist yet, the registration is
+    /// deferred until it does. Returns false if the registration was deferred.
     pub fn add_sub_phase<Main: Phase, Sub: Phase>(&mut self) -> bool {
-        self.schedule.add_child::<Main, Sub>()
+        let apply = Schedule::add_child_once::<Main, Sub>;
+        self.add_relative::<Sub, Main>(PhaseRelation::Under, apply)
     }
 
     pub fn insert_phase_before<Main: Phase, Before: Phase>(&mut self) -> bool {
-        self.schedule.insert_before::<Main, Before>()
+        let apply = Schedule::insert_before_once::<Main, Before>;
+        self.add_relative::<Main, Before>(PhaseRelation::Before, apply)
     }
 
     pub fn insert_phase_after<Main: Phase, After: Phase>(&mut self) -> bool {
-        self.schedule.insert_after::<Main, After>()
+        let apply = Schedule::insert_after_once::<Main, After>;
+        self.add_relative::<Main, After>(PhaseRelation::After, apply)
+    }
+
+    /// Runs `First` before `Second` once both are added as children of the same
+    /// phase. The constraint is applied when the systems are built.
+    pub fn order_phases<First: Phase, Second: Phase>(&mut self) {
+        let order = PhaseOrder {
+            first: ScheduleId::new::<First>(),
+            second: ScheduleId::new::<Second>(),
+            names: (
+                std::any::type_name::<First>(),
+                std::any::type_name::<Second>(),
+            ),
+        };
+
+        if !self.orders.contains(&order) {
+            self.orders.push(order);
+        }
+    }
+
+    fn sort_phases(&mut self) -> Result<(), PhaseOrderError> {
+        for order in &self.orders {
+            let first = self.schedule.parent(&order.first).map(|p| p.id());
+            let second = self.schedule.parent(&order.second).map(|p| p.id());
+            if let (Some(first), Some(second)) = (first, second) {
+                if first != second {
+                    return Err(PhaseOrderError::NotSiblings {
+                        first: order.names.0,
+                        second: order.names.1,
+                    });
+                }
+            }
+        }
+
+        self.schedule.sort_phases(&self.orders)
+    }
+
+    fn add_relative<P: Phase, Anchor: Phase>(
+        &mut self,
+        relation: PhaseRelation,
+        apply: fn(&mut Schedule) -> bool,
+    ) -> bool {
+        if apply(&mut self.schedule) {
+            self.resolve_pending();
+            true
+        } else {
+            self.pending
+                .push(PendingPhase::new::<P, Anchor>(relation, apply));
+            false
+        }
+    }
+
+    fn resolve_pending(&mut self) {
+        loop {
+            let count = self.pending.len();
+            self.pending
+                .retain(|pending| !(pending.apply)(&mut self.schedule));
+
+            if self.pending.is_empty() || self.pending.len() == count {
+                break;
+            }
+        }
     }
 
     pub fn add_system<M>(&mut self, phase: impl Phase, system: impl IntoSystem<M>) {
@@ -365,6 +708,11 @@ impl Systems {
         systems.add_system(phase, system);
     }
 
+    pub fn add_barrier(&mut self, phase: impl Phase) {
+        let systems = self.active.get_mut(&SystemTag::Global).unwrap();
+        systems.add_barrier(phase);
+    }
+
     pub fn add_phase_runner<P: Phase>(&mut self, runner: impl PhaseRunner) {
         self.phases.add::<P>(runner);
     }
@@ -380,10 +728,28 @@ impl Systems {
         };
     }
 
-    pub fn build(&mut self) {
+    pub fn build(&mut self) -> Result<(), BuildError> {
+        self.resolve_pending();
+        if let Some(pending) = self.pending.first() {
+            return Err(pending.error.clone().into());
+        }
+
+        self.sort_phases()?;
+
         for systems in self.active.values_mut() {
             systems.build();
         }
+
+        if cfg!(debug_assertions) {
+            for systems in self.active.values() {
+                for (id, graph) in systems.graphs.iter() {
+                    let phase = self.schedule.get(id).unwrap_or(&self.schedule);
+                    graph.validate(phase.name())?;
+                }
+            }
+        }
+
+        Ok(())
     }
 
     pub fn run(&self, id: ScheduleId, world: &mut World) {
@@ -435,3 +801,136 @@ impl SystemsInfo {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BuildError, Phase, PhaseOrderError, PhaseRelation, Root, ScheduleId};
+    use crate::world::World;
+
+    struct Simulate;
+    impl Phase for Simulate {}
+
+    struct Physics;
+    impl Phase for Physics {}
+
+    struct Audio;
+    impl Phase for Audio {}
+
+    struct Missing;
+    impl Phase for Missing {}
+
+    fn order(world: &World) -> Vec<ScheduleId> {
+        world.phase_order().iter().map(|s| s.id()).collect()
+    }
+
+    #[test]
+    fn deferred_sub_phase() {
+        let expected = vec![
+            ScheduleId::new::<Root>(),
+            ScheduleId::new::<Simulate>(),
+            ScheduleId::new::<Physics>(),
+            ScheduleId::new::<Audio>(),
+        ];
+
+        let mut world = World::new();
+        world
+            .add_phase_after::<Physics, Audio>()
+            .add_sub_phase::<Simulate, Physics>()
+            .add_phase::<Simulate>()
+            .build();
+        assert_eq!(order(&world), expected);
+
+        let mut world = World::new();
+        world
+            .add_phase::<Simulate>()
+            .add_sub_phase::<Simulate, Physics>()
+            .add_phase_after::<Physics, Audio>()
+            .build();
+        assert_eq!(order(&world), expected);
+    }
+
+    #[test]
+    fn duplicate_phases() {
+        let mut world = World::new();
+        world
+            .add_phase::<Simulate>()
+            .add_sub_phase::<Simulate, Physics>()
+            .add_sub_phase::<Simulate, Physics>()
+            .add_phase::<Simulate>()
+            .add_phase_after::<Physics, Audio>()
+            .add_phase_after::<Physics, Audio>()
+            .build();
+
+        assert_eq!(order(&world).len(), 4);
+        assert!(world.schedule().to_string().contains("Physics"));
+    }
+
+    #[test]
+    fn unknown_anchor() {
+        let mut world = World::new();
+        world
+            .add_phase::<Simulate>()
+            .insert_phase_before::<Audio, Missing>();
+
+        let error = match world.try_build() {
+            Err(BuildError::Phase(error)) => error,
+            _ => panic!("expected a phase error"),
+        };
+        assert_eq!(error.relation, PhaseRelation::Before);
+        assert!(error.phase.ends_with("Audio"));
+        assert!(error.anchor.ends_with("Missing"));
+    }
+
+    #[test]
+    fn ordered_sub_phases() {
+        let mut world = World::new();
+        world
+            .add_phase::<Simulate>()
+            .add_sub_phase::<Simulate, Audio>()
+            .add_sub_phase::<Simulate, Physics>()
+            .before::<Audio>()
+            .add_phase::<Missing>()
+            .after::<Simulate>()
+            .build();
+
+        let expected = vec![
+            ScheduleId::new::<Root>(),
+            ScheduleId::new::<Simulate>(),
+            ScheduleId::new::<Physics>(),
+            ScheduleId::new::<Audio>(),
+            ScheduleId::new::<Missing>(),
+        ];
+        assert_eq!(order(&world), expected);
+    }
+
+    #[test]
+    fn invalid_phase_order() {
+        let mut world = World::new();
+        world
+            .add_phase::<Simulate>()
+            .add_phase::<Audio>()
+            .before::<Simulate>()
+            .add_phase::<Physics>()
+            .after::<Simulate>()
+            .before::<Audio>();
+
+        let error = match world.try_build() {
+            Err(BuildError::Order(PhaseOrderError::Cycle(phases))) => phases,
+            _ => panic!("expected a cycle"),
+        };
+        assert_eq!(error.len(), 3);
+
+        let mut world = World::new();
+        world
+            .add_phase::<Simulate>()
+            .add_sub_phase::<Simulate, Physics>()
+            .before::<Audio>()
+            .add_phase::<Audio>();
+
+        let error = world.try_build().err();
+        assert!(matches!(
+            error,
+            Some(BuildError::Order(PhaseOrderError::NotSiblings { .. }))
+        ));
+    }
+}
@@ -0,0 +1,403 @@
+use super::schedule::{Phase, ScheduleId};
+use crate::core::{DenseMap, Resource};
+use std::{
+    cmp::Reverse,
+    fmt::Display,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Wall time of a system's most recent runs, kept in a fixed size ring.
+pub struct SystemTiming {
+    samples: [AtomicU64; Self::SAMPLES],
+    next: AtomicUsize,
+    frame: AtomicU64,
+}
+
+impl SystemTiming {
+    pub const SAMPLES: usize = 32;
+
+    pub fn new() -> Self {
+        Self {
+            samples: std::array::from_fn(|_| AtomicU64::new(0)),
+            next: AtomicUsize::new(0),
+            frame: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record(&self, frame: u64, elapsed: Duration) {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % Self::SAMPLES;
+        let nanos = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+        self.samples[index].store(nanos, Ordering::Relaxed);
+        self.frame.store(frame, Ordering::Relaxed);
+    }
+
+    /// The frame of the system's last run. Zero if it never ran.
+    pub fn frame(&self) -> u64 {
+        self.frame.load(Ordering::Relaxed)
+    }
+
+    pub fn last(&self) -> Duration {
+        match self.next.load(Ordering::Relaxed) {
+            0 => Duration::ZERO,
+            next => self.sample((next - 1) % Self::SAMPLES),
+        }
+    }
+
+    /// Recorded samples, oldest first.
+    pub fn samples(&self) -> Vec<Duration> {
+        let next = self.next.load(Ordering::Relaxed);
+        let start = next.saturating_sub(Self::SAMPLES);
+        (start..next)
+            .map(|index| self.sample(index % Self::SAMPLES))
+            .collect()
+    }
+
+    fn sample(&self, index: usize) -> Duration {
+        Duration::from_nanos(self.samples[index].load(Ordering::Relaxed))
+    }
+}
+
+impl Default for SystemTiming {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhaseTime {
+    pub name: &'static str,
+    pub systems: Duration,
+    pub flush: Duration,
+}
+
+impl PhaseTime {
+    pub fn total(&self) -> Duration {
+        self.systems + self.flush
+    }
+}
+
+/// Time spent in each phase during the current frame, where a frame is one `World::run`.
+pub struct FrameTimings {
+    frame: u64,
+    phases: DenseMap<ScheduleId, PhaseTime>,
+}
+
+impl FrameTimings {
+    pub fn new() -> Self {
+        Self {
+            frame: 0,
+            phases: DenseMap::new(),
+        }
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+        for phase in self.phases.values_mut() {
+            phase.systems = Duration::ZERO;
+            phase.flush = Duration::ZERO;
+        }
+    }
+
+    pub fn record_phase(
+        &mut self,
+        id: ScheduleId,
+        name: &'static str,
+        systems: Duration,
+        flush: Duration,
+    ) {
+        match self.phases.get_mut(&id) {
+            Some(phase) => {
+                phase.systems += systems;
+                phase.flush += flush;
+            }
+            None => {
+                self.phases.insert(
+                    id,
+                    PhaseTime {
+                        name,
+                        systems,
+                        flush,
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn phase(&self, id: &ScheduleId) -> Option<&PhaseTime> {
+        self.phases.get(id)
+    }
+
+    pub fn phases(&self) -> impl Iterator<Item = (&ScheduleId, &PhaseTime)> {
+        self.phases.iter()
+    }
+}
+
+impl Default for FrameTimings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Soft time budgets for a frame and its phases. A frame over budget records a
+/// `BudgetReport`, at most once per `interval`. The world doesn't print reports;
+/// the game or a diagnostics layer reads them through `report`.
+pub struct FrameBudget {
+    frame: Duration,
+    phases: DenseMap<ScheduleId, Duration>,
+    top: usize,
+    interval: Duration,
+    reported: Option<Instant>,
+    suppressed: usize,
+    report: Option<BudgetReport>,
+}
+
+impl FrameBudget {
+    pub const TOP_SYSTEMS: usize = 5;
+    pub const INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new(frame: Duration) -> Self {
+        Self {
+            frame,
+            phases: DenseMap::new(),
+            top: Self::TOP_SYSTEMS,
+            interval: Self::INTERVAL,
+            reported: None,
+            suppressed: 0,
+            report: None,
+        }
+    }
+
+    pub fn frame(&self) -> Duration {
+        self.frame
+    }
+
+    pub fn set_frame(&mut self, budget: Duration) -> &mut Self {
+        self.frame = budget;
+        self
+    }
+
+    pub fn set_phase<P: Phase>(&mut self, budget: Duration) -> &mut Self {
+        self.phases.insert(ScheduleId::new::<P>(), budget);
+        self
+    }
+
+    /// Number of systems listed in a report.
+    pub fn set_top(&mut self, top: usize) -> &mut Self {
+        self.top = top;
+        self
+    }
+
+    /// Minimum time between reports.
+    pub fn set_interval(&mut self, interval: Duration) -> &mut Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The latest report, kept until a later frame replaces it.
+    pub fn report(&self) -> Option<&BudgetReport> {
+        self.report.as_ref()
+    }
+
+    /// Checks a finished frame, returning a report if it broke a budget and the
+    /// last report is older than the interval.
+    pub fn check(
+        &mut self,
+        now: Instant,
+        elapsed: Duration,
+        timings: &FrameTimings,
+        systems: impl Iterator<Item = (&'static str, Duration)>,
+    ) -> Option<&BudgetReport> {
+        let over = timings
+            .phases()
+            .filter(|(id, phase)| {
+                self.phases
+                    .get(id)
+                    .is_some_and(|budget| phase.total() > *budget)
+            })
+            .map(|(_, phase)| phase.name)
+            .collect::<Vec<_>>();
+
+        if elapsed <= self.frame && over.is_empty() {
+            return None;
+        }
+
+        if let Some(reported) = self.reported {
+            if now.duration_since(reported) < self.interval {
+                self.suppressed += 1;
+                return None;
+            }
+        }
+
+        let mut systems = systems.collect::<Vec<_>>();
+        systems.sort_by_key(|(_, elapsed)| Reverse(*elapsed));
+        systems.truncate(self.top);
+
+        self.reported = Some(now);
+        self.report = Some(BudgetReport {
+            frame: timings.frame(),
+            elapsed,
+            budget: self.frame,
+            systems,
+            phases: timings.phases().map(|(_, phase)| *phase).collect(),
+            over,
+            suppressed: std::mem::take(&mut self.suppressed),
+        });
+
+        self.report.as_ref()
+    }
+}
+
+impl Resource for FrameBudget {}
+
+/// Breadcrumbs for a frame that broke its budget.
+#[derive(Debug, Clone)]
+pub struct BudgetReport {
+    pub frame: u64,
+    pub elapsed: Duration,
+    pub budget: Duration,
+    /// The slowest systems of the frame, slowest first.
+    pub systems: Vec<(&'static str, Duration)>,
+    pub phases: Vec<PhaseTime>,
+    /// Phases over their own budget.
+    pub over: Vec<&'static str>,
+    /// Breaches skipped by the rate limit since the previous report.
+    pub suppressed: usize,
+}
+
+impl Display for BudgetReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Frame {} took {:?} (budget {:?})",
+            self.frame, self.elapsed, self.budget
+        )?;
+
+        for (name, elapsed) in &self.systems {
+            writeln!(f, "  system {}: {:?}", name, elapsed)?;
+        }
+
+        for phase in &self.phases {
+            let over = match self.over.contains(&phase.name) {
+                true => " over budget",
+                false => "",
+            };
+            writeln!(
+                f,
+                "  phase {}: {:?} systems, {:?} flush{}",
+                phase.name, phase.systems, phase.flush, over
+            )?;
+        }
+
+        if self.suppressed > 0 {
+            writeln!(f, "  {} reports suppressed", self.suppressed)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrameBudget, FrameTimings, SystemTiming};
+    use crate::{
+        system::schedule::{Phase, Root, ScheduleId},
+        world::World,
+    };
+    use std::time::{Duration, Instant};
+
+    struct Update;
+    impl Phase for Update {}
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn timings() -> FrameTimings {
+        let mut timings = FrameTimings::new();
+        timings.begin_frame();
+        timings.record_phase(ScheduleId::new::<Update>(), "Update", ms(12), ms(3));
+        timings
+    }
+
+    #[test]
+    fn breach_report() {
+        let mut budget = FrameBudget::new(ms(16));
+        budget.set_top(2).set_phase::<Update>(ms(10));
+        let systems = [("physics", ms(4)), ("ai", ms(6)), ("audio", ms(1))];
+        let now = Instant::now();
+
+        let report = budget.check(now, ms(15), &timings(), systems.into_iter());
+        let report = report.unwrap();
+        assert_eq!(report.systems, [("ai", ms(6)), ("physics", ms(4))]);
+        assert_eq!(report.over, ["Update"]);
+        assert_eq!(report.phases[0].total(), ms(15));
+
+        let text = report.to_string();
+        assert!(text.contains("system ai: 6ms"));
+        assert!(text.contains("phase Update: 12ms systems, 3ms flush over budget"));
+
+        budget.set_phase::<Update>(ms(20));
+        let now = now + FrameBudget::INTERVAL;
+        let report = budget.check(now, ms(15), &timings(), systems.into_iter());
+        assert!(report.is_none());
+    }
+
+    #[test]
+    fn rate_limit() {
+        let mut budget = FrameBudget::new(ms(16));
+        budget.set_interval(ms(100));
+        let now = Instant::now();
+        let mut check = |now: Instant| {
+            let report = budget.check(now, ms(20), &timings(), std::iter::empty());
+            report.map(|report| report.suppressed)
+        };
+
+        assert_eq!(check(now), Some(0));
+        assert_eq!(check(now + ms(10)), None);
+        assert_eq!(check(now + ms(50)), None);
+        assert_eq!(check(now + ms(100)), Some(2));
+    }
+
+    #[test]
+    fn bounded_samples() {
+        let timing = SystemTiming::new();
+        assert_eq!(timing.last(), Duration::ZERO);
+
+        let runs = SystemTiming::SAMPLES as u64 + 8;
+        for run in 1..=runs {
+            timing.record(run, Duration::from_nanos(run));
+        }
+
+        let samples = timing.samples();
+        assert_eq!(samples.len(), SystemTiming::SAMPLES);
+        assert_eq!(samples[0], Duration::from_nanos(9));
+        assert_eq!(timing.last(), Duration::from_nanos(runs));
+        assert_eq!(timing.frame(), runs);
+    }
+
+    #[test]
+    fn report_slow_frame() {
+        fn slow() {
+            std::thread::sleep(Duration::from_millis(2));
+        }
+
+        let mut world = World::new();
+        world
+            .add_resource(FrameBudget::new(Duration::from_millis(1)))
+            .add_system(Root, slow)
+            .build();
+        world.run(Root);
+
+        let report = world.resource::<FrameBudget>().report().unwrap();
+        assert_eq!(report.frame, 1);
+        assert_eq!(report.systems[0].0, std::any::type_name_of_val(&slow));
+        assert!(report.systems[0].1 >= Duration::from_millis(2));
+        let mut phases = report.phases.iter();
+        assert!(phases.any(|phase| phase.name.ends_with("Root")));
+    }
+}
@@ -90,8 +90,9 @@ impl Drop for TaskPool {
 
 impl SystemArg for &TaskPool {
     type Item<'a> = &'a TaskPool;
+    type State = ();
 
-    fn get<'a>(world: &'a super::world::World) -> Self::Item<'a> {
+    fn get<'a>(world: &'a super::world::World, _: &'a mut ()) -> Self::Item<'a> {
         world.tasks()
     }
 
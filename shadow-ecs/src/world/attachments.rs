@@ -0,0 +1,297 @@
+use crate::core::{DenseMap, Entities, Entity};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+type Cleanup = Box<dyn FnOnce(Entity, Box<dyn Any + Send>) + Send>;
+
+struct Attachment {
+    ty: TypeId,
+    value: Box<dyn Any + Send>,
+    cleanup: Cleanup,
+}
+
+impl Attachment {
+    fn release(self, entity: Entity) {
+        (self.cleanup)(entity, self.value)
+    }
+}
+
+#[derive(Default)]
+struct AttachmentState {
+    attachments: HashMap<Entity, Vec<Attachment>>,
+    registered: Vec<Entity>,
+}
+
+/// External resources bound to entities. Each attachment is cleaned up exactly once:
+/// when its entity is despawned (children before their parents), when it is replaced
+/// by another attachment of the same type, or when the world is dropped.
+/// Attachments registered for an entity that was already despawned are cleaned up
+/// at the end of the next flush.
+#[derive(Clone, Default)]
+pub struct EntityAttachments {
+    state: Arc<Mutex<AttachmentState>>,
+}
+
+impl EntityAttachments {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attaches `value` to `entity`, calling `cleanup` with it when the entity goes away.
+    pub fn register<A: Send + 'static>(
+        &self,
+        entity: Entity,
+        value: A,
+        cleanup: impl FnOnce(Entity, A) + Send + 'static,
+    ) {
+        let attachment = Attachment {
+            ty: TypeId::of::<A>(),
+            value: Box::new(value),
+            cleanup: Box::new(move |entity, value| {
+                if let Ok(value) = value.downcast::<A>() {
+                    cleanup(entity, *value);
+                }
+            }),
+        };
+
+        let replaced = {
+            let mut state = self.state.lock().unwrap();
+            state.registered.push(entity);
+
+            let attachments = state.attachments.entry(entity).or_default();
+            match attachments.iter().position(|a| a.ty == attachment.ty) {
+                Some(index) => Some(std::mem::replace(&mut attachments[index], attachment)),
+                None => {
+                    attachments.push(attachment);
+                    None
+                }
+            }
+        };
+
+        if let Some(replaced) = replaced {
+            replaced.release(entity);
+        }
+    }
+
+    /// Attaches `value` to `entity`, dropping it when the entity goes away.
+    pub fn attach<A: Send + 'static>(&self, entity: Entity, value: A) {
+        self.register(entity, value, |_, value| drop(value));
+    }
+
+    /// Removes an attachment without cleaning it up.
+    pub fn detach<A: Send + 'static>(&self, entity: &Entity) -> Option<A> {
+        let mut state = self.state.lock().unwrap();
+        let attachments = state.attachments.get_mut(entity)?;
+        let index = attachments.iter().position(|a| a.ty == TypeId::of::<A>())?;
+        let attachment = attachments.remove(index);
+        if attachments.is_empty() {
+            state.attachments.remove(entity);
+        }
+
+        attachment.value.downcast::<A>().ok().map(|value| *value)
+    }
+
+    pub fn contains<A: Send + 'static>(&self, entity: &Entity) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .attachments
+            .get(entity)
+            .is_some_and(|a| a.iter().any(|a| a.ty == TypeId::of::<A>()))
+    }
+
+    pub fn len(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.attachments.values().map(|a| a.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cleans up the attachments of `despawned`, which is in despawn order
+    /// (parents before children). Children are released first.
+    pub(crate) fn release(&self, despawned: &[Entity]) {
+        let released = {
+            let mut state = self.state.lock().unwrap();
+            if state.attachments.is_empty() {
+                return;
+            }
+
+            let mut released = vec![];
+            for entity in despawned.iter().rev() {
+                if let Some(attachments) = state.attachments.remove(entity) {
+                    released.push((*entity, attachments));
+                }
+            }
+
+            released
+        };
+
+        Self::cleanup(released);
+    }
+
+    /// Cleans up attachments registered since the last sweep for entities that are gone.
+    pub(crate) fn sweep(&self, entities: &Entities) {
+        let released = {
+            let mut state = self.state.lock().unwrap();
+            let registered = std::mem::take(&mut state.registered);
+            let mut released = vec![];
+            for entity in registered {
+                if !entities.contains(&entity) {
+                    if let Some(attachments) = state.attachments.remove(&entity) {
+                        released.push((entity, attachments));
+                    }
+                }
+            }
+
+            released
+        };
+
+        Self::cleanup(released);
+    }
+
+    pub(crate) fn release_all(&self) {
+        let released = {
+            let mut state = self.state.lock().unwrap();
+            state.registered.clear();
+            state.attachments.drain().collect::<Vec<_>>()
+        };
+
+        Self::cleanup(released);
+    }
+
+    /// Moves every attachment of `other` onto the entities `map` maps them to.
+    pub(crate) fn merge(&self, other: &EntityAttachments, map: &DenseMap<Entity, Entity>) {
+        let moved = {
+            let mut other = other.state.lock().unwrap();
+            other.registered.clear();
+            other.attachments.drain().collect::<Vec<_>>()
+        };
+
+        let mut state = self.state.lock().unwrap();
+        for (entity, attachments) in moved {
+            let entity = map.get(&entity).copied().unwrap_or(entity);
+            state.registered.push(entity);
+            state
+                .attachments
+                .entry(entity)
+                .or_default()
+                .extend(attachments);
+        }
+    }
+
+    fn cleanup(released: Vec<(Entity, Vec<Attachment>)>) {
+        for (entity, attachments) in released {
+            for attachment in attachments {
+                attachment.release(entity);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::{Entity, Resource},
+        system::schedule::Root,
+        world::{
+            attachments::EntityAttachments,
+            event::{Despawn, Events},
+            World,
+        },
+    };
+    use std::sync::{Arc, Mutex};
+
+    type Log = Arc<Mutex<Vec<(Entity, u32)>>>;
+
+    fn record(log: &Log) -> impl FnOnce(Entity, u32) + Send + 'static {
+        let log = log.clone();
+        move |entity, id| log.lock().unwrap().push((entity, id))
+    }
+
+    #[test]
+    fn despawn_same_frame() {
+        struct Target(Option<Entity>);
+        impl Resource for Target {}
+
+        let log = Log::default();
+        let cleanup = log.clone();
+        let mut world = World::new();
+        world
+            .add_resource(Target(None))
+            .add_system(
+                Root,
+                move |target: &Target, attachments: &EntityAttachments, events: &Events| {
+                    let entity = target.0.unwrap();
+                    events.add(Despawn::new(entity));
+                    attachments.register(entity, 1, record(&cleanup));
+                },
+            )
+            .build();
+
+        let entity = world.spawn(None);
+        world.resource_mut::<Target>().0 = Some(entity);
+        world.run(Root);
+
+        assert_eq!(*log.lock().unwrap(), [(entity, 1)]);
+        assert!(world.attachments().is_empty());
+
+        world.attachments().register(entity, 2, record(&log));
+        world.flush();
+        assert_eq!(log.lock().unwrap().last(), Some(&(entity, 2)));
+    }
+
+    #[test]
+    fn cascade_releases_children_first() {
+        let log = Log::default();
+        let mut world = World::new();
+        let parent = world.spawn(None);
+        let child = world.spawn(Some(parent));
+        let grandchild = world.spawn(Some(child));
+
+        let attachments = world.attachments();
+        attachments.register(parent, 0, record(&log));
+        attachments.register(child, 1, record(&log));
+        attachments.register(grandchild, 2, record(&log));
+
+        world.events().add(Despawn::new(parent));
+        world.flush();
+
+        let log = log.lock().unwrap();
+        assert_eq!(*log, [(grandchild, 2), (child, 1), (parent, 0)]);
+    }
+
+    #[test]
+    fn replace_and_detach() {
+        let log = Log::default();
+        let mut world = World::new();
+        let entity = world.spawn(None);
+
+        let attachments = world.attachments();
+        attachments.register(entity, 1, record(&log));
+        attachments.register(entity, 2, record(&log));
+        assert_eq!(*log.lock().unwrap(), [(entity, 1)]);
+
+        attachments.attach(entity, "name");
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments.detach::<&str>(&entity), Some("name"));
+        assert!(!attachments.contains::<&str>(&entity));
+
+        world.despawn(&entity);
+        assert_eq!(*log.lock().unwrap(), [(entity, 1), (entity, 2)]);
+    }
+
+    #[test]
+    fn release_on_drop() {
+        let log = Log::default();
+        let mut world = World::new();
+        let entity = world.spawn(None);
+        world.attachments().register(entity, 7, record(&log));
+
+        drop(world);
+        assert_eq!(*log.lock().unwrap(), [(entity, 7)]);
+    }
+}
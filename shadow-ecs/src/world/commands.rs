@@ -0,0 +1,223 @@
+use super::{
+    event::{AddComponents, Despawn, ErasedEvent, Event, Events, RemoveComponents, Spawn},
+    World,
+};
+use crate::{
+    core::Entity,
+    system::{access::WorldAccess, SystemArg},
+};
+use std::{cell::Cell, sync::Mutex};
+
+thread_local! {
+    static SYSTEM: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Runs `f` as the system at `index` in its phase graph, so the commands it queues
+/// are applied in graph order.
+pub(crate) fn run_as<R>(index: usize, f: impl FnOnce() -> R) -> R {
+    let previous = SYSTEM.with(|system| system.replace(Some(index)));
+    let result = f();
+    SYSTEM.with(|system| system.set(previous));
+    result
+}
+
+/// Structural changes queued by a system. Each system gets its own queue, so
+/// systems using commands still run in parallel. The queues are moved into the
+/// world's events when the world flushes, after the systems of the phase (or
+/// stage) finish.
+#[derive(Default)]
+pub struct DeferredCommands {
+    events: Vec<ErasedEvent>,
+}
+
+impl DeferredCommands {
+    pub fn spawn(&mut self, spawn: Spawn) -> &mut Self {
+        self.add(spawn)
+    }
+
+    pub fn add_components(&mut self, components: AddComponents) -> &mut Self {
+        self.add(components)
+    }
+
+    pub fn remove_components(&mut self, components: RemoveComponents) -> &mut Self {
+        self.add(components)
+    }
+
+    pub fn despawn(&mut self, entity: Entity) -> &mut Self {
+        self.add(Despawn::new(entity))
+    }
+
+    pub fn add(&mut self, event: impl Event) -> &mut Self {
+        self.events.push(ErasedEvent::new(event));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+/// Queues handed to systems since the last flush, each keyed by the system's index
+/// in its graph and the order it was handed out. Queues are boxed so handing out a
+/// new one never moves the ones already in use, and they are kept for reuse once
+/// flushed.
+#[derive(Default)]
+pub(crate) struct CommandQueues {
+    queues: Mutex<(usize, Vec<KeyedQueue>)>,
+}
+
+/// A queue keyed by the system's graph index and the order it was handed out.
+/// Queues taken outside a system, such as by observers, sort after every system.
+type KeyedQueue = ((usize, usize), Box<DeferredCommands>);
+
+impl CommandQueues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    fn acquire(&self) -> &mut DeferredCommands {
+        let system = SYSTEM.with(|system| system.get()).unwrap_or(usize::MAX);
+        let mut queues = self.queues.lock().unwrap();
+        let (used, queues) = &mut *queues;
+        if *used == queues.len() {
+            queues.push(Default::default());
+        }
+
+        let (key, queue) = &mut queues[*used];
+        *key = (system, *used);
+        let queue: *mut DeferredCommands = &mut **queue;
+        *used += 1;
+
+        // Each queue is handed out once until `flush`, which takes `&mut self`.
+        unsafe { &mut *queue }
+    }
+
+    /// Moves the queued commands into `events` in graph order, so the result doesn't
+    /// depend on which system took its queue first. Queues of the same system keep the
+    /// order they were handed out in.
+    pub fn flush(&mut self, events: &Events) {
+        let (used, queues) = self.queues.get_mut().unwrap();
+        let queues = &mut queues[..*used];
+        queues.sort_unstable_by_key(|(key, _)| *key);
+        for (_, queue) in queues {
+            events.extend(queue.events.drain(..));
+        }
+
+        *used = 0;
+    }
+}
+
+impl SystemArg for &mut DeferredCommands {
+    type Item<'a> = &'a mut DeferredCommands;
+    type State = ();
+
+    fn get<'a>(world: &'a World, _: &'a mut ()) -> Self::Item<'a> {
+        world.commands.acquire()
+    }
+
+    fn access() -> Vec<WorldAccess> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_as, DeferredCommands};
+    use crate::{
+        core::{Component, Entities, Resource},
+        system::schedule::Root,
+        world::{
+            event::{RemoveComponents, Spawn},
+            query::Query,
+            World,
+        },
+    };
+
+    struct Health(u32);
+    impl Component for Health {}
+
+    #[derive(Default)]
+    struct Seen(Vec<usize>);
+    impl Resource for Seen {}
+
+    fn spawn(commands: &mut DeferredCommands) {
+        commands.spawn(Spawn::new().with(Health(10)));
+    }
+
+    fn count(entities: &Entities, seen: &mut Seen) {
+        seen.0.push(entities.len());
+    }
+
+    #[test]
+    fn commands_apply_after_phase() {
+        let mut world = World::new();
+        world.register::<Health>();
+        world
+            .init_resource::<Seen>()
+            .add_system(Root, spawn)
+            .add_system(Root, spawn)
+            .add_system(Root, count)
+            .build();
+
+        world.run(Root);
+        world.run(Root);
+        assert_eq!(world.resource::<Seen>().0, [0, 2]);
+        assert_eq!(world.entities().len(), 4);
+        assert!(Query::<&Health>::new(&world).all(|health| health.0 == 10));
+        assert_eq!(Query::<&Health>::new(&world).count(), 4);
+    }
+
+    #[test]
+    fn commands_remove_and_despawn() {
+        let mut world = World::new();
+        world.register::<Health>();
+        let kept = world.spawn(None);
+        world.add_component(&kept, Health(1));
+        let removed = world.spawn(None);
+        world.add_component(&removed, Health(2));
+
+        world
+            .add_system(Root, move |commands: &mut DeferredCommands| {
+                commands
+                    .remove_components(RemoveComponents::new(kept).with::<Health>())
+                    .despawn(removed);
+            })
+            .build();
+        world.run(Root);
+
+        assert!(world.entities().contains(&kept));
+        assert!(!world.entities().contains(&removed));
+        assert_eq!(Query::<&Health>::new(&world).count(), 0);
+    }
+
+    #[test]
+    fn commands_apply_in_graph_order() {
+        struct Order(usize);
+        impl Component for Order {}
+
+        let mut world = World::new();
+        world.register::<Order>();
+
+        // Parallel systems can take their queues in any order.
+        for index in (0..4).rev() {
+            run_as(index, || {
+                world
+                    .commands
+                    .acquire()
+                    .spawn(Spawn::new().with(Order(index)));
+            });
+        }
+        world.commands.acquire().spawn(Spawn::new().with(Order(4)));
+        world.flush();
+
+        let order = Query::<&Order>::new(&world)
+            .map(|order| order.0)
+            .collect::<Vec<_>>();
+        assert_eq!(order, [0, 1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,184 @@
+use super::World;
+use crate::{
+    archetype::table::EntityRow,
+    core::{ColumnCell, Component, ComponentId, Entity},
+};
+use std::sync::Arc;
+
+/// Builds the value a component starts from when data leaves it out.
+#[derive(Clone)]
+pub struct ComponentDefault(Arc<dyn Fn() -> ColumnCell + Send + Sync>);
+
+impl ComponentDefault {
+    pub fn new<C: Component>(default: impl Fn() -> C + Send + Sync + 'static) -> Self {
+        Self(Arc::new(move || ColumnCell::from(default())))
+    }
+
+    pub fn get(&self) -> ColumnCell {
+        (self.0)()
+    }
+}
+
+impl World {
+    pub fn register_default<C: Component + Default>(&mut self) -> &mut Self {
+        self.register_default_with::<C>(C::default)
+    }
+
+    pub fn register_default_with<C: Component>(
+        &mut self,
+        default: impl Fn() -> C + Send + Sync + 'static,
+    ) -> &mut Self {
+        let id = ComponentId::new::<C>();
+        if !self.components.contains(&id) {
+            self.register::<C>();
+        }
+
+        self.components
+            .add_extension(&id, ComponentDefault::new(default));
+        self
+    }
+
+    pub fn default_component(&self, id: &ComponentId) -> Option<ColumnCell> {
+        if !self.components.contains(id) {
+            return None;
+        }
+
+        let meta = self.components.meta(id);
+        meta.extension::<ComponentDefault>().map(|d| d.get())
+    }
+
+    /// Adds the default value of each component in `ids` that `entity` doesn't have yet.
+    /// Returns the ids that have no registered default.
+    pub fn add_defaults(&mut self, entity: &Entity, ids: &[ComponentId]) -> Vec<ComponentId> {
+        let mut row = EntityRow::new();
+        let mut missing = vec![];
+        for id in ids {
+            if self.archetypes.has_component(entity, id) {
+                continue;
+            }
+
+            match self.default_component(id) {
+                Some(cell) => {
+                    row.add_cell(*id, cell);
+                }
+                None => missing.push(*id),
+            }
+        }
+
+        if !row.is_empty() {
+            self.archetypes.add_components(entity, row);
+        }
+
+        missing
+    }
+
+    /// Applies `patch` to the entity's component. If the entity doesn't have one, the
+    /// patch is applied to the registered default and the result is added.
+    /// Returns false if there is neither a component nor a default to patch.
+    pub fn patch_component<C: Component>(
+        &mut self,
+        entity: &Entity,
+        patch: impl FnOnce(&mut C),
+    ) -> bool {
+        let archetypes = &self.archetypes;
+        let component = archetypes
+            .entity_archetype(entity)
+            .and_then(|id| archetypes.get(&id))
+            .and_then(|archetype| archetype.component_mut::<C>(entity, archetypes.tick()));
+
+        if let Some(component) = component {
+            patch(component);
+            return true;
+        }
+
+        match self.default_component(&ComponentId::new::<C>()) {
+            Some(cell) => {
+                let mut component = cell.take::<C>();
+                patch(&mut component);
+                self.add_component(entity, component);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::{Component, ComponentId, Entity},
+        world::World,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Light {
+        color: [f32; 3],
+        intensity: f32,
+        range: f32,
+    }
+
+    impl Default for Light {
+        fn default() -> Self {
+            Self {
+                color: [1.0; 3],
+                intensity: 1.0,
+                range: 10.0,
+            }
+        }
+    }
+
+    impl Component for Light {}
+
+    struct Tag;
+    impl Component for Tag {}
+
+    fn light(world: &World, entity: &Entity) -> Option<Light> {
+        let archetypes = world.archetypes();
+        let archetype = archetypes.get(&archetypes.entity_archetype(entity)?)?;
+        archetype.component::<Light>(entity).cloned()
+    }
+
+    #[test]
+    fn add_missing_defaults() {
+        let mut world = World::new();
+        world.register_default::<Light>().register::<Tag>();
+
+        let entity = world.spawn(None);
+        let ids = [ComponentId::new::<Light>(), ComponentId::new::<Tag>()];
+        let missing = world.add_defaults(&entity, &ids);
+
+        assert_eq!(missing, vec![ComponentId::new::<Tag>()]);
+        assert_eq!(light(&world, &entity), Some(Light::default()));
+    }
+
+    #[test]
+    fn patch_over_template() {
+        let mut world = World::new();
+        world.register_default_with(|| Light {
+            range: 5.0,
+            ..Default::default()
+        });
+
+        let template = Light {
+            color: [0.5, 0.2, 0.1],
+            intensity: 3.0,
+            range: 20.0,
+        };
+        let instance = world.spawn(None);
+        world.add_component(&instance, template.clone());
+        assert!(world.patch_component::<Light>(&instance, |light| light.intensity = 8.0));
+
+        let expected = Light {
+            intensity: 8.0,
+            ..template
+        };
+        assert_eq!(light(&world, &instance), Some(expected));
+
+        let empty = world.spawn(None);
+        assert!(world.patch_component::<Light>(&empty, |light| light.color = [0.0; 3]));
+        let light = light(&world, &empty).unwrap();
+        assert_eq!((light.color, light.range), ([0.0; 3], 5.0));
+
+        assert!(!world.patch_component::<Tag>(&empty, |_| {}));
+    }
+}
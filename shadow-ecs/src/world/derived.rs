@@ -0,0 +1,460 @@
+use super::{
+    event::{Event, Events},
+    World,
+};
+use crate::{
+    core::{Component, ComponentId, Entity, Resource},
+    system::schedule::{Phase, ScheduleId},
+};
+use std::collections::{HashMap, HashSet};
+
+/// The components a derivation is computed from. Their last values are cached per
+/// entity, so a derived component is only recomputed when one of them changes.
+pub trait DerivedSources: Clone + PartialEq + Send + Sync + 'static {
+    fn ids() -> Vec<ComponentId>;
+    fn get(world: &World, entity: &Entity) -> Option<Self>;
+}
+
+macro_rules! impl_derived_sources_for_tuples {
+    ($(($($name:ident),+)),+) => {
+        $(
+            impl<$($name: Component + Clone + PartialEq),+> DerivedSources for ($($name,)+) {
+                fn ids() -> Vec<ComponentId> {
+                    vec![$(ComponentId::new::<$name>()),+]
+                }
+
+                fn get(world: &World, entity: &Entity) -> Option<Self> {
+                    let archetypes = world.archetypes();
+                    let archetype = archetypes.get(&archetypes.entity_archetype(entity)?)?;
+                    Some(($(archetype.component::<$name>(entity)?.clone(),)+))
+                }
+            }
+        )+
+    };
+}
+
+impl_derived_sources_for_tuples!((A), (A, B), (A, B, C), (A, B, C, D));
+
+/// A component computed from other components on the same entity. It is added when
+/// all of its sources are present and removed when one of them goes away.
+pub trait DerivedComponent: Component + Clone {
+    type Sources: DerivedSources;
+
+    /// If true, `compute` also receives the parent's derived value, and parents are
+    /// always updated before their children.
+    const HIERARCHICAL: bool = false;
+
+    fn compute(sources: &Self::Sources, parent: Option<&Self>) -> Self;
+}
+
+struct Cached<S> {
+    sources: S,
+    parent: Option<Entity>,
+}
+
+/// Per-entity cache of a derived component's sources.
+pub struct Derived<D: DerivedComponent> {
+    cache: HashMap<Entity, Cached<D::Sources>>,
+    forced: HashSet<Entity>,
+    recomputed: usize,
+}
+
+impl<D: DerivedComponent> Derived<D> {
+    pub fn new() -> Self {
+        Self {
+            cache: HashMap::new(),
+            forced: HashSet::new(),
+            recomputed: 0,
+        }
+    }
+
+    /// Recomputes the entity's component on the next update even if its sources
+    /// haven't changed.
+    pub fn force_recompute(&mut self, entity: Entity) {
+        self.forced.insert(entity);
+    }
+
+    /// The number of entities recomputed by the last update.
+    pub fn recomputed(&self) -> usize {
+        self.recomputed
+    }
+
+    fn update(world: &mut World) {
+        let mut state = std::mem::take(world.resource_mut::<Self>());
+        let archetypes = world.archetypes();
+        let mut entities = vec![];
+        for id in archetypes.query(&D::Sources::ids(), &HashSet::new()) {
+            if let Some(archetype) = archetypes.get(&id) {
+                entities.extend_from_slice(archetype.entities());
+            }
+        }
+
+        if D::HIERARCHICAL {
+            let all = world.entities();
+            entities.sort_by_cached_key(|entity| {
+                std::iter::successors(all.parent(entity), |entity| all.parent(entity)).count()
+            });
+        }
+
+        state.recomputed = 0;
+        let mut changed = HashSet::new();
+        for entity in &entities {
+            let sources = match D::Sources::get(world, entity) {
+                Some(sources) => sources,
+                None => continue,
+            };
+
+            let parent = match D::HIERARCHICAL {
+                true => world.entities().parent(entity).copied(),
+                false => None,
+            };
+
+            let forced = state.forced.remove(entity);
+            let stale = match state.cache.get(entity) {
+                Some(cached) => cached.sources != sources || cached.parent != parent,
+                None => true,
+            };
+
+            let parent_changed = parent.is_some_and(|parent| changed.contains(&parent));
+            if !forced && !stale && !parent_changed && world.has_component::<D>(entity) {
+                continue;
+            }
+
+            let value = {
+                let parent = parent.and_then(|parent| component::<D>(world, &parent));
+                D::compute(&sources, parent)
+            };
+
+            match component_mut::<D>(world, entity) {
+                Some(component) => *component = value,
+                None => {
+                    world.add_component(entity, value);
+                }
+            }
+
+            state.cache.insert(*entity, Cached { sources, parent });
+            state.recomputed += 1;
+            changed.insert(*entity);
+        }
+
+        let present = entities.into_iter().collect::<HashSet<_>>();
+        let removed = state
+            .cache
+            .keys()
+            .filter(|entity| !present.contains(entity))
+            .copied()
+            .collect::<Vec<_>>();
+
+        let id = ComponentId::new::<D>();
+        for entity in removed {
+            state.cache.remove(&entity);
+            if world.entities().contains(&entity) {
+                world.remove_component(&entity, &id);
+            }
+        }
+
+        state.forced.retain(|entity| present.contains(entity));
+        *world.resource_mut::<Self>() = state;
+    }
+}
+
+impl<D: DerivedComponent> Default for Derived<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D: DerivedComponent> Resource for Derived<D> {}
+
+fn component<'a, C: Component>(world: &'a World, entity: &Entity) -> Option<&'a C> {
+    let archetypes = world.archetypes();
+    let archetype = archetypes.get(&archetypes.entity_archetype(entity)?)?;
+    archetype.component::<C>(entity)
+}
+
+fn component_mut<'a, C: Component>(world: &'a World, entity: &Entity) -> Option<&'a mut C> {
+    let archetypes = world.archetypes();
+    let archetype = archetypes.get(&archetypes.entity_archetype(entity)?)?;
+    archetype.component_mut::<C>(entity, archetypes.tick())
+}
+
+struct Derivation {
+    phase: ScheduleId,
+    reads: Vec<ComponentId>,
+    writes: ComponentId,
+    update: fn(&mut World),
+}
+
+/// The registered derivations, ordered so that a derived component is updated before
+/// the derivations that read it.
+#[derive(Default)]
+pub struct Derivations {
+    derivations: Vec<Derivation>,
+}
+
+impl Derivations {
+    /// Returns false if `D` was already added.
+    fn add<D: DerivedComponent>(&mut self, phase: ScheduleId) -> bool {
+        let writes = ComponentId::new::<D>();
+        if self.derivations.iter().any(|d| d.writes == writes) {
+            return false;
+        }
+
+        self.derivations.push(Derivation {
+            phase,
+            reads: D::Sources::ids(),
+            writes,
+            update: Derived::<D>::update,
+        });
+
+        let mut remaining = std::mem::take(&mut self.derivations);
+        while !remaining.is_empty() {
+            let ready = remaining.iter().position(|derivation| {
+                let reads = &derivation.reads;
+                !remaining.iter().any(|other| reads.contains(&other.writes))
+            });
+
+            match ready {
+                Some(index) => self.derivations.push(remaining.remove(index)),
+                None => panic!("Derived components depend on each other"),
+            }
+        }
+
+        true
+    }
+
+    fn has_phase(&self, phase: ScheduleId) -> bool {
+        self.derivations.iter().any(|d| d.phase == phase)
+    }
+}
+
+impl Resource for Derivations {}
+
+/// Updates the derivations added to `phase`, in dependency order.
+pub struct UpdateDerived {
+    phase: ScheduleId,
+}
+
+impl Event for UpdateDerived {
+    type Output = ();
+
+    fn invoke(self, world: &mut World) -> Option<Self::Output> {
+        let updates = world
+            .resource::<Derivations>()
+            .derivations
+            .iter()
+            .filter(|derivation| derivation.phase == self.phase)
+            .map(|derivation| derivation.update)
+            .collect::<Vec<_>>();
+
+        for update in updates {
+            update(world);
+        }
+
+        None
+    }
+}
+
+impl World {
+    /// Keeps `D` up to date with its sources, at the end of `phase`.
+    pub fn add_derived<D: DerivedComponent>(&mut self, phase: impl Phase) -> &mut Self {
+        let id = phase.id();
+        if !self.components.contains(&ComponentId::new::<D>()) {
+            self.register::<D>();
+        }
+
+        if self.try_resource::<Derivations>().is_none() {
+            self.init_resource::<Derivations>()
+                .register_event::<UpdateDerived>();
+        }
+
+        let derivations = self.resource_mut::<Derivations>();
+        let new_phase = !derivations.has_phase(id);
+        if derivations.add::<D>(id) {
+            self.init_resource::<Derived<D>>();
+        }
+
+        if new_phase {
+            self.add_system(phase, move |events: &Events| {
+                events.add(UpdateDerived { phase: id })
+            });
+        }
+
+        self
+    }
+
+    pub fn force_recompute<D: DerivedComponent>(&self, entity: Entity) {
+        if let Some(derived) = self.try_resource_mut::<Derived<D>>() {
+            derived.force_recompute(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Derived, DerivedComponent};
+    use crate::{
+        core::{Component, ComponentId, Entity},
+        system::schedule::Root,
+        world::World,
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Base(i32);
+    impl Component for Base {}
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Double(i32);
+    impl Component for Double {}
+
+    impl DerivedComponent for Double {
+        type Sources = (Base,);
+
+        fn compute((base,): &Self::Sources, _: Option<&Self>) -> Self {
+            Self(base.0 * 2)
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Square(i32);
+    impl Component for Square {}
+
+    impl DerivedComponent for Square {
+        type Sources = (Base,);
+
+        fn compute((base,): &Self::Sources, _: Option<&Self>) -> Self {
+            Self(base.0 * base.0)
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Sum(i32);
+    impl Component for Sum {}
+
+    impl DerivedComponent for Sum {
+        type Sources = (Double, Square);
+
+        fn compute((double, square): &Self::Sources, _: Option<&Self>) -> Self {
+            Self(double.0 + square.0)
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Global(i32);
+    impl Component for Global {}
+
+    impl DerivedComponent for Global {
+        type Sources = (Base,);
+        const HIERARCHICAL: bool = true;
+
+        fn compute((base,): &Self::Sources, parent: Option<&Self>) -> Self {
+            Self(base.0 + parent.map_or(0, |parent| parent.0))
+        }
+    }
+
+    fn get<C: Component + Copy>(world: &World, entity: &Entity) -> Option<C> {
+        let archetypes = world.archetypes();
+        let archetype = archetypes.get(&archetypes.entity_archetype(entity)?)?;
+        archetype.component::<C>(entity).copied()
+    }
+
+    fn set_base(world: &World, entity: &Entity, value: i32) {
+        let archetypes = world.archetypes();
+        let archetype = archetypes.get(&archetypes.entity_archetype(entity).unwrap());
+        let tick = archetypes.tick();
+        let base = archetype.unwrap().component_mut::<Base>(entity, tick);
+        base.unwrap().0 = value;
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.register::<Base>();
+        world
+    }
+
+    #[test]
+    fn diamond_sources() {
+        let mut world = world();
+        world
+            .add_derived::<Sum>(Root)
+            .add_derived::<Double>(Root)
+            .add_derived::<Square>(Root)
+            .build();
+
+        let entity = world.spawn(None);
+        world.add_component(&entity, Base(3));
+        world.run(Root);
+        assert_eq!(get(&world, &entity), Some(Sum(15)));
+
+        set_base(&world, &entity, 4);
+        world.run(Root);
+        assert_eq!(get(&world, &entity), Some(Sum(24)));
+        assert_eq!(world.resource::<Derived<Sum>>().recomputed(), 1);
+    }
+
+    #[test]
+    fn parents_before_children() {
+        let mut world = world();
+        world.add_derived::<Global>(Root).build();
+
+        let grandchild = world.spawn(None);
+        let child = world.spawn(None);
+        let parent = world.spawn(None);
+        world.set_parent(&grandchild, Some(&child));
+        world.set_parent(&child, Some(&parent));
+        for (entity, base) in [(grandchild, 100), (child, 10), (parent, 1)] {
+            world.add_component(&entity, Base(base));
+        }
+
+        world.run(Root);
+        assert_eq!(get(&world, &grandchild), Some(Global(111)));
+
+        set_base(&world, &parent, 2);
+        world.run(Root);
+        assert_eq!(get(&world, &child), Some(Global(12)));
+        assert_eq!(get(&world, &grandchild), Some(Global(112)));
+        assert_eq!(world.resource::<Derived<Global>>().recomputed(), 3);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut world = world();
+        world.add_derived::<Double>(Root).build();
+
+        let entity = world.spawn(None);
+        world.run(Root);
+        assert_eq!(get::<Double>(&world, &entity), None);
+
+        world.add_component(&entity, Base(5));
+        world.run(Root);
+        assert_eq!(get(&world, &entity), Some(Double(10)));
+
+        world.remove_component(&entity, &ComponentId::new::<Base>());
+        world.run(Root);
+        assert_eq!(get::<Double>(&world, &entity), None);
+    }
+
+    #[test]
+    fn unchanged_sources_skip_recompute() {
+        let mut world = world();
+        world.add_derived::<Double>(Root).build();
+
+        let entities = (0..10)
+            .map(|base| {
+                let entity = world.spawn(None);
+                world.add_component(&entity, Base(base));
+                entity
+            })
+            .collect::<Vec<_>>();
+
+        world.run(Root);
+        assert_eq!(world.resource::<Derived<Double>>().recomputed(), 10);
+
+        world.run(Root).run(Root);
+        assert_eq!(world.resource::<Derived<Double>>().recomputed(), 0);
+
+        world.force_recompute::<Double>(entities[3]);
+        world.run(Root);
+        assert_eq!(world.resource::<Derived<Double>>().recomputed(), 1);
+    }
+}
@@ -0,0 +1,602 @@
+use super::{
+    event::{AddComponents, Despawn, RemoveComponents, SetParent},
+    World,
+};
+use crate::{
+    archetype::{table::EntityRow, Archetype, ArchetypeId},
+    core::{ColumnCell, Component, ComponentId, DenseMap, DenseSet, Entity},
+};
+use ahash::AHasher;
+use std::{
+    hash::{Hash, Hasher},
+    ops::Range,
+};
+
+/// A component whose values can be captured in snapshots and compared.
+pub trait Diffable: Component + Clone {
+    /// True if `self` and `other` differ by more than `epsilon`. Components
+    /// without floating point fields can ignore `epsilon`.
+    fn differs(&self, other: &Self, epsilon: f32) -> bool;
+
+    /// Feeds the exact value to `state`. Snapshots hash each archetype's values so
+    /// unchanged archetypes can be skipped without comparing them.
+    fn hash_value<H: Hasher>(&self, state: &mut H);
+}
+
+#[derive(Clone, Copy)]
+pub struct ComponentDiff {
+    capture: fn(&Archetype, &Entity) -> Option<ColumnCell>,
+    clone: fn(&ColumnCell) -> ColumnCell,
+    differs: fn(&ColumnCell, &ColumnCell, f32) -> bool,
+    hash: fn(&ColumnCell, &mut AHasher),
+}
+
+impl ComponentDiff {
+    pub fn new<C: Diffable>() -> Self {
+        Self {
+            capture: |archetype, entity| {
+                let component = archetype.component::<C>(entity)?;
+                Some(ColumnCell::from(component.clone()))
+            },
+            clone: |cell| ColumnCell::from(cell.value::<C>().clone()),
+            differs: |a, b, epsilon| a.value::<C>().differs(b.value::<C>(), epsilon),
+            hash: |cell, state| cell.value::<C>().hash_value(state),
+        }
+    }
+}
+
+struct SnapshotCell {
+    cell: ColumnCell,
+    diff: ComponentDiff,
+}
+
+pub struct EntitySnapshot {
+    archetype: ArchetypeId,
+    parent: Option<Entity>,
+    components: DenseMap<ComponentId, Option<SnapshotCell>>,
+}
+
+impl EntitySnapshot {
+    pub fn parent(&self) -> Option<Entity> {
+        self.parent
+    }
+
+    pub fn components(&self) -> &[ComponentId] {
+        self.components.keys()
+    }
+}
+
+/// A copy of every entity's hierarchy and components. Values are only kept for
+/// components registered with `World::register_diffable`; other components are
+/// tracked by presence.
+pub struct WorldSnapshot {
+    entities: DenseMap<Entity, EntitySnapshot>,
+    archetypes: DenseMap<ArchetypeId, ArchetypeSnapshot>,
+}
+
+struct ArchetypeSnapshot {
+    /// The archetype's entities, as a range of the snapshot's entity list.
+    entities: Range<usize>,
+    /// A hash of the entities, their parents and their diffable values.
+    hash: u64,
+}
+
+impl WorldSnapshot {
+    pub fn capture(world: &World) -> Self {
+        let mut entities = DenseMap::new();
+        let mut archetypes = DenseMap::new();
+        for archetype in world.archetypes().iter() {
+            let start = entities.len();
+            let mut hasher = AHasher::default();
+            for entity in archetype.entities() {
+                let parent = world.entities().parent(entity).copied();
+                entity.hash(&mut hasher);
+                parent.hash(&mut hasher);
+
+                let mut components = DenseMap::new();
+                for id in archetype.components() {
+                    let diff = world.components().meta(id).extension::<ComponentDiff>();
+                    let cell = diff.and_then(|diff| {
+                        let cell = (diff.capture)(archetype, entity)?;
+                        (diff.hash)(&cell, &mut hasher);
+                        Some(SnapshotCell { cell, diff: *diff })
+                    });
+
+                    components.insert(*id, cell);
+                }
+
+                let snapshot = EntitySnapshot {
+                    archetype: archetype.id(),
+                    parent,
+                    components,
+                };
+                entities.insert(*entity, snapshot);
+            }
+
+            let snapshot = ArchetypeSnapshot {
+                entities: start..entities.len(),
+                hash: hasher.finish(),
+            };
+            archetypes.insert(archetype.id(), snapshot);
+        }
+
+        Self {
+            entities,
+            archetypes,
+        }
+    }
+
+    pub fn get(&self, entity: &Entity) -> Option<&EntitySnapshot> {
+        self.entities.get(entity)
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        self.entities.keys()
+    }
+
+    /// Archetypes with the same entities, in the same order, and the same hash in
+    /// both snapshots.
+    fn unchanged(&self, other: &Self) -> DenseSet<ArchetypeId> {
+        let mut unchanged = DenseSet::new();
+        for (id, a) in self.archetypes.iter() {
+            let Some(b) = other.archetypes.get(id) else {
+                continue;
+            };
+
+            let a_entities = &self.entities.keys()[a.entities.clone()];
+            let b_entities = &other.entities.keys()[b.entities.clone()];
+            if a.hash == b.hash && a_entities == b_entities {
+                unchanged.insert(*id);
+            }
+        }
+
+        unchanged
+    }
+}
+
+pub struct SpawnedEntity {
+    pub parent: Option<Entity>,
+    pub components: EntityRow,
+    /// Components that aren't diffable, so only their presence is known.
+    pub untracked: DenseSet<ComponentId>,
+}
+
+pub struct ChangedEntity {
+    /// `Some` when the entity was reparented.
+    pub parent: Option<Option<Entity>>,
+    pub added: EntityRow,
+    /// Added components that aren't diffable, so only their presence is known.
+    pub added_untracked: DenseSet<ComponentId>,
+    pub removed: DenseSet<ComponentId>,
+    pub modified: EntityRow,
+}
+
+pub enum EntityChange {
+    Spawned(Box<SpawnedEntity>),
+    Despawned,
+    Changed(Box<ChangedEntity>),
+}
+
+/// The changes that turn one snapshot into another, keyed by the entity ids of the snapshots.
+///
+/// Archetypes with the same entities and the same content hash in both snapshots are
+/// skipped without comparing values. The hash covers exact values, so a match means
+/// nothing changed at any epsilon, and a mismatch falls back to comparing each value
+/// with the epsilon. Two different sets of values can still share a 64-bit hash; the
+/// odds are negligible, but such a collision would leave that archetype's value
+/// changes out of the diff.
+pub struct WorldDiff {
+    changes: DenseMap<Entity, EntityChange>,
+}
+
+impl WorldDiff {
+    pub const EPSILON: f32 = 1e-5;
+
+    pub fn compute(a: &WorldSnapshot, b: &WorldSnapshot) -> Self {
+        Self::compute_with_epsilon(a, b, Self::EPSILON)
+    }
+
+    pub fn compute_with_epsilon(a: &WorldSnapshot, b: &WorldSnapshot, epsilon: f32) -> Self {
+        let mut changes = DenseMap::new();
+        let unchanged = a.unchanged(b);
+
+        for (entity, old) in a.entities.iter() {
+            let new = match b.get(entity) {
+                Some(new) => new,
+                None => {
+                    changes.insert(*entity, EntityChange::Despawned);
+                    continue;
+                }
+            };
+
+            if old.archetype == new.archetype && unchanged.contains(&old.archetype) {
+                continue;
+            }
+
+            let parent = (old.parent != new.parent).then_some(new.parent);
+            let mut added = EntityRow::new();
+            let mut added_untracked = DenseSet::new();
+            let mut removed = DenseSet::new();
+            let mut modified = EntityRow::new();
+
+            for (id, cell) in new.components.iter() {
+                match (old.components.get(id), cell) {
+                    (None, Some(cell)) => {
+                        added.add_cell(*id, (cell.diff.clone)(&cell.cell));
+                    }
+                    (None, None) => {
+                        added_untracked.insert(*id);
+                    }
+                    (Some(Some(old)), Some(cell))
+                        if (cell.diff.differs)(&old.cell, &cell.cell, epsilon) =>
+                    {
+                        modified.add_cell(*id, (cell.diff.clone)(&cell.cell));
+                    }
+                    _ => {}
+                }
+            }
+
+            for id in old.components.keys() {
+                if !new.components.contains(id) {
+                    removed.insert(*id);
+                }
+            }
+
+            if parent.is_some()
+                || !added.is_empty()
+                || !added_untracked.is_empty()
+                || !removed.is_empty()
+                || !modified.is_empty()
+            {
+                let change = ChangedEntity {
+                    parent,
+                    added,
+                    added_untracked,
+                    removed,
+                    modified,
+                };
+                changes.insert(*entity, EntityChange::Changed(Box::new(change)));
+            }
+        }
+
+        for (entity, new) in b.entities.iter() {
+            if a.get(entity).is_some() {
+                continue;
+            }
+
+            let mut components = EntityRow::new();
+            let mut untracked = DenseSet::new();
+            for (id, cell) in new.components.iter() {
+                match cell {
+                    Some(cell) => {
+                        components.add_cell(*id, (cell.diff.clone)(&cell.cell));
+                    }
+                    None => untracked.insert(*id),
+                }
+            }
+
+            let change = SpawnedEntity {
+                parent: new.parent,
+                components,
+                untracked,
+            };
+            changes.insert(*entity, EntityChange::Spawned(Box::new(change)));
+        }
+
+        Self { changes }
+    }
+
+    pub fn get(&self, entity: &Entity) -> Option<&EntityChange> {
+        self.changes.get(entity)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Entity, &EntityChange)> {
+        self.changes.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Patches `world` to match the target snapshot through the structural events.
+    /// Spawned entities get new ids; the returned map goes from snapshot ids to them.
+    /// Untracked components have no value to add, so they are left out.
+    pub fn apply(mut self, world: &mut World) -> DenseMap<Entity, Entity> {
+        let mut spawned = DenseMap::new();
+        for (entity, change) in self.changes.iter() {
+            if let EntityChange::Spawned(_) = change {
+                spawned.insert(*entity, world.spawn(None));
+            }
+        }
+
+        let remap = |entity: Entity| spawned.get(&entity).copied().unwrap_or(entity);
+        for (entity, change) in self.changes.drain() {
+            let target = remap(entity);
+            match change {
+                EntityChange::Spawned(mut change) => {
+                    if change.parent.is_some() {
+                        world
+                            .events()
+                            .add(SetParent::new(target, change.parent.map(remap)));
+                    }
+
+                    let mut add = AddComponents::new(target);
+                    for (id, cell) in change.components.drain() {
+                        add = add.with_cell(id, cell);
+                    }
+                    world.events().add(add);
+                }
+                EntityChange::Despawned => world.events().add(Despawn::new(target)),
+                EntityChange::Changed(mut change) => {
+                    if let Some(parent) = change.parent {
+                        world
+                            .events()
+                            .add(SetParent::new(target, parent.map(remap)));
+                    }
+
+                    if !change.removed.is_empty() {
+                        let mut remove = RemoveComponents::new(target);
+                        for id in change.removed.iter() {
+                            remove = remove.with_id(*id);
+                        }
+                        world.events().add(remove);
+                    }
+
+                    if !change.added.is_empty() || !change.modified.is_empty() {
+                        let mut add = AddComponents::new(target);
+                        for (id, cell) in change.added.drain().chain(change.modified.drain()) {
+                            add = add.with_cell(id, cell);
+                        }
+                        world.events().add(add);
+                    }
+                }
+            }
+        }
+
+        world.flush();
+        spawned
+    }
+}
+
+impl World {
+    pub fn register_diffable<C: Diffable>(&mut self) -> &mut Self {
+        let id = ComponentId::new::<C>();
+        if !self.components.contains(&id) {
+            self.register::<C>();
+        }
+
+        self.components
+            .add_extension(&id, ComponentDiff::new::<C>());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diffable, EntityChange, WorldDiff, WorldSnapshot};
+    use crate::{
+        core::{Component, ComponentId, Entity},
+        world::World,
+    };
+    use std::{
+        cell::Cell,
+        hash::{Hash, Hasher},
+    };
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Position(f32);
+    impl Component for Position {}
+
+    impl Diffable for Position {
+        fn differs(&self, other: &Self, epsilon: f32) -> bool {
+            (self.0 - other.0).abs() > epsilon
+        }
+
+        fn hash_value<H: Hasher>(&self, state: &mut H) {
+            self.0.to_bits().hash(state);
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Health(u32);
+    impl Component for Health {}
+
+    impl Diffable for Health {
+        fn differs(&self, other: &Self, _: f32) -> bool {
+            self != other
+        }
+
+        fn hash_value<H: Hasher>(&self, state: &mut H) {
+            self.0.hash(state);
+        }
+    }
+
+    fn position(world: &World, entity: &Entity) -> Option<Position> {
+        let archetypes = world.archetypes();
+        let archetype = archetypes.get(&archetypes.entity_archetype(entity)?)?;
+        archetype.component::<Position>(entity).cloned()
+    }
+
+    fn setup() -> (World, [Entity; 4]) {
+        let mut world = World::new();
+        world
+            .register_diffable::<Position>()
+            .register_diffable::<Health>();
+
+        let root = world.spawn(None);
+        let moved = world.spawn(Some(root));
+        let noisy = world.spawn(None);
+        let doomed = world.spawn(None);
+        world.add_component(&root, Position(0.0));
+        world.add_component(&moved, Position(1.0));
+        world.add_component(&noisy, Position(2.0));
+        world.add_component(&doomed, Health(3));
+
+        (world, [root, moved, noisy, doomed])
+    }
+
+    fn mutate(world: &mut World, [root, moved, noisy, doomed]: [Entity; 4]) -> Entity {
+        world.set_parent(&moved, None);
+        world.add_component(&moved, Position(5.0));
+        world.add_component(&noisy, Position(2.0 + 1e-6));
+        world.add_component(&root, Health(10));
+        world.remove_component(&root, &ComponentId::new::<Position>());
+        world.despawn(&doomed);
+
+        let spawned = world.spawn(Some(root));
+        world.add_component(&spawned, Position(7.0));
+        spawned
+    }
+
+    #[test]
+    fn diff_and_apply() {
+        let (mut world, entities) = setup();
+        let [root, moved, noisy, doomed] = entities;
+        let before = WorldSnapshot::capture(&world);
+        let spawned = mutate(&mut world, entities);
+        let after = WorldSnapshot::capture(&world);
+
+        let diff = WorldDiff::compute(&before, &after);
+        assert_eq!(diff.len(), 4);
+        assert!(diff.get(&noisy).is_none());
+        assert!(matches!(diff.get(&doomed), Some(EntityChange::Despawned)));
+        assert!(matches!(
+            diff.get(&spawned),
+            Some(EntityChange::Spawned(change)) if change.parent == Some(root)
+        ));
+
+        match diff.get(&moved) {
+            Some(EntityChange::Changed(change)) => {
+                assert_eq!(change.parent, Some(None));
+                assert!(change.added.is_empty());
+                assert!(change.added_untracked.is_empty() && change.removed.is_empty());
+                assert_eq!(change.modified.get::<Position>(), Some(&Position(5.0)));
+            }
+            _ => panic!("expected a change"),
+        }
+
+        match diff.get(&root) {
+            Some(EntityChange::Changed(change)) => {
+                assert_eq!(change.added.get::<Health>(), Some(&Health(10)));
+                assert!(change.removed.contains(&ComponentId::new::<Position>()));
+            }
+            _ => panic!("expected a change"),
+        }
+
+        let strict = WorldDiff::compute_with_epsilon(&before, &after, 0.0);
+        assert!(strict.get(&noisy).is_some());
+
+        let (mut target, _) = setup();
+        let spawned_ids = diff.apply(&mut target);
+        let spawned = spawned_ids.get(&spawned).copied().unwrap();
+
+        assert_eq!(position(&target, &moved), Some(Position(5.0)));
+        assert_eq!(position(&target, &root), None);
+        assert_eq!(position(&target, &spawned), Some(Position(7.0)));
+        assert_eq!(target.entities().parent(&moved), None);
+        assert_eq!(target.entities().parent(&spawned), Some(&root));
+        assert!(!target.entities().iter().any(|entity| *entity == doomed));
+        assert!(target.has_component::<Health>(&root));
+    }
+
+    #[test]
+    fn untracked_components_added() {
+        struct Marker;
+        impl Component for Marker {}
+
+        let (mut world, [root, ..]) = setup();
+        world.register::<Marker>();
+        let before = WorldSnapshot::capture(&world);
+        world.add_component(&root, Marker);
+        let spawned = world.spawn(None);
+        world.add_component(&spawned, Marker);
+        let after = WorldSnapshot::capture(&world);
+
+        let marker = ComponentId::new::<Marker>();
+        let diff = WorldDiff::compute(&before, &after);
+        match diff.get(&root) {
+            Some(EntityChange::Changed(change)) => {
+                assert!(change.added.is_empty());
+                assert!(change.added_untracked.contains(&marker));
+            }
+            _ => panic!("expected a change"),
+        }
+
+        match diff.get(&spawned) {
+            Some(EntityChange::Spawned(change)) => {
+                assert!(change.components.is_empty());
+                assert!(change.untracked.contains(&marker));
+            }
+            _ => panic!("expected a spawn"),
+        }
+    }
+
+    #[test]
+    fn unchanged_archetypes_are_skipped() {
+        thread_local! {
+            static COMPARED: Cell<usize> = const { Cell::new(0) };
+        }
+
+        #[derive(Clone, PartialEq)]
+        struct Static(u32);
+        impl Component for Static {}
+
+        impl Diffable for Static {
+            fn differs(&self, other: &Self, _: f32) -> bool {
+                COMPARED.with(|compared| compared.set(compared.get() + 1));
+                self != other
+            }
+
+            fn hash_value<H: Hasher>(&self, state: &mut H) {
+                self.0.hash(state);
+            }
+        }
+
+        let (mut world, [root, ..]) = setup();
+        world.register_diffable::<Static>();
+        let statics = (0..50)
+            .map(|index| {
+                let entity = world.spawn(None);
+                world.add_component(&entity, Static(index));
+                entity
+            })
+            .collect::<Vec<_>>();
+
+        let before = WorldSnapshot::capture(&world);
+        world.add_component(&root, Position(4.0));
+        let after = WorldSnapshot::capture(&world);
+
+        let diff = WorldDiff::compute(&before, &after);
+        assert_eq!(diff.len(), 1);
+        assert!(diff.get(&root).is_some());
+        assert_eq!(COMPARED.with(|compared| compared.get()), 0);
+
+        world.add_component(&statics[10], Static(100));
+        let changed = WorldSnapshot::capture(&world);
+
+        let diff = WorldDiff::compute(&after, &changed);
+        assert_eq!(diff.len(), 1);
+        assert!(diff.get(&statics[10]).is_some());
+        assert_eq!(COMPARED.with(|compared| compared.get()), statics.len());
+    }
+
+    #[test]
+    fn matching_hashes_need_matching_entities() {
+        let (mut world, [root, ..]) = setup();
+        let before = WorldSnapshot::capture(&world);
+        let archetype = before.entities.get(&root).unwrap().archetype;
+
+        let spawned = world.spawn(None);
+        world.add_component(&spawned, Position(5.0));
+        let mut after = WorldSnapshot::capture(&world);
+        after.archetypes.get_mut(&archetype).unwrap().hash =
+            before.archetypes.get(&archetype).unwrap().hash;
+
+        assert!(!before.unchanged(&after).contains(&archetype));
+        assert!(after.unchanged(&after).contains(&archetype));
+    }
+}
@@ -1,5 +1,8 @@
 use super::World;
-use crate::core::{internal::blob::BlobCell, DenseSet, Resource};
+use crate::{
+    core::{internal::blob::BlobCell, DenseSet, Resource},
+    system::schedule::{Phase, ScheduleId},
+};
 use std::{
     any::TypeId,
     collections::HashMap,
@@ -71,8 +74,10 @@ impl EventMeta {
             invoke: |event, world| {
                 let event = event.take::<E>();
                 if let Some(output) = event.invoke(world) {
-                    world.events().invoked::<E>();
-                    world.resource_mut::<EventOutputs<E>>().add(output);
+                    if world.observes::<E>() {
+                        world.events().invoked::<E>();
+                        world.resource_mut::<EventOutputs<E>>().add(output);
+                    }
                 }
             },
             clear: |world| {
@@ -94,11 +99,16 @@ impl EventMeta {
     }
 }
 
+/// A multi-producer event queue shared by every clone of the handle. Events can be
+/// added from any thread and become visible at the next drain point (`World::flush`
+/// or the next iteration of its loop). A drain takes the whole queue at once, so an
+/// event is delivered exactly once and a batch is never split across drains.
 #[derive(Clone)]
 pub struct Events {
     events: Arc<Mutex<Vec<ErasedEvent>>>,
     metas: HashMap<EventType, Arc<EventMeta>>,
     invocations: Arc<RwLock<DenseSet<EventInvocation>>>,
+    deferred: Arc<Mutex<HashMap<ScheduleId, Vec<ErasedEvent>>>>,
 }
 
 impl Events {
@@ -107,6 +117,7 @@ impl Events {
             events: Arc::new(Mutex::new(Vec::new())),
             metas: HashMap::new(),
             invocations: Arc::new(RwLock::new(DenseSet::new())),
+            deferred: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -132,11 +143,26 @@ impl Events {
         events.push(event.into());
     }
 
-    pub fn extend(&self, events: Vec<impl Into<ErasedEvent>>) {
+    /// Queues events under a single lock so they are drained, and observed, in the
+    /// same flush iteration, even when they have different types.
+    pub fn extend(&self, events: impl IntoIterator<Item = impl Into<ErasedEvent>>) {
         let mut _events = self.events.lock().unwrap();
         _events.extend(events.into_iter().map(|e| e.into()));
     }
 
+    /// Queues an event to be invoked right before the systems of phase `P` run.
+    /// Deferring to a phase that already ran this frame delivers it on the next run.
+    pub fn add_deferred<P: Phase>(&self, event: impl Into<ErasedEvent>) {
+        let mut deferred = self.deferred.lock().unwrap();
+        let events = deferred.entry(ScheduleId::new::<P>()).or_default();
+        events.push(event.into());
+    }
+
+    pub fn take_deferred(&self, phase: &ScheduleId) -> Vec<ErasedEvent> {
+        let mut deferred = self.deferred.lock().unwrap();
+        deferred.remove(phase).unwrap_or_default()
+    }
+
     pub fn remove<E: Event>(&self) -> Vec<ErasedEvent> {
         let mut events = self.events.lock().unwrap();
         let mut drained = Vec::new();
@@ -177,6 +203,7 @@ impl Events {
     pub fn clear(&self) {
         let mut events = self.events.lock().unwrap();
         events.clear();
+        self.deferred.lock().unwrap().clear();
     }
 
     pub fn is_empty(&self) -> bool {
@@ -271,10 +298,12 @@ impl Hash for EventInvocation {
 pub mod internal {
     use super::{Event, EventOutputs, World};
     use crate::{
-        archetype::table::EntityRow,
+        archetype::table::{ComponentSet, EntityRow},
         core::{ColumnCell, Component, ComponentId, DenseSet, Entity},
         system::schedule::SystemTag,
     };
+    use std::any::Any;
+
     pub struct Spawn {
         parent: Option<Entity>,
         components: EntityRow,
@@ -297,6 +326,11 @@ pub mod internal {
             self.components.add_component(component);
             self
         }
+
+        pub fn with_components(mut self, components: impl ComponentSet) -> Self {
+            components.add_to(&mut self.components);
+            self
+        }
     }
 
     impl Event for Spawn {
@@ -335,17 +369,57 @@ pub mod internal {
         const PRIORITY: i32 = i32::MIN + 1000;
 
         fn invoke(self, world: &mut super::World) -> Option<Self::Output> {
-            let mut entities = vec![];
-            for (entity, mut components) in world.despawn(&self.entity).drain() {
-                entities.push(entity);
-                for (id, cell) in components.drain() {
-                    let meta = world.components().extension::<ComponentEvents>(&id);
-                    meta.remove(world, &entity, cell);
-                }
-            }
+            let tree = despawn_tree(world, self.entity)?;
+            let entities = tree.entities.clone();
+            world.events().invoked::<DespawnCascade>();
+            world
+                .resource_mut::<EventOutputs<DespawnCascade>>()
+                .add(tree);
+            Some(entities)
+        }
+    }
+
+    /// Every entity removed by despawning `root`, root first.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct DespawnedTree {
+        pub root: Entity,
+        pub entities: Vec<Entity>,
+    }
 
-            (!entities.is_empty()).then_some(entities)
+    /// Despawns an entity and its descendants like `Despawn`. Observers of this
+    /// event also see every tree removed by a `Despawn`, one output per root.
+    pub struct DespawnCascade {
+        root: Entity,
+    }
+
+    impl DespawnCascade {
+        pub fn new(root: Entity) -> Self {
+            Self { root }
+        }
+    }
+
+    impl Event for DespawnCascade {
+        type Output = DespawnedTree;
+        const PRIORITY: i32 = Despawn::PRIORITY;
+
+        fn invoke(self, world: &mut super::World) -> Option<Self::Output> {
+            despawn_tree(world, self.root)
+        }
+    }
+
+    /// Removes the whole tree before reporting any removed component, so observers
+    /// only see the world after the cascade. Despawning a dead entity does nothing.
+    fn despawn_tree(world: &mut super::World, root: Entity) -> Option<DespawnedTree> {
+        let mut entities = vec![];
+        for (entity, mut components) in world.despawn(&root).drain() {
+            entities.push(entity);
+            for (id, cell) in components.drain() {
+                let meta = world.components().extension::<ComponentEvents>(&id);
+                meta.remove(world, &entity, cell);
+            }
         }
+
+        (!entities.is_empty()).then_some(DespawnedTree { root, entities })
     }
 
     pub struct ParentUpdate {
@@ -404,6 +478,44 @@ pub mod internal {
         }
     }
 
+    pub struct EntityEnabled {
+        entity: Entity,
+    }
+
+    impl EntityEnabled {
+        pub fn new(entity: Entity) -> Self {
+            Self { entity }
+        }
+    }
+
+    impl Event for EntityEnabled {
+        type Output = Entity;
+
+        fn invoke(self, world: &mut super::World) -> Option<Self::Output> {
+            world.set_enabled(&self.entity, true).then_some(self.entity)
+        }
+    }
+
+    pub struct EntityDisabled {
+        entity: Entity,
+    }
+
+    impl EntityDisabled {
+        pub fn new(entity: Entity) -> Self {
+            Self { entity }
+        }
+    }
+
+    impl Event for EntityDisabled {
+        type Output = Entity;
+
+        fn invoke(self, world: &mut super::World) -> Option<Self::Output> {
+            world
+                .set_enabled(&self.entity, false)
+                .then_some(self.entity)
+        }
+    }
+
     pub struct AddChildren {
         parent: Entity,
         children: Vec<Entity>,
@@ -490,13 +602,9 @@ pub mod internal {
 
         fn invoke(mut self, world: &mut super::World) -> Option<Self::Output> {
             let component = self.component.take()?;
-            world.add_component(&self.entity, component)?;
-
-            let id = ComponentId::new::<C>();
-            let meta = world.components().extension::<ComponentEvents>(&id);
-            meta.add(world, &self.entity);
-
-            Some(self.entity)
+            world
+                .set_component(&self.entity, component)
+                .then_some(self.entity)
         }
     }
 
@@ -518,6 +626,16 @@ pub mod internal {
 
             self
         }
+
+        pub fn with_components(mut self, components: impl ComponentSet) -> Self {
+            components.add_to(&mut self.components);
+            self
+        }
+
+        pub fn with_cell(mut self, id: ComponentId, cell: ColumnCell) -> Self {
+            self.components.add_cell(id, cell);
+            self
+        }
     }
 
     impl Event for AddComponents {
@@ -567,6 +685,11 @@ pub mod internal {
 
         fn invoke(self, world: &mut super::World) -> Option<Self::Output> {
             let id = ComponentId::new::<C>();
+            if !world.observes::<Self>() {
+                world.discard_component(&self.entity, &id);
+                return None;
+            }
+
             let mut result = world.remove_component(&self.entity, &id)?;
             let component = result.removed_mut().remove_component::<C>()?;
             Some(RemovedComponent::new(self.entity, component))
@@ -590,6 +713,11 @@ pub mod internal {
             self.components.insert(ComponentId::new::<C>());
             self
         }
+
+        pub fn with_id(mut self, id: ComponentId) -> Self {
+            self.components.insert(id);
+            self
+        }
     }
 
     impl Event for RemoveComponents {
@@ -608,6 +736,7 @@ pub mod internal {
     }
 
     pub struct ComponentEvents {
+        register: fn(&mut World),
         add: Box<dyn Fn(&World, &Entity) + Send + Sync + 'static>,
         remove: Box<dyn Fn(&World, &Entity, ColumnCell) + Send + Sync + 'static>,
     }
@@ -615,12 +744,23 @@ pub mod internal {
     impl ComponentEvents {
         pub fn new<C: Component>() -> Self {
             Self {
+                register: |world| {
+                    world.register::<C>();
+                },
                 add: Box::new(|world, entity| {
+                    if !world.observes::<AddComponent<C>>() {
+                        return;
+                    }
+
                     let outputs = world.resource_mut::<EventOutputs<AddComponent<C>>>();
                     world.events().invoked::<AddComponent<C>>();
                     outputs.add(*entity);
                 }),
                 remove: Box::new(|world, entity, cell| {
+                    if !world.observes::<RemoveComponent<C>>() {
+                        return;
+                    }
+
                     let outputs = world.resource_mut::<EventOutputs<RemoveComponent<C>>>();
                     let component = cell.take::<C>();
                     world.events().invoked::<RemoveComponent<C>>();
@@ -629,6 +769,11 @@ pub mod internal {
             }
         }
 
+        /// Registers the component and its events in another world.
+        pub fn register(&self, world: &mut World) {
+            (self.register)(world);
+        }
+
         pub fn add(&self, world: &World, entity: &Entity) {
             (self.add)(world, entity);
         }
@@ -676,19 +821,60 @@ pub mod internal {
         }
     }
 
+    #[derive(Debug, Clone)]
+    pub struct SystemPanicked {
+        name: &'static str,
+        message: String,
+    }
+
+    impl SystemPanicked {
+        pub fn new(name: &'static str, message: impl Into<String>) -> Self {
+            Self {
+                name,
+                message: message.into(),
+            }
+        }
+
+        pub fn from_payload(name: &'static str, payload: Box<dyn Any + Send>) -> Self {
+            Self::new(name, crate::system::panic_message(&*payload))
+        }
+
+        pub fn name(&self) -> &'static str {
+            self.name
+        }
+
+        pub fn message(&self) -> &str {
+            &self.message
+        }
+    }
+
+    impl Event for SystemPanicked {
+        type Output = Self;
+
+        fn invoke(self, _: &mut super::World) -> Option<Self::Output> {
+            Some(self)
+        }
+    }
+
     #[cfg(test)]
     mod tests {
         use crate::{
-            core::{Component, Entity, Resource},
+            core::{Component, ComponentId, Entity, Resource},
             system::schedule::Root,
             world::{
                 event::{
-                    AddComponent, Despawn, Events, ParentUpdate, RemoveChildren, RemoveComponent,
-                    RemoveComponents, RemovedComponent, SetParent,
+                    AddComponent, AddComponents, Despawn, DespawnCascade, DespawnedTree,
+                    EntityDisabled, EntityEnabled, EventOutputs, Events, ParentUpdate,
+                    RemoveChildren, RemoveComponent, RemoveComponents, RemovedComponent, SetParent,
                 },
+                query::Query,
                 World,
             },
         };
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
 
         use super::Spawn;
 
@@ -776,6 +962,275 @@ pub mod internal {
             assert!(world.resource::<Added>().health);
         }
 
+        #[test]
+        fn add_components_typed() {
+            struct Player;
+            impl Component for Player {}
+            struct Health;
+            impl Component for Health {}
+            struct Speed;
+            impl Component for Speed {}
+
+            let mut world = World::new();
+            let typed = world.spawn(None);
+            let moves = world
+                .add_components_typed(&typed, (Player, Health, Speed))
+                .into_iter()
+                .count();
+            assert_eq!(moves, 1);
+
+            let sequential = world.spawn(None);
+            let moves = [
+                world.add_component(&sequential, Player),
+                world.add_component(&sequential, Health),
+                world.add_component(&sequential, Speed),
+            ];
+            assert_eq!(moves.iter().flatten().count(), 3);
+        }
+
+        #[test]
+        fn on_add_components_typed() {
+            struct Player;
+            impl Component for Player {}
+            struct Health;
+            impl Component for Health {}
+            #[derive(Default)]
+            struct Added {
+                player: usize,
+                health: usize,
+            }
+            impl Resource for Added {}
+
+            let mut world = World::new();
+            world.register::<Player>();
+            world.register::<Health>();
+            world.add_resource(Added::default());
+
+            world.observe::<AddComponent<Player>, _>(|entities: &[Entity], added: &mut Added| {
+                added.player += entities.len();
+            });
+
+            world.observe::<AddComponent<Health>, _>(|entities: &[Entity], added: &mut Added| {
+                added.health += entities.len();
+            });
+
+            let entity = world.spawn(None);
+            world
+                .events()
+                .add(AddComponents::new(entity).with_components((Player, Health)));
+
+            world.run(Root);
+
+            assert_eq!(world.resource::<Added>().player, 1);
+            assert_eq!(world.resource::<Added>().health, 1);
+        }
+
+        #[test]
+        #[should_panic]
+        fn duplicate_components_typed() {
+            struct Player;
+            impl Component for Player {}
+
+            let mut world = World::new();
+            let entity = world.spawn(None);
+            world.add_components_typed(&entity, (Player, Player));
+        }
+
+        #[test]
+        fn deferred_events() {
+            use crate::system::schedule::Phase;
+
+            struct First;
+            impl Phase for First {}
+            struct Second;
+            impl Phase for Second {}
+
+            #[derive(Default)]
+            struct Log(Vec<&'static str>);
+            impl Resource for Log {}
+
+            let mut world = World::new();
+            world.add_resource(Log::default());
+            world.add_phase::<First>();
+            world.add_phase::<Second>();
+            world.add_system(First, |log: &mut Log| log.0.push("first"));
+            world.add_system(Second, |log: &mut Log| log.0.push("second"));
+            world.observe::<Spawn, _>(|_: &[Entity], log: &mut Log, events: &Events| {
+                log.0.push("spawn");
+                if log.0.len() < 4 {
+                    events.add_deferred::<First>(Spawn::new());
+                }
+            });
+            world.build();
+
+            world.events().add_deferred::<Second>(Spawn::new());
+            world.run(Root);
+            assert_eq!(world.resource::<Log>().0, ["first", "spawn", "second"]);
+
+            world.run(Root);
+            assert_eq!(
+                world.resource::<Log>().0,
+                ["first", "spawn", "second", "spawn", "first", "second"]
+            );
+        }
+
+        #[test]
+        fn add_existing_component_in_place() {
+            struct Health(u32);
+            impl Component for Health {}
+
+            let mut world = World::new();
+            let first = world.spawn(None);
+            let second = world.spawn(None);
+            world.add_component(&first, Health(1));
+            world.add_component(&second, Health(2));
+
+            let result = world.add_component(&first, Health(3)).unwrap();
+            assert_eq!(result.from(), result.to());
+            assert_eq!(result.removed().get::<Health>().unwrap().0, 1);
+
+            let archetype = world.archetypes().get(&result.to()).unwrap();
+            assert_eq!(archetype.entities(), &[first, second]);
+            assert_eq!(archetype.component::<Health>(&first).unwrap().0, 3);
+            assert_eq!(archetype.component::<Health>(&second).unwrap().0, 2);
+        }
+
+        #[test]
+        fn cached_edge_moves_keep_values() {
+            struct Health(u32);
+            impl Component for Health {}
+            struct Speed(u32);
+            impl Component for Speed {}
+
+            let mut world = World::new();
+            let first = world.spawn(None);
+            let second = world.spawn(None);
+            world.add_component(&first, Speed(1));
+            world.add_component(&first, Health(10));
+            world.add_component(&second, Speed(2));
+
+            let moves = world.archetypes().moves;
+            let added = world.add_component(&second, Health(20)).unwrap();
+            let removed = world
+                .remove_component(&first, &ComponentId::new::<Health>())
+                .unwrap();
+            assert_eq!(world.archetypes().moves - moves, 2);
+
+            assert_eq!(added.added().iter().count(), 1);
+            assert_eq!(removed.removed().get::<Health>().unwrap().0, 10);
+            assert_eq!(removed.to(), added.from());
+
+            let archetype = world.archetypes().get(&added.to()).unwrap();
+            assert_eq!(archetype.entities(), &[second]);
+            assert_eq!(archetype.component::<Speed>(&second).unwrap().0, 2);
+            assert_eq!(archetype.component::<Health>(&second).unwrap().0, 20);
+
+            let archetype = world.archetypes().get(&removed.to()).unwrap();
+            assert_eq!(archetype.entities(), &[first]);
+            assert_eq!(archetype.component::<Speed>(&first).unwrap().0, 1);
+        }
+
+        #[test]
+        fn unobserved_component_events_drop_values() {
+            struct Health(Arc<AtomicUsize>);
+            impl Component for Health {}
+            impl Drop for Health {
+                fn drop(&mut self) {
+                    self.0.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            let drops = Arc::new(AtomicUsize::new(0));
+            let mut world = World::new();
+            world.register::<Health>();
+            let entity = world.spawn(None);
+            world.add_component(&entity, Health(drops.clone()));
+            world.remove_component(&entity, &ComponentId::new::<Health>());
+            assert_eq!(drops.load(Ordering::Relaxed), 1);
+
+            world
+                .events()
+                .add(AddComponent::new(entity, Health(drops.clone())));
+            world.run(Root);
+            world
+                .events()
+                .add(AddComponent::new(entity, Health(drops.clone())));
+            world.run(Root);
+            assert_eq!(drops.load(Ordering::Relaxed), 2);
+
+            world.events().add(RemoveComponent::<Health>::new(entity));
+            world.run(Root);
+            assert_eq!(drops.load(Ordering::Relaxed), 3);
+            assert!(!world.has_component::<Health>(&entity));
+            assert_eq!(
+                world.resource::<EventOutputs<AddComponent<Health>>>().len(),
+                0
+            );
+        }
+
+        #[test]
+        fn observed_component_events_report_values() {
+            struct Health(u32);
+            impl Component for Health {}
+
+            #[derive(Default)]
+            struct Log {
+                added: Vec<Entity>,
+                removed: Vec<u32>,
+            }
+            impl Resource for Log {}
+
+            let mut world = World::new();
+            world.register::<Health>().init_resource::<Log>();
+            world.observe::<AddComponent<Health>, _>(|entities: &[Entity], log: &mut Log| {
+                log.added.extend_from_slice(entities);
+            });
+            world.observe::<RemoveComponent<Health>, _>(
+                |removed: &[RemovedComponent<Health>], log: &mut Log| {
+                    log.removed
+                        .extend(removed.iter().map(|removed| removed.component.0));
+                },
+            );
+
+            let entity = world.spawn(None);
+            world.events().add(AddComponent::new(entity, Health(1)));
+            world.run(Root);
+            world.events().add(AddComponent::new(entity, Health(2)));
+            world.run(Root);
+            world.events().add(RemoveComponent::<Health>::new(entity));
+            world.run(Root);
+
+            let log = world.resource::<Log>();
+            assert_eq!(log.added, [entity, entity]);
+            assert_eq!(log.removed, [2]);
+        }
+
+        #[test]
+        fn add_components_shared_archetype() {
+            struct Player;
+            impl Component for Player {}
+            struct Health;
+            impl Component for Health {}
+
+            let mut world = World::new();
+            let first = world.spawn(None);
+            let second = world.spawn(None);
+            world.add_component(&first, Player);
+            let a = world.add_component(&first, Health).unwrap();
+            world.add_component(&second, Health);
+            let b = world.add_component(&second, Player).unwrap();
+
+            assert_eq!(a.to(), b.to());
+            let archetype = world.archetypes().get(&a.to()).unwrap();
+            assert_eq!(archetype.entities(), &[first, second]);
+
+            let root = world
+                .archetypes()
+                .get(&world.archetypes().root_id())
+                .unwrap();
+            assert!(root.entities().is_empty());
+        }
+
         #[test]
         fn on_remove_components() {
             struct Player;
@@ -822,6 +1277,70 @@ pub mod internal {
             assert!(world.resource::<Removed>().health);
         }
 
+        #[test]
+        fn despawn_cascade() {
+            struct Health(u32);
+            impl Component for Health {}
+
+            #[derive(Default)]
+            struct Cleanup {
+                removed: Vec<(Entity, u32)>,
+                found: Vec<bool>,
+                remaining: Vec<usize>,
+                trees: Vec<DespawnedTree>,
+            }
+            impl Resource for Cleanup {}
+
+            let mut world = World::new();
+            world.register::<Health>().init_resource::<Cleanup>();
+            let survivor = world.spawn(None);
+            world.add_component(&survivor, Health(0));
+            let root = world.spawn(None);
+            let child = world.spawn(Some(root));
+            let grandchild = world.spawn(Some(child));
+            for (entity, health) in [(root, 1), (child, 2), (grandchild, 3)] {
+                world.add_component(&entity, Health(health));
+            }
+
+            world.observe::<RemoveComponent<Health>, _>(
+                |removed: &[RemovedComponent<Health>], world: &World, cleanup: &mut Cleanup| {
+                    for removed in removed {
+                        let entity = removed.entity;
+                        let entities = world.entities();
+                        let found = entities.contains(&entity)
+                            || entities.parent(&entity).is_some()
+                            || entities.children(&entity).is_some()
+                            || world.has_component::<Health>(&entity)
+                            || world.archetypes().entity_archetype(&entity).is_some();
+
+                        cleanup.removed.push((entity, removed.component.0));
+                        cleanup.found.push(found);
+                    }
+
+                    let remaining = Query::<&Health>::new(world).count();
+                    cleanup.remaining.push(remaining);
+                },
+            );
+            world.observe::<DespawnCascade, _>(|trees: &[DespawnedTree], cleanup: &mut Cleanup| {
+                cleanup.trees.extend_from_slice(trees);
+            });
+
+            world.events().add(Despawn::new(root));
+            world.events().add(Despawn::new(child));
+            world.flush();
+
+            let cleanup = world.resource::<Cleanup>();
+            assert_eq!(cleanup.removed, [(root, 1), (child, 2), (grandchild, 3)]);
+            assert_eq!(cleanup.found, [false; 3]);
+            assert_eq!(cleanup.remaining, [1]);
+            let tree = DespawnedTree {
+                root,
+                entities: vec![root, child, grandchild],
+            };
+            assert_eq!(cleanup.trees, [tree]);
+            assert!(world.entities().children(&survivor).is_some());
+        }
+
         #[test]
         fn on_remove_component() {
             struct Player;
@@ -922,6 +1441,45 @@ pub mod internal {
             assert!(world.resource::<Parented>().0);
         }
 
+        #[test]
+        fn on_enable_and_disable() {
+            #[derive(Default)]
+            struct Toggled {
+                enabled: Vec<Entity>,
+                disabled: Vec<Entity>,
+            }
+            impl Resource for Toggled {}
+
+            let mut world = World::new();
+            world.init_resource::<Toggled>();
+
+            world.observe::<EntityEnabled, _>(|entities: &[Entity], toggled: &mut Toggled| {
+                toggled.enabled.extend_from_slice(entities);
+            });
+
+            world.observe::<EntityDisabled, _>(|entities: &[Entity], toggled: &mut Toggled| {
+                toggled.disabled.extend_from_slice(entities);
+            });
+
+            let parent = world.spawn(None);
+            let child = world.spawn(Some(parent));
+
+            world.events().add(EntityDisabled::new(parent));
+            world.events().add(EntityDisabled::new(parent));
+            world.run(Root);
+
+            assert!(!world.entities().is_active(&child));
+            assert_eq!(world.resource::<Toggled>().disabled, vec![parent]);
+            assert!(world.resource::<Toggled>().enabled.is_empty());
+
+            world.events().add(EntityEnabled::new(parent));
+            world.events().add(EntityEnabled::new(child));
+            world.run(Root);
+
+            assert!(world.entities().is_active(&child));
+            assert_eq!(world.resource::<Toggled>().enabled, vec![parent]);
+        }
+
         #[test]
         fn on_remove_children() {
             struct RemovedChildren(usize);
@@ -952,5 +1510,74 @@ pub mod internal {
 
             assert_eq!(world.resource::<RemovedChildren>().0, child_count);
         }
+
+        #[test]
+        fn concurrent_batches() {
+            use crate::world::event::{ErasedEvent, Event, EventOutputs};
+
+            struct Ping(usize);
+            impl Event for Ping {
+                type Output = usize;
+                const PRIORITY: i32 = -1;
+
+                fn invoke(self, _: &mut World) -> Option<Self::Output> {
+                    Some(self.0)
+                }
+            }
+
+            struct Pong(usize);
+            impl Event for Pong {
+                type Output = usize;
+
+                fn invoke(self, _: &mut World) -> Option<Self::Output> {
+                    Some(self.0)
+                }
+            }
+
+            #[derive(Default)]
+            struct Log(Vec<usize>);
+            impl Resource for Log {}
+
+            const THREADS: usize = 4;
+            const PAIRS: usize = 500;
+
+            let mut world = World::new();
+            world
+                .init_resource::<Log>()
+                .register_event::<Ping>()
+                .register_event::<Pong>()
+                .observe::<Ping, _>(
+                    |pings: &[usize], pongs: &EventOutputs<Pong>, log: &mut Log| {
+                        let mut pongs = pongs.slice().to_vec();
+                        pongs.sort();
+                        let mut pings = pings.to_vec();
+                        pings.sort();
+                        assert_eq!(pings, pongs, "batch was split across drains");
+                        log.0.extend(pings);
+                    },
+                )
+                .observe::<Pong, _>(|_: &[usize]| {});
+
+            let handles = (0..THREADS)
+                .map(|thread| {
+                    let events = world.events().clone();
+                    std::thread::spawn(move || {
+                        for pair in 0..PAIRS {
+                            let id = thread * PAIRS + pair;
+                            let batch = [ErasedEvent::new(Ping(id)), ErasedEvent::new(Pong(id))];
+                            events.extend(batch);
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            while !handles.iter().all(|h| h.is_finished()) || !world.events().is_empty() {
+                world.flush();
+            }
+
+            let mut log = std::mem::take(&mut world.resource_mut::<Log>().0);
+            log.sort();
+            assert_eq!(log, (0..THREADS * PAIRS).collect::<Vec<_>>());
+        }
     }
 }
@@ -0,0 +1,228 @@
+use super::{event::ComponentEvents, World};
+use crate::{
+    archetype::Archetypes,
+    core::{DenseMap, Entities, Entity, Resource},
+};
+
+pub enum ResourceMerge<R: Resource> {
+    Skip,
+    Overwrite,
+    Merge(fn(&mut R, R)),
+}
+
+type MergeResource = Box<dyn FnOnce(&mut World, &mut World)>;
+
+/// Controls what happens to resources when merging worlds. Resources without
+/// a rule stay in the merged world and are dropped with it.
+pub struct MergeOptions {
+    resources: Vec<MergeResource>,
+}
+
+impl MergeOptions {
+    pub fn new() -> Self {
+        Self { resources: vec![] }
+    }
+
+    pub fn resource<R: Resource>(&mut self, rule: ResourceMerge<R>) -> &mut Self {
+        let merge: MergeResource = match rule {
+            ResourceMerge::Skip => return self,
+            ResourceMerge::Overwrite => Box::new(|world, other| {
+                if let Some(resource) = other.remove_resource::<R>() {
+                    world.add_resource(resource);
+                }
+            }),
+            ResourceMerge::Merge(merge) => Box::new(move |world, other| {
+                let resource = match other.remove_resource::<R>() {
+                    Some(resource) => resource,
+                    None => return,
+                };
+
+                match world.try_resource_mut::<R>() {
+                    Some(existing) => merge(existing, resource),
+                    None => {
+                        world.add_resource(resource);
+                    }
+                }
+            }),
+        };
+
+        self.resources.push(merge);
+        self
+    }
+
+    pub fn overwrite<R: Resource>(&mut self) -> &mut Self {
+        self.resource::<R>(ResourceMerge::Overwrite)
+    }
+
+    pub fn merge_with<R: Resource>(&mut self, merge: fn(&mut R, R)) -> &mut Self {
+        self.resource::<R>(ResourceMerge::Merge(merge))
+    }
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    /// Moves all entities, components and hierarchy from `other` into this world.
+    /// Entities are given new ids; the returned map goes from `other`'s ids to the new ones.
+    /// Events, observers and systems of `other` are not merged.
+    pub fn merge(&mut self, mut other: World, options: MergeOptions) -> DenseMap<Entity, Entity> {
+        for merge in options.resources {
+            merge(self, &mut other);
+        }
+
+        for (id, meta) in other.components.iter() {
+            if !self.components.contains(id) {
+                if let Some(events) = meta.extension::<ComponentEvents>() {
+                    events.register(self);
+                }
+            }
+        }
+
+        let entities = std::mem::replace(&mut other.entities, Entities::new());
+        let archetypes = std::mem::replace(&mut other.archetypes, Archetypes::new());
+        let map = self.entities.merge(entities);
+        self.archetypes.merge(archetypes, &map);
+        self.attachments.merge(&other.attachments, &map);
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MergeOptions;
+    use crate::{
+        core::{Component, ComponentId, Entity, Resource},
+        system::schedule::Root,
+        world::{
+            query::{Added, Changed, Query},
+            World,
+        },
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+    impl Component for Health {}
+
+    #[derive(Debug, PartialEq)]
+    struct Speed(f32);
+    impl Component for Speed {}
+
+    struct Score(u32);
+    impl Resource for Score {}
+
+    struct Level(&'static str);
+    impl Resource for Level {}
+
+    #[test]
+    fn merge_worlds() {
+        let mut world = World::new();
+        world.register::<Health>();
+        let existing = world.spawn(None);
+        world.add_component(&existing, Health(1));
+
+        let mut other = World::new();
+        other.register::<Health>().register::<Speed>();
+        let parent = other.spawn(None);
+        other.add_component(&parent, Health(2));
+        let child = other.spawn(Some(parent));
+        other.add_components_typed(&child, (Health(3), Speed(1.5)));
+        other.set_enabled(&child, false);
+
+        let map = world.merge(other, MergeOptions::new());
+        let parent = map.get(&parent).copied().unwrap();
+        let child = map.get(&child).copied().unwrap();
+
+        assert_eq!(world.entities().len(), 3);
+        assert_eq!(world.entities().parent(&child), Some(&parent));
+        assert_eq!(world.entities().children(&parent), Some(&[child][..]));
+        assert!(!world.entities().is_enabled(&child));
+        assert!(world.components().contains(&ComponentId::new::<Speed>()));
+
+        let archetypes = world.archetypes();
+        let component = |entity| {
+            let id = archetypes.entity_archetype(entity).unwrap();
+            archetypes.get(&id).unwrap().component::<Health>(entity)
+        };
+        assert_eq!(component(&existing), Some(&Health(1)));
+        assert_eq!(component(&parent), Some(&Health(2)));
+        assert_eq!(component(&child), Some(&Health(3)));
+    }
+
+    #[test]
+    fn merge_appends_tables() {
+        let mut other = World::new();
+        other.register::<Health>();
+        let entities = (0..10_000)
+            .map(|index| {
+                let entity = other.spawn(None);
+                other.add_component(&entity, Health(index));
+                entity
+            })
+            .collect::<Vec<_>>();
+
+        let mut world = World::new();
+        world.register::<Health>();
+        let existing = world.spawn(None);
+        world.add_component(&existing, Health(0));
+
+        let moves = world.archetypes().moves;
+        let map = world.merge(other, MergeOptions::new());
+        assert_eq!(world.archetypes().moves, moves);
+        assert_eq!(world.entities().len(), 10_001);
+
+        let archetypes = world.archetypes();
+        let id = archetypes.entity_archetype(&existing).unwrap();
+        let archetype = archetypes.get(&id).unwrap();
+        for (index, entity) in entities.iter().enumerate() {
+            let entity = map.get(entity).unwrap();
+            assert_eq!(
+                archetype.component::<Health>(entity),
+                Some(&Health(index as u32))
+            );
+        }
+    }
+
+    #[test]
+    fn merged_components_are_added_and_changed() {
+        let mut other = World::new();
+        other.register::<Health>();
+        let entity = other.spawn(None);
+        other.add_component(&entity, Health(1));
+
+        let mut world = World::new();
+        world.register::<Health>().build();
+        let existing = world.spawn(None);
+        world.add_component(&existing, Health(0));
+        world.run(Root);
+
+        let map = world.merge(other, MergeOptions::new());
+        let entity = map.get(&entity).copied().unwrap();
+        world.run(Root);
+
+        let added = Query::<Entity, Added<Health>>::new(&world).collect::<Vec<_>>();
+        let changed = Query::<Entity, Changed<Health>>::new(&world).collect::<Vec<_>>();
+        assert_eq!(added, [entity]);
+        assert_eq!(changed, [entity]);
+    }
+
+    #[test]
+    fn merge_resources() {
+        let mut world = World::new();
+        world.add_resource(Score(1)).add_resource(Level("town"));
+
+        let mut other = World::new();
+        other.add_resource(Score(2)).add_resource(Level("forest"));
+
+        let mut options = MergeOptions::new();
+        options.merge_with::<Score>(|score, other| score.0 += other.0);
+        world.merge(other, options);
+
+        assert_eq!(world.resource::<Score>().0, 3);
+        assert_eq!(world.resource::<Level>().0, "town");
+    }
+}
@@ -1,27 +1,45 @@
-use event::{Event, Events};
+use event::{ErasedEvent, Event, Events};
 
 use self::event::{
-    AddChildren, AddComponent, AddComponents, ComponentEvents, Despawn, RemoveChildren,
-    RemoveComponent, RemoveComponents, SetParent, Spawn,
+    AddChildren, AddComponent, AddComponents, ComponentEvents, Despawn, DespawnCascade,
+    EntityDisabled, EntityEnabled, RemoveChildren, RemoveComponent, RemoveComponents, SetParent,
+    Spawn, SystemPanicked,
 };
 use super::{
     archetype::{ArchetypeId, ArchetypeMove, Archetypes},
     core::{
         Component, ComponentId, Components, DenseMap, DenseSet, Entities, Entity, LocalResource,
-        LocalResources, Resource, Resources,
+        LocalResources, Resource, ResourceType, Resources,
     },
     system::{
         observer::{EventObservers, IntoObserver},
-        schedule::{Phase, PhaseRunner, SystemGroup, SystemTag, Systems, SystemsInfo},
-        IntoSystem, RunMode,
+        schedule::{
+            BuildError, Phase, PhaseRunner, Schedule, ScheduleId, SystemGroup, SystemTag, Systems,
+            SystemsInfo,
+        },
+        timing::{FrameBudget, FrameTimings},
+        IntoSystem, PanicPolicy, RunMode,
     },
     task::{max_thread_count, TaskPool},
 };
-use crate::archetype::table::EntityRow;
-use std::{any::TypeId, collections::HashSet};
+use crate::archetype::table::{ComponentSet, EntityRow};
+use attachments::EntityAttachments;
+use commands::CommandQueues;
+use std::{
+    any::TypeId,
+    collections::HashSet,
+    time::{Duration, Instant},
+};
 
+pub mod attachments;
+pub mod commands;
+pub mod defaults;
+pub mod derived;
+pub mod diff;
 pub mod event;
+pub mod merge;
 pub mod query;
+pub mod stats;
 
 pub struct World {
     systems: Option<Systems>,
@@ -34,6 +52,10 @@ pub struct World {
     events: Events,
     observers: EventObservers,
     tasks: TaskPool,
+    attachments: EntityAttachments,
+    commands: CommandQueues,
+    panic_policy: PanicPolicy,
+    timings: FrameTimings,
 }
 
 impl World {
@@ -42,11 +64,15 @@ impl World {
         let mut events = Events::new();
         resources.add(events.register::<Spawn>());
         resources.add(events.register::<Despawn>());
+        resources.add(events.register::<DespawnCascade>());
         resources.add(events.register::<SetParent>());
         resources.add(events.register::<AddChildren>());
         resources.add(events.register::<RemoveChildren>());
         resources.add(events.register::<AddComponents>());
         resources.add(events.register::<RemoveComponents>());
+        resources.add(events.register::<EntityEnabled>());
+        resources.add(events.register::<EntityDisabled>());
+        resources.add(events.register::<SystemPanicked>());
 
         Self {
             resources,
@@ -59,6 +85,10 @@ impl World {
             archetypes: Archetypes::new(),
             observers: EventObservers::new(),
             tasks: TaskPool::new(max_thread_count().min(3)),
+            attachments: EntityAttachments::new(),
+            commands: CommandQueues::new(),
+            panic_policy: PanicPolicy::default(),
+            timings: FrameTimings::new(),
         }
     }
 
@@ -86,6 +116,10 @@ impl World {
         &self.entities
     }
 
+    pub fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
     pub fn archetypes(&self) -> &Archetypes {
         &self.archetypes
     }
@@ -94,9 +128,25 @@ impl World {
         &self.events
     }
 
+    pub fn attachments(&self) -> &EntityAttachments {
+        &self.attachments
+    }
+
     pub fn tasks(&self) -> &TaskPool {
         &self.tasks
     }
+
+    pub fn panic_policy(&self) -> PanicPolicy {
+        self.panic_policy
+    }
+
+    pub fn timings(&self) -> &FrameTimings {
+        &self.timings
+    }
+
+    pub(crate) fn timings_mut(&mut self) -> &mut FrameTimings {
+        &mut self.timings
+    }
 }
 
 impl World {
@@ -108,6 +158,26 @@ impl World {
             .register_event::<RemoveComponent<C>>()
     }
 
+    /// Registers a zero sized marker stored outside the archetype tables. Adding or
+    /// removing it never moves the entity, at the cost of per-entity checks in queries
+    /// filtering on it. Must be registered before any entity has the marker.
+    pub fn register_sparse_marker<C: Component>(&mut self) -> &mut Self {
+        if std::mem::size_of::<C>() != 0 {
+            panic!(
+                "Sparse marker {} must be zero sized",
+                std::any::type_name::<C>()
+            );
+        }
+
+        self.archetypes.register_sparse(ComponentId::new::<C>());
+        self.register::<C>()
+    }
+
+    /// Creates the archetype for `S`, and the edges leading to it, ahead of time.
+    pub fn register_archetype<S: ComponentSet>(&mut self) -> ArchetypeId {
+        self.archetypes.register(S::table_builder())
+    }
+
     pub fn register_event<E: Event>(&mut self) -> &mut Self {
         let outputs = self.events.register::<E>();
         self.add_resource(outputs);
@@ -119,13 +189,29 @@ impl World {
         self
     }
 
-    pub fn add_phase<P: Phase>(&mut self) -> &mut Self {
-        self.systems.as_mut().unwrap().add_phase::<P>();
+    /// Applies the structural events queued by the systems added to `phase` so far
+    /// before the systems added after this call run.
+    pub fn add_barrier(&mut self, phase: impl Phase) -> &mut Self {
+        self.systems.as_mut().unwrap().add_barrier(phase);
         self
     }
 
-    pub fn add_sub_phase<Main: Phase, Sub: Phase>(&mut self) -> &mut Self {
+    pub fn add_phase<P: Phase>(&mut self) -> PhaseConfig<'_, P> {
+        self.systems.as_mut().unwrap().add_phase::<P>();
+        PhaseConfig::new(self)
+    }
+
+    pub fn add_sub_phase<Main: Phase, Sub: Phase>(&mut self) -> PhaseConfig<'_, Sub> {
         self.systems.as_mut().unwrap().add_sub_phase::<Main, Sub>();
+        PhaseConfig::new(self)
+    }
+
+    /// Runs `First` before `Second`. Both must be children of the same phase.
+    pub fn order_phases<First: Phase, Second: Phase>(&mut self) -> &mut Self {
+        self.systems
+            .as_mut()
+            .unwrap()
+            .order_phases::<First, Second>();
         self
     }
 
@@ -145,6 +231,19 @@ impl World {
         self
     }
 
+    /// Places `New` right after `Anchor`, waiting for `Anchor` to be added if needed.
+    pub fn add_phase_after<Anchor: Phase, New: Phase>(&mut self) -> &mut Self {
+        self.insert_phase_after::<New, Anchor>()
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        self.systems.as_ref().unwrap().schedule()
+    }
+
+    pub fn phase_order(&self) -> Vec<&Schedule> {
+        self.systems.as_ref().unwrap().phase_order()
+    }
+
     pub fn add_phase_runner<P: Phase>(&mut self, runner: impl PhaseRunner) -> &mut Self {
         self.systems.as_mut().unwrap().add_phase_runner::<P>(runner);
         self
@@ -188,10 +287,68 @@ impl World {
         self
     }
 
+    /// True if any observer is registered for `E`. Events skip recording outputs nobody reads.
+    pub fn observes<E: Event>(&self) -> bool {
+        self.observers.contains::<E>()
+    }
+
+    pub fn set_panic_policy(&mut self, policy: PanicPolicy) -> &mut Self {
+        self.panic_policy = policy;
+        self
+    }
+
     pub fn build(&mut self) -> &mut Self {
-        self.systems.as_mut().unwrap().build();
+        if let Err(error) = self.try_build() {
+            panic!("{}", error);
+        }
+
         self
     }
+
+    pub fn try_build(&mut self) -> Result<&mut Self, BuildError> {
+        self.systems.as_mut().unwrap().build()?;
+        Ok(self)
+    }
+}
+
+/// Orders a newly added phase against its sibling phases. Dereferences to the
+/// world so registration calls can keep chaining.
+pub struct PhaseConfig<'a, P: Phase> {
+    world: &'a mut World,
+    _marker: std::marker::PhantomData<P>,
+}
+
+impl<'a, P: Phase> PhaseConfig<'a, P> {
+    fn new(world: &'a mut World) -> Self {
+        Self {
+            world,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    pub fn before<Other: Phase>(self) -> Self {
+        self.world.order_phases::<P, Other>();
+        self
+    }
+
+    pub fn after<Other: Phase>(self) -> Self {
+        self.world.order_phases::<Other, P>();
+        self
+    }
+}
+
+impl<P: Phase> std::ops::Deref for PhaseConfig<'_, P> {
+    type Target = World;
+
+    fn deref(&self) -> &Self::Target {
+        self.world
+    }
+}
+
+impl<P: Phase> std::ops::DerefMut for PhaseConfig<'_, P> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.world
+    }
 }
 
 impl World {
@@ -203,12 +360,14 @@ impl World {
 
     pub fn despawn(&mut self, entity: &Entity) -> DenseMap<Entity, EntityRow> {
         let mut despawned = DenseMap::new();
-        for entity in self.entities.despawn(entity) {
-            if let Some((_, set)) = self.archetypes.remove_entity(&entity) {
-                despawned.insert(entity, set);
+        let entities = self.entities.despawn(entity);
+        for entity in &entities {
+            if let Some((_, set)) = self.archetypes.despawn_entity(entity) {
+                despawned.insert(*entity, set);
             }
         }
 
+        self.attachments.release(&entities);
         despawned
     }
 
@@ -239,6 +398,12 @@ impl World {
         self.archetypes.add_component(entity, &id, component)
     }
 
+    /// Adds or overwrites the component without reporting the move. See `Archetypes::set_component`.
+    pub fn set_component<C: Component>(&mut self, entity: &Entity, component: C) -> bool {
+        let id = ComponentId::new::<C>();
+        self.archetypes.set_component(entity, &id, component)
+    }
+
     pub fn add_components(
         &mut self,
         entity: &Entity,
@@ -247,6 +412,15 @@ impl World {
         self.archetypes.add_components(entity, components)
     }
 
+    pub fn add_components_typed(
+        &mut self,
+        entity: &Entity,
+        components: impl ComponentSet,
+    ) -> Option<ArchetypeMove> {
+        let row = EntityRow::from_components(components);
+        self.archetypes.add_components(entity, row)
+    }
+
     pub fn remove_component(
         &mut self,
         entity: &Entity,
@@ -255,6 +429,11 @@ impl World {
         self.archetypes.remove_component(entity, component)
     }
 
+    /// Removes the component and drops it without reporting the move.
+    pub fn discard_component(&mut self, entity: &Entity, component: &ComponentId) -> bool {
+        self.archetypes.discard_component(entity, component)
+    }
+
     pub fn remove_components(
         &mut self,
         entity: &Entity,
@@ -267,6 +446,13 @@ impl World {
         self.entities.set_parent(entity, parent)
     }
 
+    /// Enables or disables an entity without moving it between archetypes.
+    /// Queries skip disabled entities and their descendants unless they use `IncludeDisabled`.
+    /// Observers aren't notified; queue `EntityEnabled` or `EntityDisabled` to reach them.
+    pub fn set_enabled(&mut self, entity: &Entity, enabled: bool) -> bool {
+        self.entities.set_enabled(entity, enabled)
+    }
+
     pub fn activate_system_group(&mut self, tag: impl Into<SystemTag>) {
         self.infos.activate(tag.into());
     }
@@ -275,7 +461,16 @@ impl World {
         self.infos.deactivate(tag.into());
     }
 
+    pub fn enable_system(&mut self, name: &str) -> bool {
+        self.systems.as_ref().unwrap().set_enabled(name, true)
+    }
+
+    pub fn disable_system(&mut self, name: &str) -> bool {
+        self.systems.as_ref().unwrap().set_enabled(name, false)
+    }
+
     pub fn flush(&mut self) {
+        self.commands.flush(&self.events);
         let mut events = self.events.drain();
 
         while !events.is_empty() {
@@ -285,8 +480,37 @@ impl World {
             }
 
             self.observers.run(self);
+            self.commands.flush(&self.events);
             events = self.events.drain();
         }
+
+        self.attachments.sweep(&self.entities);
+    }
+
+    pub fn flush_deferred(&mut self, phase: ScheduleId) {
+        let mut events = self.events.take_deferred(&phase);
+        while !events.is_empty() {
+            for event in events {
+                let meta = self.events.meta_dynamic(event.ty());
+                meta.invoke(event, self);
+            }
+
+            self.observers.run(self);
+            events = self.events.take_deferred(&phase);
+        }
+    }
+
+    /// Invokes `event` and its observers now, then applies the events they raise.
+    /// Events queued before the call are set aside and stay queued for the next flush.
+    pub fn dispatch<E: Event>(&mut self, event: E) {
+        let pending = self.events.drain();
+
+        let meta = self.events.meta::<E>();
+        meta.invoke(ErasedEvent::new(event), self);
+        self.observers.run(self);
+        self.flush();
+
+        self.events.extend(pending);
     }
 
     pub fn flush_events<E: Event>(&mut self) {
@@ -307,15 +531,38 @@ impl World {
         let systems = self.systems.take().unwrap();
         let id = phase.id();
 
+        let started = Instant::now();
+        self.timings.begin_frame();
+        self.archetypes.advance_tick();
         systems.run(id, self);
+        self.check_budget(&systems, started.elapsed());
 
         self.systems = Some(systems);
 
         self
     }
+
+    fn check_budget(&self, systems: &Systems, elapsed: Duration) {
+        let Some(budget) = self.resources.try_get_mut::<FrameBudget>() else {
+            return;
+        };
+
+        let frame = self.timings.frame();
+        let ran = systems
+            .all()
+            .filter(|system| system.timing().frame() == frame)
+            .map(|system| (system.name(), system.timing().last()));
+
+        budget.check(Instant::now(), elapsed, &self.timings, ran);
+    }
 }
 
 impl World {
+    /// Resolves a resource's stable name, or an alias of one.
+    pub fn resource_type(&self, name: &str) -> Option<ResourceType> {
+        self.resources.type_by_name(name)
+    }
+
     pub fn try_resource<R: Resource>(&self) -> Option<&R> {
         self.resources.try_get::<R>()
     }
@@ -364,3 +611,9 @@ impl Default for World {
         Self::new()
     }
 }
+
+impl Drop for World {
+    fn drop(&mut self) {
+        self.attachments.release_all();
+    }
+}
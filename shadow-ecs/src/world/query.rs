@@ -1,12 +1,12 @@
 use crate::{
     archetype::{Archetype, ArchetypeId},
-    core::{Component, ComponentId, Entity},
+    core::{Component, ComponentId, Entity, Tick},
     system::{
         access::{Access, WorldAccess, WorldAccessType},
-        SystemArg,
+        cost, SystemArg,
     },
 };
-use std::collections::HashSet;
+use std::{borrow::Cow, collections::HashSet};
 
 use super::World;
 
@@ -14,18 +14,19 @@ pub trait BaseQuery {
     type Item<'a>;
 
     fn init(_: &World, _: &mut QueryState) {}
-    fn fetch(archetype: &Archetype, entity: Entity) -> Self::Item<'_>;
+    /// `tick` is the world's change tick, stamped on components fetched mutably.
+    fn fetch(archetype: &Archetype, entity: Entity, tick: Tick) -> Self::Item<'_>;
     fn access() -> Vec<WorldAccess>;
 }
 
 impl<C: Component> BaseQuery for &C {
     type Item<'a> = &'a C;
 
-    fn init(_: &World, state: &mut QueryState) {
-        state.add_component(ComponentId::new::<C>());
+    fn init(world: &World, state: &mut QueryState) {
+        state.add_component(fetched::<C>(world));
     }
 
-    fn fetch(archetype: &Archetype, entity: Entity) -> Self::Item<'_> {
+    fn fetch(archetype: &Archetype, entity: Entity, _: Tick) -> Self::Item<'_> {
         archetype.component::<C>(&entity).unwrap()
     }
 
@@ -40,12 +41,12 @@ impl<C: Component> BaseQuery for &C {
 impl<C: Component> BaseQuery for &mut C {
     type Item<'a> = &'a mut C;
 
-    fn init(_: &World, state: &mut QueryState) {
-        state.add_component(ComponentId::new::<C>());
+    fn init(world: &World, state: &mut QueryState) {
+        state.add_component(fetched::<C>(world));
     }
 
-    fn fetch(archetype: &Archetype, entity: Entity) -> Self::Item<'_> {
-        archetype.component_mut::<C>(&entity).unwrap()
+    fn fetch(archetype: &Archetype, entity: Entity, tick: Tick) -> Self::Item<'_> {
+        archetype.component_mut::<C>(&entity, tick).unwrap()
     }
 
     fn access() -> Vec<WorldAccess> {
@@ -59,7 +60,7 @@ impl<C: Component> BaseQuery for &mut C {
 impl<C: Component> BaseQuery for Option<&C> {
     type Item<'a> = Option<&'a C>;
 
-    fn fetch(archetype: &Archetype, entity: Entity) -> Self::Item<'_> {
+    fn fetch(archetype: &Archetype, entity: Entity, _: Tick) -> Self::Item<'_> {
         archetype.component::<C>(&entity)
     }
 
@@ -74,8 +75,8 @@ impl<C: Component> BaseQuery for Option<&C> {
 impl<C: Component> BaseQuery for Option<&mut C> {
     type Item<'a> = Option<&'a mut C>;
 
-    fn fetch(archetype: &Archetype, entity: Entity) -> Self::Item<'_> {
-        archetype.component_mut::<C>(&entity)
+    fn fetch(archetype: &Archetype, entity: Entity, tick: Tick) -> Self::Item<'_> {
+        archetype.component_mut::<C>(&entity, tick)
     }
 
     fn access() -> Vec<WorldAccess> {
@@ -89,7 +90,7 @@ impl<C: Component> BaseQuery for Option<&mut C> {
 impl BaseQuery for Entity {
     type Item<'a> = Entity;
 
-    fn fetch(_: &Archetype, entity: Entity) -> Self::Item<'_> {
+    fn fetch(_: &Archetype, entity: Entity, _: Tick) -> Self::Item<'_> {
         entity
     }
 
@@ -99,6 +100,19 @@ impl BaseQuery for Entity {
     }
 }
 
+fn fetched<C: Component>(world: &World) -> ComponentId {
+    let id = ComponentId::new::<C>();
+    if world.archetypes().sparse().is_sparse(&id) {
+        let name = std::any::type_name::<C>();
+        panic!(
+            "Sparse marker {} can only be used in With and Not filters",
+            name
+        );
+    }
+
+    id
+}
+
 pub trait FilterQuery {
     fn init(world: &World, state: &mut QueryState);
 }
@@ -108,8 +122,12 @@ pub struct With<C: Component> {
 }
 
 impl<C: Component> FilterQuery for With<C> {
-    fn init(_: &World, state: &mut QueryState) {
-        state.add_component(ComponentId::new::<C>());
+    fn init(world: &World, state: &mut QueryState) {
+        let id = ComponentId::new::<C>();
+        match world.archetypes().sparse().is_sparse(&id) {
+            true => state.add_sparse(id),
+            false => state.add_component(id),
+        }
     }
 }
 
@@ -118,8 +136,49 @@ pub struct Not<C: Component> {
 }
 
 impl<C: Component> FilterQuery for Not<C> {
+    fn init(world: &World, state: &mut QueryState) {
+        let id = ComponentId::new::<C>();
+        match world.archetypes().sparse().is_sparse(&id) {
+            true => state.exclude_sparse(id),
+            false => state.exclude(id),
+        }
+    }
+}
+
+/// Entities whose `C` was added or borrowed mutably during the previous frame, where a
+/// frame is one `World::run`. Fetching `&mut C` counts as a change even if nothing is written.
+pub struct Changed<C: Component> {
+    _marker: std::marker::PhantomData<C>,
+}
+
+impl<C: Component> FilterQuery for Changed<C> {
+    fn init(world: &World, state: &mut QueryState) {
+        let id = fetched::<C>(world);
+        state.add_component(id);
+        state.add_changed(id);
+    }
+}
+
+/// Entities that gained `C` during the previous frame, ticked like `Changed`. Replacing a
+/// component the entity already has doesn't count.
+pub struct Added<C: Component> {
+    _marker: std::marker::PhantomData<C>,
+}
+
+impl<C: Component> FilterQuery for Added<C> {
+    fn init(world: &World, state: &mut QueryState) {
+        let id = fetched::<C>(world);
+        state.add_component(id);
+        state.add_added(id);
+    }
+}
+
+/// Includes rows whose entity, or one of its ancestors, is disabled.
+pub struct IncludeDisabled;
+
+impl FilterQuery for IncludeDisabled {
     fn init(_: &World, state: &mut QueryState) {
-        state.exclude(ComponentId::new::<C>());
+        state.include_disabled();
     }
 }
 
@@ -129,39 +188,165 @@ impl FilterQuery for () {
 
 pub struct Query<'a, Q: BaseQuery, F: FilterQuery = ()> {
     world: &'a World,
-    archetypes: Vec<ArchetypeId>,
+    cache: Cow<'a, QueryCache>,
     row_index: usize,
     archetype_index: usize,
     archetype: Option<&'a Archetype>,
+    tick: Tick,
     _marker: std::marker::PhantomData<(Q, F)>,
 }
 
 impl<'a, Q: BaseQuery, F: FilterQuery> Query<'a, Q, F> {
     pub fn new(world: &'a World) -> Self {
-        let mut state = QueryState::new();
-        Q::init(world, &mut state);
-        F::init(world, &mut state);
+        let mut cache = QueryCache::default();
+        cache.update::<Q, F>(world);
+        Self::from_cache(world, Cow::Owned(cache))
+    }
 
-        let archetypes = world
-            .archetypes()
-            .query(state.components(), &state.excluded);
-        let archetype = archetypes.get(0).and_then(|id| world.archetypes().get(id));
+    fn from_cache(world: &'a World, cache: Cow<'a, QueryCache>) -> Self {
+        let archetypes = world.archetypes();
+        let matched = cache
+            .archetypes
+            .iter()
+            .filter_map(|id| archetypes.get(id))
+            .map(|archetype| archetype.entities().len())
+            .sum();
+        cost::record_matched(matched);
 
+        let archetype = cache.archetypes.first().and_then(|id| archetypes.get(id));
         Self {
             world,
-            archetypes,
+            cache,
             archetype_index: 0,
             row_index: 0,
             archetype,
+            tick: archetypes.tick(),
             _marker: std::marker::PhantomData,
         }
     }
+
+    /// The only matching item, or `None` if zero or several entities match.
+    /// `single_mut` is the same for queries that fetch `&mut C`.
+    pub fn single(mut self) -> Option<Q::Item<'a>> {
+        let item = self.next()?;
+        match self.next() {
+            Some(_) => None,
+            None => Some(item),
+        }
+    }
+
+    /// Like `single`, but panics unless exactly one entity matches.
+    pub fn single_unchecked(self) -> Q::Item<'a> {
+        match self.single() {
+            Some(item) => item,
+            None => panic!(
+                "Query::single_unchecked expected one match for {}",
+                std::any::type_name::<Q>()
+            ),
+        }
+    }
+
+    fn matches(&self, archetype: &Archetype, entity: &Entity) -> bool {
+        let archetypes = self.world.archetypes();
+        let sparse = archetypes.sparse();
+        let state = &self.cache.state;
+        let changed =
+            |id: &ComponentId| archetype.changed_tick(id, entity) == Some(self.tick.prev());
+
+        let entities = self.world.entities();
+        (state.include_disabled || !entities.any_disabled() || entities.is_active(entity))
+            && sparse.matches(entity, &state.with_sparse, &state.without_sparse)
+            && state.changed.iter().all(changed)
+            && state.added.iter().all(|id| archetypes.is_added(id, entity))
+    }
+
+    fn next_archetype(&mut self) {
+        self.archetype_index += 1;
+        self.row_index = 0;
+        self.archetype = self
+            .cache
+            .archetypes
+            .get(self.archetype_index)
+            .and_then(|id| self.world.archetypes().get(id));
+    }
+}
+
+impl<'a, C: Component, F: FilterQuery> Query<'a, &mut C, F> {
+    /// The only matching component, mutably, or `None` if zero or several entities match.
+    pub fn single_mut(self) -> Option<&'a mut C> {
+        self.single()
+    }
+}
+
+impl<'a, Q: BaseQuery, F: FilterQuery> Query<'a, Q, F> {
+    /// Visits every unordered pair of the remaining rows once, with both items
+    /// borrowed mutably at the same time.
+    pub fn iter_combinations_mut(mut self) -> QueryCombinations<'a, Q> {
+        let mut rows = vec![];
+        while let Some(archetype) = self.archetype {
+            let entities = &archetype.entities()[self.row_index..];
+            rows.extend(
+                entities
+                    .iter()
+                    .filter(|entity| self.matches(archetype, entity))
+                    .map(|entity| (archetype, *entity)),
+            );
+
+            self.next_archetype();
+        }
+
+        QueryCombinations {
+            rows,
+            first: 0,
+            second: 1,
+            tick: self.tick,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+/// Pairs of query rows. Each pair borrows the combinations, so a row can't be
+/// borrowed twice at once.
+pub struct QueryCombinations<'a, Q: BaseQuery> {
+    rows: Vec<(&'a Archetype, Entity)>,
+    first: usize,
+    second: usize,
+    tick: Tick,
+    _marker: std::marker::PhantomData<Q>,
+}
+
+impl<'a, Q: BaseQuery> QueryCombinations<'a, Q> {
+    pub fn fetch_next(&mut self) -> Option<(Q::Item<'_>, Q::Item<'_>)> {
+        if self.second >= self.rows.len() {
+            self.first += 1;
+            self.second = self.first + 1;
+            if self.second >= self.rows.len() {
+                return None;
+            }
+        }
+
+        let second = self.second;
+        self.second += 1;
+
+        let rows = &self.rows;
+        let fetch = |index: usize| {
+            let (archetype, entity): (&Archetype, Entity) = rows[index];
+            Q::fetch(archetype, entity, self.tick)
+        };
+
+        Some((fetch(self.first), fetch(second)))
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct QueryState {
     components: Vec<ComponentId>,
     excluded: HashSet<ComponentId>,
+    with_sparse: Vec<ComponentId>,
+    without_sparse: Vec<ComponentId>,
+    changed: Vec<ComponentId>,
+    added: Vec<ComponentId>,
+    include_disabled: bool,
 }
 
 impl QueryState {
@@ -169,9 +354,18 @@ impl QueryState {
         Self {
             components: Vec::new(),
             excluded: HashSet::new(),
+            with_sparse: Vec::new(),
+            without_sparse: Vec::new(),
+            changed: Vec::new(),
+            added: Vec::new(),
+            include_disabled: false,
         }
     }
 
+    pub fn include_disabled(&mut self) {
+        self.include_disabled = true;
+    }
+
     pub fn add_component(&mut self, component: ComponentId) {
         self.components.push(component);
     }
@@ -180,6 +374,25 @@ impl QueryState {
         self.excluded.insert(component);
     }
 
+    /// Requires a sparse marker, checked per entity.
+    pub fn add_sparse(&mut self, marker: ComponentId) {
+        self.with_sparse.push(marker);
+    }
+
+    pub fn exclude_sparse(&mut self, marker: ComponentId) {
+        self.without_sparse.push(marker);
+    }
+
+    /// Requires the component to have changed in the previous frame, checked per entity.
+    pub fn add_changed(&mut self, component: ComponentId) {
+        self.changed.push(component);
+    }
+
+    /// Requires the component to have been added in the previous frame, checked per entity.
+    pub fn add_added(&mut self, component: ComponentId) {
+        self.added.push(component);
+    }
+
     pub fn components(&self) -> &[ComponentId] {
         &self.components
     }
@@ -189,36 +402,81 @@ impl QueryState {
     }
 }
 
+/// A query's filters and the archetypes that have its components, kept by a system
+/// between runs. Archetypes are only ever added, so the cache is rebuilt when the
+/// archetype count changes, or when a sparse marker is registered.
+#[derive(Clone, Default)]
+pub struct QueryCache {
+    state: QueryState,
+    archetypes: Vec<ArchetypeId>,
+    generation: Option<(usize, usize)>,
+}
+
+impl QueryCache {
+    pub fn update<Q: BaseQuery, F: FilterQuery>(&mut self, world: &World) {
+        let archetypes = world.archetypes();
+        let generation = Some((archetypes.len(), archetypes.sparse().len()));
+        if self.generation == generation {
+            return;
+        }
+
+        self.state = QueryState::new();
+        Q::init(world, &mut self.state);
+        F::init(world, &mut self.state);
+
+        // Empty archetypes are kept, since entities may move into them before the next
+        // rebuild. Iteration skips them.
+        let state = &self.state;
+        self.archetypes.clear();
+        if !state.components.is_empty() {
+            let matched = archetypes
+                .iter_with(&state.components)
+                .filter(|archetype| !state.excluded.iter().any(|id| archetype.has_component(id)))
+                .map(Archetype::id);
+            self.archetypes.extend(matched);
+        }
+
+        self.generation = generation;
+    }
+
+    pub fn state(&self) -> &QueryState {
+        &self.state
+    }
+
+    pub fn archetypes(&self) -> &[ArchetypeId] {
+        &self.archetypes
+    }
+}
+
 impl<'a, Q: BaseQuery, F: FilterQuery> Iterator for Query<'a, Q, F> {
     type Item = Q::Item<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.archetype_index >= self.archetypes.len() {
-            return None;
-        } else if self.row_index >= self.archetype.unwrap().entities().len() {
-            self.archetype_index += 1;
-            self.row_index = 0;
-            self.archetype = self
-                .archetypes
-                .get(self.archetype_index)
-                .and_then(|id| self.world.archetypes().get(id));
-
-            return self.next();
-        } else {
-            let archetype = self.archetype.unwrap();
+        while let Some(archetype) = self.archetype {
+            if self.row_index >= archetype.entities().len() {
+                self.next_archetype();
+                continue;
+            }
+
             let entity = archetype.entities()[self.row_index];
             self.row_index += 1;
 
-            Some(Q::fetch(archetype, entity))
+            if self.matches(archetype, &entity) {
+                return Some(Q::fetch(archetype, entity, self.tick));
+            }
         }
+
+        None
     }
 }
 
 impl<Q: BaseQuery, F: FilterQuery> SystemArg for Query<'_, Q, F> {
     type Item<'a> = Query<'a, Q, F>;
+    type State = QueryCache;
 
-    fn get<'a>(world: &'a World) -> Self::Item<'a> {
-        Query::new(world)
+    fn get<'a>(world: &'a World, state: &'a mut QueryCache) -> Self::Item<'a> {
+        state.update::<Q, F>(world);
+        Query::from_cache(world, Cow::Borrowed(state))
     }
 
     fn access() -> Vec<WorldAccess> {
@@ -239,8 +497,8 @@ macro_rules! impl_base_query_for_tuples {
                     )+
                 }
 
-                fn fetch(archetype: &Archetype, entity: Entity) -> Self::Item<'_> {
-                    ($($name::fetch(archetype, entity),)+)
+                fn fetch(archetype: &Archetype, entity: Entity, tick: Tick) -> Self::Item<'_> {
+                    ($($name::fetch(archetype, entity, tick),)+)
                 }
 
                 fn access() -> Vec<WorldAccess> {
@@ -285,12 +543,20 @@ impl_base_query_for_tuples!((A, B, C, D, E, F, G, H, I, J, K, L, M, N, O));
 impl_base_query_for_tuples!((A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P));
 impl_base_query_for_tuples!((A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q));
 
+impl_filter_query_for_tuple!(A, B);
+impl_filter_query_for_tuple!(A, B, C);
+impl_filter_query_for_tuple!(A, B, C, D);
+impl_filter_query_for_tuple!(A, B, C, D, E);
+impl_filter_query_for_tuple!(A, B, C, D, E, F);
+impl_filter_query_for_tuple!(A, B, C, D, E, F, G);
+impl_filter_query_for_tuple!(A, B, C, D, E, F, G, H);
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::archetype::table::EntityRow;
+    use crate::archetype::{table::EntityRow, EdgeType};
     use crate::core::Component;
+    use crate::system::schedule::Root;
     use crate::world::World;
 
     struct A;
@@ -356,4 +622,442 @@ mod tests {
             None => (),
         };
     }
+
+    fn spawn_a(world: &mut World, parent: Option<Entity>) -> Entity {
+        let entity = world.spawn(parent);
+        let mut components = EntityRow::new();
+        components.add_component(A);
+        world.add_components(&entity, components);
+        entity
+    }
+
+    #[test]
+    fn query_skips_disabled() {
+        let mut world = World::new();
+        let first = spawn_a(&mut world, None);
+        let second = spawn_a(&mut world, None);
+        world.set_enabled(&first, false);
+
+        let entities = Query::<Entity, With<A>>::new(&world).collect::<Vec<_>>();
+        assert_eq!(entities, vec![second]);
+
+        let entities = Query::<Entity, (With<A>, IncludeDisabled)>::new(&world).count();
+        assert_eq!(entities, 2);
+
+        world.set_enabled(&first, true);
+        assert_eq!(Query::<&A>::new(&world).count(), 2);
+    }
+
+    #[test]
+    fn disabled_parent_hides_children() {
+        let mut world = World::new();
+        let parent = spawn_a(&mut world, None);
+        let child = spawn_a(&mut world, Some(parent));
+        world.set_enabled(&child, false);
+        world.set_enabled(&parent, false);
+        assert_eq!(Query::<&A>::new(&world).count(), 0);
+
+        world.set_enabled(&parent, true);
+        let entities = Query::<Entity, With<A>>::new(&world).collect::<Vec<_>>();
+        assert_eq!(entities, vec![parent]);
+        assert!(!world.entities().is_enabled(&child));
+    }
+
+    #[test]
+    fn reparenting_updates_inherited_disabled() {
+        let mut world = World::new();
+        let disabled = spawn_a(&mut world, None);
+        let enabled = spawn_a(&mut world, None);
+        let child = spawn_a(&mut world, Some(enabled));
+        let grandchild = spawn_a(&mut world, Some(child));
+        world.set_enabled(&disabled, false);
+        assert_eq!(Query::<&A>::new(&world).count(), 3);
+
+        world.set_parent(&child, Some(&disabled));
+        let entities = Query::<Entity, With<A>>::new(&world).collect::<Vec<_>>();
+        assert_eq!(entities, vec![enabled]);
+
+        world.set_parent(&child, None);
+        assert!(world.entities().is_active(&grandchild));
+        assert_eq!(Query::<&A>::new(&world).count(), 3);
+    }
+
+    #[test]
+    fn inherited_flag_follows_nested_toggles() {
+        let mut world = World::new();
+        let root = spawn_a(&mut world, None);
+        let middle = spawn_a(&mut world, Some(root));
+        let leaf = spawn_a(&mut world, Some(middle));
+
+        world.set_enabled(&middle, false);
+        world.set_enabled(&root, false);
+        world.set_enabled(&middle, true);
+        assert!(!world.entities().is_active(&leaf));
+
+        world.set_enabled(&root, true);
+        assert!(world.entities().is_active(&leaf));
+        assert_eq!(Query::<&A>::new(&world).count(), 3);
+    }
+
+    #[test]
+    fn despawn_forgets_disabled_entities() {
+        let mut world = World::new();
+        let parent = spawn_a(&mut world, None);
+        let child = spawn_a(&mut world, Some(parent));
+        world.set_enabled(&child, false);
+        assert!(world.entities().any_disabled());
+
+        world.despawn(&parent);
+        assert!(!world.entities().any_disabled());
+    }
+
+    #[test]
+    fn toggling_does_not_move_entities() {
+        let mut world = World::new();
+        let entities = (0..10_000)
+            .map(|_| spawn_a(&mut world, None))
+            .collect::<Vec<_>>();
+        let id = world.archetypes().entity_archetype(&entities[0]).unwrap();
+
+        for entity in &entities {
+            world.set_enabled(entity, false);
+        }
+        assert_eq!(Query::<&A>::new(&world).count(), 0);
+
+        for entity in &entities {
+            world.set_enabled(entity, true);
+            assert_eq!(world.archetypes().entity_archetype(entity), Some(id));
+        }
+
+        let archetype = world.archetypes().get(&id).unwrap();
+        assert_eq!(archetype.entities(), entities.as_slice());
+    }
+
+    #[test]
+    fn iter_combinations_mut() {
+        struct Hits(Vec<Entity>);
+        impl Component for Hits {}
+
+        let mut world = World::new();
+        let entities = (0..4)
+            .map(|_| {
+                let entity = world.spawn(None);
+                world.add_component(&entity, Hits(vec![]));
+                entity
+            })
+            .collect::<Vec<_>>();
+
+        let query = Query::<(Entity, &mut Hits)>::new(&world);
+        let mut pairs = query.iter_combinations_mut();
+        let mut count = 0;
+        while let Some(((a, a_hits), (b, b_hits))) = pairs.fetch_next() {
+            a_hits.0.push(b);
+            b_hits.0.push(a);
+            count += 1;
+        }
+        assert_eq!(count, 6);
+
+        for (entity, hits) in Query::<(Entity, &Hits)>::new(&world) {
+            assert_eq!(hits.0.len(), entities.len() - 1);
+            assert!(entities
+                .iter()
+                .all(|other| *other == entity || hits.0.contains(other)));
+        }
+    }
+
+    #[derive(Default)]
+    struct Hovered;
+    impl Component for Hovered {}
+
+    #[test]
+    fn sparse_markers_do_not_move_entities() {
+        let mut world = World::new();
+        world.register_sparse_marker::<Hovered>();
+        let entities = (0..10_000)
+            .map(|_| spawn_a(&mut world, None))
+            .collect::<Vec<_>>();
+        let id = world.archetypes().entity_archetype(&entities[0]).unwrap();
+        let archetypes = world.archetypes().len();
+
+        for entity in &entities {
+            let added = world.add_component(entity, Hovered).unwrap();
+            assert_eq!((added.from(), added.to()), (id, id));
+        }
+        assert!(entities.iter().all(|e| world.has_component::<Hovered>(e)));
+
+        let hovered = ComponentId::new::<Hovered>();
+        for entity in &entities {
+            let removed = world.remove_component(entity, &hovered).unwrap();
+            assert_eq!((removed.from(), removed.to()), (id, id));
+            assert!(removed.removed().contains::<Hovered>());
+        }
+
+        assert_eq!(world.archetypes().len(), archetypes);
+        let archetype = world.archetypes().get(&id).unwrap();
+        assert_eq!(archetype.entities(), entities.as_slice());
+    }
+
+    #[test]
+    fn query_sparse_markers() {
+        let mut world = World::new();
+        world.register_sparse_marker::<Hovered>();
+        let first = spawn_a(&mut world, None);
+        let second = spawn_a(&mut world, None);
+        world.add_component(&second, Hovered);
+
+        let hovered = Query::<Entity, (With<A>, With<Hovered>)>::new(&world).collect::<Vec<_>>();
+        assert_eq!(hovered, vec![second]);
+
+        let rest = Query::<Entity, (With<A>, Not<Hovered>)>::new(&world).collect::<Vec<_>>();
+        assert_eq!(rest, vec![first]);
+
+        world.despawn(&second);
+        assert_eq!(Query::<&A, With<Hovered>>::new(&world).count(), 0);
+    }
+
+    #[test]
+    fn register_archetype() {
+        let mut world = World::new();
+        let id = world.register_archetype::<(A, B, C)>();
+        let archetype = world.archetypes().get(&id).unwrap();
+        assert!(archetype.entities().is_empty());
+        let archetypes = world.archetypes().len();
+
+        let root = world.archetypes().root_id();
+        let edge = world
+            .archetypes()
+            .get(&root)
+            .unwrap()
+            .edge(id, EdgeType::Add);
+        assert_eq!(edge, Some(&id));
+
+        let entity = world.spawn(None);
+        world.add_components_typed(&entity, (C, A, B));
+        assert_eq!(world.archetypes().entity_archetype(&entity), Some(id));
+        assert_eq!(world.archetypes().len(), archetypes);
+    }
+
+    #[test]
+    fn iter_archetypes() {
+        let mut world = World::new();
+        world.register_sparse_marker::<Hovered>();
+        world.register_archetype::<(A, B, C)>();
+        let entity = spawn_a(&mut world, None);
+        world.add_component(&entity, C);
+
+        let archetypes = world.archetypes();
+        assert_eq!(archetypes.iter().count(), archetypes.len());
+        assert_eq!(archetypes.iter_with(&[]).count(), archetypes.len());
+
+        let ids = [ComponentId::new::<C>(), ComponentId::new::<A>()];
+        let with = archetypes.iter_with(&ids).map(Archetype::id);
+        let with = with.collect::<Vec<_>>();
+        let expected = archetypes
+            .iter()
+            .filter(|a| a.has_component(&ids[0]) && a.has_component(&ids[1]))
+            .map(Archetype::id)
+            .collect::<Vec<_>>();
+        assert_eq!(with.len(), 2);
+        assert_eq!(with, expected);
+
+        let hovered = [ComponentId::new::<Hovered>()];
+        assert_eq!(archetypes.iter_with(&hovered).count(), 0);
+    }
+
+    #[test]
+    fn single() {
+        let mut world = World::new();
+        assert!(Query::<&A>::new(&world).single().is_none());
+
+        let first = spawn_a(&mut world, None);
+        world.add_component(&first, B);
+        let (entity, _) = Query::<(Entity, &mut A)>::new(&world).single_unchecked();
+        assert_eq!(entity, first);
+
+        spawn_a(&mut world, None);
+        assert!(Query::<&A>::new(&world).single().is_none());
+        assert_eq!(Query::<Entity, With<B>>::new(&world).single(), Some(first));
+    }
+
+    #[test]
+    fn single_mut() {
+        struct Score(u32);
+        impl Component for Score {}
+
+        let mut world = World::new();
+        let entity = world.spawn(None);
+        world.add_component(&entity, Score(1));
+
+        if let Some(score) = Query::<&mut Score>::new(&world).single_mut() {
+            score.0 += 1;
+        }
+        assert_eq!(Query::<&Score>::new(&world).single().map(|s| s.0), Some(2));
+
+        let other = world.spawn(None);
+        world.add_component(&other, Score(5));
+        assert!(Query::<&mut Score>::new(&world).single_mut().is_none());
+
+        world.add_component(&other, B);
+        let score = Query::<&mut Score, Not<B>>::new(&world).single_mut();
+        assert_eq!(score.map(|s| s.0), Some(2));
+    }
+
+    #[test]
+    fn query_changed() {
+        let mut world = World::new();
+        world.build();
+        let first = spawn_a(&mut world, None);
+        let second = spawn_a(&mut world, None);
+        world.add_component(&second, B);
+
+        let changed = |world: &World| Query::<Entity, Changed<A>>::new(world).count();
+        assert_eq!(changed(&world), 0);
+
+        world.run(Root);
+        assert_eq!(changed(&world), 2);
+
+        Query::<&mut A, With<B>>::new(&world).for_each(drop);
+        world.run(Root);
+        let changed_b = Query::<Entity, (Changed<A>, With<B>)>::new(&world);
+        assert_eq!(changed_b.collect::<Vec<_>>(), [second]);
+        let changed_not_b = Query::<Entity, (Changed<A>, Not<B>)>::new(&world);
+        assert_eq!(changed_not_b.count(), 0);
+
+        world.add_component(&first, C);
+        world.run(Root);
+        assert_eq!(changed(&world), 0);
+        let changed_c = Query::<(Entity, &A), Changed<C>>::new(&world);
+        let changed_c = changed_c.map(|(entity, _)| entity);
+        assert_eq!(changed_c.collect::<Vec<_>>(), [first]);
+
+        world.add_component(&first, A);
+        world.run(Root);
+        let changed_a = Query::<Entity, Changed<A>>::new(&world);
+        assert_eq!(changed_a.collect::<Vec<_>>(), [first]);
+    }
+
+    #[test]
+    fn query_changed_across_tick_wrap() {
+        let mut world = World::new();
+        world.build();
+        world.archetypes.set_tick(Tick::new(u32::MAX - 1));
+        let first = spawn_a(&mut world, None);
+        let changed = |world: &World| Query::<Entity, Changed<A>>::new(world).collect::<Vec<_>>();
+
+        world.run(Root);
+        assert_eq!(changed(&world), [first]);
+
+        let second = spawn_a(&mut world, None);
+        world.run(Root);
+        assert_eq!(world.archetypes().tick(), Tick::new(0));
+        assert_eq!(changed(&world), [second]);
+
+        world.run(Root);
+        assert!(changed(&world).is_empty());
+
+        world.add_component(&first, A);
+        world.run(Root);
+        assert_eq!(changed(&world), [first]);
+    }
+
+    #[test]
+    fn clamped_ticks_stay_old_across_wrap() {
+        let mut world = World::new();
+        world.build();
+        let old = spawn_a(&mut world, None);
+        world
+            .archetypes
+            .set_tick(Tick::new(Tick::CLAMP_INTERVAL * 5 - 1));
+        let recent = spawn_a(&mut world, None);
+        let changed = |world: &World| Query::<Entity, Changed<A>>::new(world).collect::<Vec<_>>();
+
+        world.run(Root);
+        let archetypes = world.archetypes();
+        let archetype = archetypes.get(&archetypes.entity_archetype(&old).unwrap());
+        let tick = archetype
+            .unwrap()
+            .changed_tick(&ComponentId::new::<A>(), &old);
+        assert_eq!(
+            tick.map(|tick| tick.age(archetypes.tick())),
+            Some(Tick::MAX_AGE)
+        );
+        assert_eq!(changed(&world), [recent]);
+
+        // Unclamped, `old` would still be stamped zero and look changed at tick one.
+        world.archetypes.set_tick(Tick::new(u32::MAX));
+        world.run(Root);
+        world.run(Root);
+        assert_eq!(world.archetypes().tick(), Tick::new(1));
+        assert!(changed(&world).is_empty());
+    }
+
+    #[test]
+    fn query_added() {
+        let mut world = World::new();
+        world.build();
+        let first = spawn_a(&mut world, None);
+        let added = |world: &World| Query::<Entity, Added<A>>::new(world).collect::<Vec<_>>();
+        assert!(added(&world).is_empty());
+
+        world.run(Root);
+        assert_eq!(added(&world), [first]);
+
+        world.flush();
+        assert_eq!(added(&world), [first]);
+
+        world.run(Root);
+        assert!(added(&world).is_empty());
+
+        world.add_component(&first, A);
+        world.add_component(&first, B);
+        world.run(Root);
+        assert!(added(&world).is_empty());
+        let added_b = Query::<Entity, Added<B>>::new(&world);
+        assert_eq!(added_b.collect::<Vec<_>>(), [first]);
+
+        let second = spawn_a(&mut world, None);
+        world.run(Root);
+        world.add_components_typed(&second, (B, C));
+        world.run(Root);
+        let added_bc = Query::<Entity, (Added<B>, Added<C>)>::new(&world);
+        assert_eq!(added_bc.collect::<Vec<_>>(), [second]);
+    }
+
+    #[test]
+    fn system_query_cache_follows_archetypes() {
+        #[derive(Default)]
+        struct Seen(Vec<usize>);
+        impl crate::core::Resource for Seen {}
+
+        let mut world = World::new();
+        world.register_archetype::<(A, C)>();
+        world
+            .init_resource::<Seen>()
+            .add_system(Root, |query: Query<&A, Not<B>>, seen: &mut Seen| {
+                seen.0.push(query.count())
+            })
+            .build();
+
+        let first = spawn_a(&mut world, None);
+        world.run(Root);
+
+        // A new archetype, then a move into an archetype that was empty when cached.
+        let second = spawn_a(&mut world, None);
+        world.add_component(&second, B);
+        world.run(Root);
+        world.add_component(&first, C);
+        world.run(Root);
+        world.despawn(&second);
+        world.remove_component(&first, &ComponentId::new::<C>());
+        world.run(Root);
+
+        assert_eq!(world.resource::<Seen>().0, [1, 1, 1, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Query::single_unchecked expected one match")]
+    fn single_unchecked_panics() {
+        let world = World::new();
+        Query::<&A>::new(&world).single_unchecked();
+    }
 }
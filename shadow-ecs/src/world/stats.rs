@@ -0,0 +1,127 @@
+use crate::{
+    archetype::{ArchetypeId, Archetypes},
+    core::{Entities, Resource},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchetypeStats {
+    pub id: ArchetypeId,
+    pub entities: usize,
+    /// Bytes reserved by the archetype's table.
+    pub memory: usize,
+}
+
+/// Entity and memory counts of the archetype tables, refreshed once a frame by
+/// [`update_world_stats`].
+#[derive(Debug, Default)]
+pub struct WorldStats {
+    entities: usize,
+    archetypes: Vec<ArchetypeStats>,
+    memory: usize,
+    peak_memory: usize,
+}
+
+impl WorldStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entities(&self) -> usize {
+        self.entities
+    }
+
+    pub fn archetypes(&self) -> &[ArchetypeStats] {
+        &self.archetypes
+    }
+
+    pub fn archetype(&self, id: &ArchetypeId) -> Option<&ArchetypeStats> {
+        self.archetypes.iter().find(|stats| stats.id == *id)
+    }
+
+    pub fn archetype_count(&self) -> usize {
+        self.archetypes.len()
+    }
+
+    pub fn memory(&self) -> usize {
+        self.memory
+    }
+
+    pub fn peak_memory(&self) -> usize {
+        self.peak_memory
+    }
+
+    pub fn update(&mut self, entities: &Entities, archetypes: &Archetypes) {
+        self.entities = entities.len();
+        self.archetypes.clear();
+        self.archetypes
+            .extend(archetypes.iter().map(|archetype| ArchetypeStats {
+                id: archetype.id(),
+                entities: archetype.entities().len(),
+                memory: archetype.memory(),
+            }));
+
+        self.memory = self.archetypes.iter().map(|stats| stats.memory).sum();
+        self.peak_memory = self.peak_memory.max(self.memory);
+    }
+}
+
+impl Resource for WorldStats {}
+
+pub fn update_world_stats(entities: &Entities, archetypes: &Archetypes, stats: &mut WorldStats) {
+    stats.update(entities, archetypes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{update_world_stats, WorldStats};
+    use crate::{
+        archetype::ArchetypeId,
+        core::{Component, ComponentId},
+        system::schedule::Root,
+        world::{query::Query, World},
+    };
+
+    struct Position(u64);
+    impl Component for Position {}
+
+    #[test]
+    fn stats_track_archetypes_and_peak_memory() {
+        let mut world = World::new();
+        world.register::<Position>();
+        world
+            .init_resource::<WorldStats>()
+            .add_system(Root, update_world_stats)
+            .build();
+
+        let entities = (0..100)
+            .map(|index| {
+                let entity = world.spawn(None);
+                world.add_component(&entity, Position(index));
+                entity
+            })
+            .collect::<Vec<_>>();
+        world.run(Root);
+
+        let id = ArchetypeId::new(&[ComponentId::new::<Position>()]);
+        let stats = world.resource::<WorldStats>();
+        let peak = stats.peak_memory();
+        assert_eq!(world.entity_count(), 100);
+        assert_eq!(stats.entities(), 100);
+        assert_eq!(stats.archetype_count(), world.archetypes().len());
+        assert_eq!(stats.archetype(&id).map(|stats| stats.entities), Some(100));
+        assert!(stats.memory() >= 100 * std::mem::size_of::<Position>());
+        let total = Query::<&Position>::new(&world).map(|p| p.0).sum::<u64>();
+        assert_eq!(total, (0..100).sum::<u64>());
+
+        for entity in &entities {
+            world.despawn(entity);
+        }
+        world.run(Root);
+
+        let stats = world.resource::<WorldStats>();
+        assert_eq!(stats.entities(), 0);
+        assert_eq!(stats.archetype(&id).map(|stats| stats.entities), Some(0));
+        assert_eq!(stats.peak_memory(), peak);
+        assert!(stats.memory() <= peak);
+    }
+}
@@ -0,0 +1,145 @@
+//! Checks that hot paths don't allocate once warmed up. The counting allocator replaces
+//! the global allocator, so these tests live in their own binary.
+
+use shadow_ecs::{
+    core::{Component, ComponentId, Resource},
+    system::schedule::Root,
+    world::{query::Query, World},
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+struct CountingAllocator;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if COUNTING.with(|counting| counting.get()) {
+            ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Counts allocations made by the calling thread while `f` runs. Allocations on
+/// other threads, such as a task pool, are not counted, so callers must keep the
+/// work they measure on this thread. A global counter would pick up other tests
+/// running in parallel.
+fn count_allocations(f: impl FnOnce()) -> usize {
+    ALLOCATIONS.with(|count| count.set(0));
+    COUNTING.with(|counting| counting.set(true));
+    f();
+    COUNTING.with(|counting| counting.set(false));
+    ALLOCATIONS.with(|count| count.get())
+}
+
+#[derive(Default)]
+struct Frames(usize);
+impl Resource for Frames {}
+
+struct Position(usize);
+impl Component for Position {}
+
+fn count_frames(frames: &mut Frames) {
+    frames.0 += 1;
+}
+
+fn step(frames: &Frames, positions: Query<&mut Position>) {
+    for position in positions {
+        position.0 = frames.0;
+    }
+}
+
+#[test]
+fn steady_state_frame_does_not_allocate() {
+    let mut world = World::new();
+    for index in 0..100 {
+        let entity = world.spawn(None);
+        world.add_component(&entity, Position(index));
+    }
+
+    world
+        .init_resource::<Frames>()
+        .add_system(Root, count_frames)
+        .add_system(Root, step)
+        .build();
+
+    for _ in 0..10 {
+        world.run(Root);
+    }
+
+    // Both systems touch `Frames`, so each gets its own row and runs inline. The
+    // whole frame is on this thread.
+    let allocations = count_allocations(|| {
+        for _ in 0..100 {
+            world.run(Root);
+        }
+    });
+
+    assert_eq!(world.resource::<Frames>().0, 110);
+    assert!(Query::<&Position>::new(&world).all(|position| position.0 == 110));
+    assert_eq!(allocations, 0);
+}
+
+#[test]
+fn cached_edge_moves_do_not_allocate() {
+    struct Health(u32);
+    impl Component for Health {}
+    struct Speed(u32);
+    impl Component for Speed {}
+
+    let mut world = World::new();
+    let entities = (0..100)
+        .map(|index| {
+            let entity = world.spawn(None);
+            world.add_component(&entity, Speed(index));
+            entity
+        })
+        .collect::<Vec<_>>();
+
+    let health = ComponentId::new::<Health>();
+    let churn = |world: &mut World| {
+        for entity in &entities {
+            world.set_component(entity, Health(1));
+            world.set_component(entity, Health(2));
+        }
+
+        for entity in &entities {
+            world.discard_component(entity, &health);
+        }
+    };
+
+    churn(&mut world);
+    let allocations = count_allocations(|| {
+        for _ in 0..10 {
+            churn(&mut world);
+        }
+    });
+
+    assert_eq!(allocations, 0);
+    assert!(entities
+        .iter()
+        .all(|entity| !world.has_component::<Health>(entity)));
+
+    world.set_component(&entities[0], Health(3));
+    let speed = Query::<&Speed>::new(&world)
+        .map(|speed| speed.0)
+        .sum::<u32>();
+    let health = Query::<&Health>::new(&world)
+        .map(|health| health.0)
+        .sum::<u32>();
+    assert_eq!((speed, health), ((0..100).sum(), 3));
+}
@@ -0,0 +1,451 @@
+use crate::{
+    game::Game,
+    plugin::Plugin,
+    state::{State, States},
+};
+use shadow_ecs::{
+    core::{DenseMap, Resource},
+    world::{event::Event, World},
+};
+use std::{fmt::Display, str::FromStr, sync::Arc};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleError {
+    Empty,
+    UnclosedQuote,
+    UnknownCommand(String),
+    Arity {
+        command: String,
+        expected: usize,
+        found: usize,
+    },
+    InvalidArg {
+        command: String,
+        index: usize,
+        value: String,
+    },
+    Failed {
+        command: String,
+        message: String,
+    },
+}
+
+impl Display for ConsoleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsoleError::Empty => write!(f, "Empty command"),
+            ConsoleError::UnclosedQuote => write!(f, "Unclosed quote"),
+            ConsoleError::UnknownCommand(command) => write!(f, "Unknown command: {}", command),
+            ConsoleError::Arity {
+                command,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} expects {} arguments, found {}",
+                command, expected, found
+            ),
+            ConsoleError::InvalidArg {
+                command,
+                index,
+                value,
+            } => write!(f, "{}: invalid argument {} '{}'", command, index, value),
+            ConsoleError::Failed { command, message } => write!(f, "{}: {}", command, message),
+        }
+    }
+}
+
+impl std::error::Error for ConsoleError {}
+
+/// A parsed command line. Arguments are split on whitespace; double quotes group
+/// words into a single argument.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandArgs {
+    command: String,
+    args: Vec<String>,
+}
+
+impl CommandArgs {
+    pub fn parse(line: &str) -> Result<Self, ConsoleError> {
+        let mut tokens = vec![];
+        let mut token: Option<String> = None;
+        let mut quoted = false;
+
+        for c in line.chars() {
+            match c {
+                '"' => {
+                    quoted = !quoted;
+                    token.get_or_insert_with(String::new);
+                }
+                c if c.is_whitespace() && !quoted => tokens.extend(token.take()),
+                c => token.get_or_insert_with(String::new).push(c),
+            }
+        }
+
+        if quoted {
+            return Err(ConsoleError::UnclosedQuote);
+        }
+
+        tokens.extend(token);
+        let mut tokens = tokens.into_iter();
+        let command = tokens.next().ok_or(ConsoleError::Empty)?;
+
+        Ok(Self {
+            command,
+            args: tokens.collect(),
+        })
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    pub fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty()
+    }
+
+    pub fn expect(&self, count: usize) -> Result<&Self, ConsoleError> {
+        match self.args.len() == count {
+            true => Ok(self),
+            false => Err(ConsoleError::Arity {
+                command: self.command.clone(),
+                expected: count,
+                found: self.args.len(),
+            }),
+        }
+    }
+
+    pub fn string(&self, index: usize) -> Result<&str, ConsoleError> {
+        match self.args.get(index) {
+            Some(arg) => Ok(arg),
+            None => Err(ConsoleError::Arity {
+                command: self.command.clone(),
+                expected: index + 1,
+                found: self.args.len(),
+            }),
+        }
+    }
+
+    pub fn get<T: FromStr>(&self, index: usize) -> Result<T, ConsoleError> {
+        let arg = self.string(index)?;
+        arg.parse().map_err(|_| ConsoleError::InvalidArg {
+            command: self.command.clone(),
+            index,
+            value: arg.to_string(),
+        })
+    }
+
+    pub fn int(&self, index: usize) -> Result<i64, ConsoleError> {
+        self.get(index)
+    }
+
+    pub fn float(&self, index: usize) -> Result<f32, ConsoleError> {
+        self.get(index)
+    }
+
+    pub fn fail(&self, message: impl Into<String>) -> ConsoleError {
+        ConsoleError::Failed {
+            command: self.command.clone(),
+            message: message.into(),
+        }
+    }
+}
+
+type CommandFn =
+    Arc<dyn Fn(&CommandArgs, &mut World) -> Result<String, ConsoleError> + Send + Sync>;
+
+pub struct ConsoleCommand {
+    name: String,
+    hint: String,
+    run: CommandFn,
+}
+
+impl ConsoleCommand {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Argument hint shown by completion, e.g. `<name> [count]`.
+    pub fn hint(&self) -> &str {
+        &self.hint
+    }
+}
+
+/// Commands that can be run by name at runtime, through `RunCommand` events.
+pub struct ConsoleCommands {
+    commands: DenseMap<String, ConsoleCommand>,
+    history: Vec<String>,
+    max_history: usize,
+}
+
+impl ConsoleCommands {
+    pub const MAX_HISTORY: usize = 100;
+
+    pub fn new() -> Self {
+        let mut commands = Self {
+            commands: DenseMap::new(),
+            history: vec![],
+            max_history: Self::MAX_HISTORY,
+        };
+
+        commands
+            .register("enable_system", "<name>", |args, world| {
+                let name = args.expect(1)?.string(0)?;
+                match world.enable_system(name) {
+                    true => Ok(format!("Enabled {}", name)),
+                    false => Err(args.fail("no such system")),
+                }
+            })
+            .register("disable_system", "<name>", |args, world| {
+                let name = args.expect(1)?.string(0)?;
+                match world.disable_system(name) {
+                    true => Ok(format!("Disabled {}", name)),
+                    false => Err(args.fail("no such system")),
+                }
+            })
+            .register("activate_group", "<tag>", |args, world| {
+                let tag = args.expect(1)?.string(0)?;
+                world.activate_system_group(tag);
+                Ok(format!("Activated {}", tag))
+            })
+            .register("deactivate_group", "<tag>", |args, world| {
+                let tag = args.expect(1)?.string(0)?;
+                world.deactivate_system_group(tag);
+                Ok(format!("Deactivated {}", tag))
+            });
+
+        commands
+    }
+
+    pub fn register<F>(&mut self, name: &str, hint: &str, run: F) -> &mut Self
+    where
+        F: Fn(&CommandArgs, &mut World) -> Result<String, ConsoleError> + Send + Sync + 'static,
+    {
+        let command = ConsoleCommand {
+            name: name.to_string(),
+            hint: hint.to_string(),
+            run: Arc::new(run),
+        };
+
+        self.commands.insert(name.to_string(), command);
+        self
+    }
+
+    /// Registers a command that queues a transition to the state parsed from its argument.
+    pub fn register_state<S: States>(
+        &mut self,
+        name: &str,
+        parse: fn(&str) -> Option<S>,
+    ) -> &mut Self {
+        self.register(name, "<state>", move |args, world| {
+            let value = args.expect(1)?.string(0)?;
+            let state = parse(value).ok_or_else(|| ConsoleError::InvalidArg {
+                command: args.command().to_string(),
+                index: 0,
+                value: value.to_string(),
+            })?;
+
+            match world.try_resource_mut::<State<S>>() {
+                Some(current) => current.set(state),
+                None => return Err(args.fail("state not added")),
+            }
+
+            Ok(format!("Queued {}", value))
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ConsoleCommand> {
+        self.commands.get(&name.to_string())
+    }
+
+    /// Commands whose name starts with `prefix`, sorted by name.
+    pub fn complete(&self, prefix: &str) -> Vec<&ConsoleCommand> {
+        let mut commands = self
+            .commands
+            .values()
+            .iter()
+            .filter(|command| command.name.starts_with(prefix))
+            .collect::<Vec<_>>();
+        commands.sort_by(|a, b| a.name.cmp(&b.name));
+        commands
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    pub fn set_max_history(&mut self, max_history: usize) {
+        self.max_history = max_history;
+        self.trim_history();
+    }
+
+    fn record(&mut self, line: &str) {
+        self.history.push(line.to_string());
+        self.trim_history();
+    }
+
+    fn trim_history(&mut self) {
+        let excess = self.history.len().saturating_sub(self.max_history);
+        self.history.drain(..excess);
+    }
+}
+
+impl Default for ConsoleCommands {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resource for ConsoleCommands {}
+
+pub struct CommandOutput {
+    pub line: String,
+    pub result: Result<String, ConsoleError>,
+}
+
+/// Runs a console command line with mutable access to the world at the next flush.
+pub struct RunCommand {
+    line: String,
+}
+
+impl RunCommand {
+    pub fn new(line: impl Into<String>) -> Self {
+        Self { line: line.into() }
+    }
+}
+
+impl Event for RunCommand {
+    type Output = CommandOutput;
+
+    fn invoke(self, world: &mut World) -> Option<Self::Output> {
+        let commands = world.try_resource_mut::<ConsoleCommands>()?;
+        commands.record(&self.line);
+
+        let command =
+            CommandArgs::parse(&self.line).and_then(|args| match commands.get(args.command()) {
+                Some(command) => Ok((command.run.clone(), args)),
+                None => Err(ConsoleError::UnknownCommand(args.command().to_string())),
+            });
+
+        let result = command.and_then(|(run, args)| run(&args, world));
+        Some(CommandOutput {
+            line: self.line,
+            result,
+        })
+    }
+}
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn run(&mut self, game: &mut Game) {
+        game.try_init_resource::<ConsoleCommands>();
+        game.register_event::<RunCommand>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandArgs, CommandOutput, ConsoleCommands, ConsoleError, RunCommand};
+    use shadow_ecs::{core::Resource, world::World};
+
+    #[derive(Default)]
+    struct Outputs(Vec<Result<String, ConsoleError>>);
+    impl Resource for Outputs {}
+
+    fn world() -> World {
+        let mut commands = ConsoleCommands::new();
+        commands.register("spawn", "<count>", |args, world| {
+            let count = args.expect(1)?.int(0)?;
+            for _ in 0..count {
+                world.spawn(None);
+            }
+
+            Ok(format!("Spawned {}", count))
+        });
+
+        let mut world = World::new();
+        world
+            .add_resource(commands)
+            .init_resource::<Outputs>()
+            .register_event::<RunCommand>()
+            .observe::<RunCommand, _>(|runs: &[CommandOutput], outputs: &mut Outputs| {
+                outputs.0.extend(runs.iter().map(|run| run.result.clone()));
+            });
+        world
+    }
+
+    #[test]
+    fn parse_args() {
+        let args = CommandArgs::parse(r#"  say "hello world" 3 "" "#).unwrap();
+        assert_eq!(args.command(), "say");
+        assert_eq!(args.args(), ["hello world", "3", ""]);
+        assert_eq!(args.int(1), Ok(3));
+        assert!(matches!(
+            args.int(0),
+            Err(ConsoleError::InvalidArg { index: 0, .. })
+        ));
+        assert!(matches!(
+            args.expect(2),
+            Err(ConsoleError::Arity {
+                expected: 2,
+                found: 3,
+                ..
+            })
+        ));
+
+        assert_eq!(CommandArgs::parse("   "), Err(ConsoleError::Empty));
+        assert_eq!(
+            CommandArgs::parse(r#"say "oops"#),
+            Err(ConsoleError::UnclosedQuote)
+        );
+    }
+
+    #[test]
+    fn run_commands() {
+        let mut world = world();
+        world.events().add(RunCommand::new("spawn 3"));
+        world.events().add(RunCommand::new("spawn"));
+        world.events().add(RunCommand::new("fly"));
+        assert_eq!(world.entities().len(), 0);
+
+        world.flush();
+        assert_eq!(world.entities().len(), 3);
+
+        let outputs = &world.resource::<Outputs>().0;
+        assert_eq!(outputs[0], Ok("Spawned 3".to_string()));
+        assert!(matches!(outputs[1], Err(ConsoleError::Arity { .. })));
+        assert_eq!(
+            outputs[2],
+            Err(ConsoleError::UnknownCommand("fly".to_string()))
+        );
+
+        let history = world.resource::<ConsoleCommands>().history();
+        assert_eq!(history, ["spawn 3", "spawn", "fly"]);
+    }
+
+    #[test]
+    fn complete_commands() {
+        let world = world();
+        let commands = world.resource::<ConsoleCommands>();
+        let names = commands
+            .complete("d")
+            .iter()
+            .map(|command| (command.name(), command.hint()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            [("deactivate_group", "<tag>"), ("disable_system", "<name>")]
+        );
+        assert_eq!(commands.complete("sp")[0].name(), "spawn");
+    }
+}
@@ -1,16 +1,22 @@
 use super::plugin::Plugins;
 use crate::{
-    phases::{Execute, Shutdown, Startup},
+    phases::{Execute, Last, PostInit, PreUpdate, Shutdown, Startup},
     plugin::Plugin,
+    state::{apply_state_transition, State, StateHooks, StateTransition, States},
 };
 use shadow_ecs::{
     core::{Component, LocalResource, Resource},
     system::{
         observer::IntoObserver,
         schedule::{Phase, PhaseRunner, SystemGroup},
-        IntoSystem,
+        IntoSystem, PanicPolicy,
+    },
+    world::{
+        derived::DerivedComponent,
+        event::Event,
+        stats::{update_world_stats, WorldStats},
+        World,
     },
-    world::{event::Event, World},
 };
 
 pub struct Game {
@@ -25,6 +31,9 @@ impl Game {
         world.add_phase::<Startup>();
         world.add_phase::<Execute>();
         world.add_phase::<Shutdown>();
+        world
+            .init_resource::<WorldStats>()
+            .add_system(PreUpdate, update_world_stats);
 
         Self {
             world,
@@ -116,11 +125,28 @@ impl Game {
         self
     }
 
+    /// Applies `event`, its observers and any events they raise before returning,
+    /// bypassing the queue. Events queued earlier stay queued. See `World::dispatch`.
+    pub fn dispatch_event<E: Event>(&mut self, event: E) -> &mut Self {
+        self.world.dispatch(event);
+        self
+    }
+
     pub fn add_system<M>(&mut self, phase: impl Phase, system: impl IntoSystem<M>) -> &mut Self {
         self.world.add_system(phase, system);
         self
     }
 
+    pub fn add_barrier(&mut self, phase: impl Phase) -> &mut Self {
+        self.world.add_barrier(phase);
+        self
+    }
+
+    pub fn add_derived<D: DerivedComponent>(&mut self, phase: impl Phase) -> &mut Self {
+        self.world.add_derived::<D>(phase);
+        self
+    }
+
     pub fn add_phase<P: Phase>(&mut self) -> &mut Self {
         self.world.add_phase::<P>();
         self
@@ -141,6 +167,16 @@ impl Game {
         self
     }
 
+    pub fn add_phase_after<Anchor: Phase, New: Phase>(&mut self) -> &mut Self {
+        self.world.add_phase_after::<Anchor, New>();
+        self
+    }
+
+    pub fn order_phases<First: Phase, Second: Phase>(&mut self) -> &mut Self {
+        self.world.order_phases::<First, Second>();
+        self
+    }
+
     pub fn add_phase_runner<P: Phase>(&mut self, runner: impl PhaseRunner) -> &mut Self {
         self.world.add_phase_runner::<P>(runner);
         self
@@ -151,6 +187,50 @@ impl Game {
         self
     }
 
+    pub fn set_panic_policy(&mut self, policy: PanicPolicy) -> &mut Self {
+        self.world.set_panic_policy(policy);
+        self
+    }
+
+    pub fn enable_system(&mut self, name: &str) -> bool {
+        self.world.enable_system(name)
+    }
+
+    pub fn disable_system(&mut self, name: &str) -> bool {
+        self.world.disable_system(name)
+    }
+
+    pub fn add_state<S: States>(&mut self, initial: S) -> &mut Self {
+        self.world.add_resource(State::new(initial));
+        self.world.add_resource(StateHooks::<S>::new());
+        self.world.register_event::<StateTransition<S>>();
+        self.world.add_system(PostInit, apply_state_transition::<S>);
+        self.world.add_system(Last, apply_state_transition::<S>);
+        self
+    }
+
+    pub fn on_enter<S: States, M>(&mut self, state: S, system: impl IntoSystem<M>) -> &mut Self {
+        self.world
+            .resource_mut::<StateHooks<S>>()
+            .add_enter(state, system);
+        self
+    }
+
+    pub fn on_exit<S: States, M>(&mut self, state: S, system: impl IntoSystem<M>) -> &mut Self {
+        self.world
+            .resource_mut::<StateHooks<S>>()
+            .add_exit(state, system);
+        self
+    }
+
+    pub fn add_state_guard<S: States>(
+        &mut self,
+        guard: impl Fn(&World) -> bool + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.world.resource_mut::<StateHooks<S>>().add_guard(guard);
+        self
+    }
+
     pub fn add_plugin<P: Plugin>(&mut self, plugin: P) -> &mut Self {
         self.plugins.add_plugin(plugin);
         self
@@ -213,3 +293,61 @@ pub enum Environment {
     Development,
     Release,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Game;
+    use shadow_ecs::{
+        core::Resource,
+        world::{
+            event::{Event, Events},
+            World,
+        },
+    };
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+    impl Resource for Log {}
+
+    struct Damage(u32);
+    impl Event for Damage {
+        type Output = u32;
+
+        fn invoke(self, _: &mut World) -> Option<Self::Output> {
+            Some(self.0)
+        }
+    }
+
+    struct Died;
+    impl Event for Died {
+        type Output = ();
+
+        fn invoke(self, world: &mut World) -> Option<Self::Output> {
+            world.resource_mut::<Log>().0.push("died".into());
+            None
+        }
+    }
+
+    #[test]
+    fn dispatch_event_bypasses_the_queue() {
+        let mut game = Game::new();
+        game.init_resource::<Log>()
+            .register_event::<Damage>()
+            .register_event::<Died>()
+            .observe::<Damage, _>(|damage: &[u32], log: &mut Log, events: &Events| {
+                log.0.extend(damage.iter().map(|damage| damage.to_string()));
+                if damage.iter().sum::<u32>() >= 10 {
+                    events.add(Died);
+                }
+            });
+
+        game.world.events().add(Damage(3));
+        game.dispatch_event(Damage(12));
+
+        assert_eq!(game.resource::<Log>().0, ["12", "died"]);
+        assert_eq!(game.world.events().len(), 1);
+
+        game.world.flush();
+        assert_eq!(game.resource::<Log>().0, ["12", "died", "3"]);
+    }
+}
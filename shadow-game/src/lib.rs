@@ -1,3 +1,5 @@
+pub mod console;
 pub mod game;
 pub mod phases;
 pub mod plugin;
+pub mod state;
@@ -0,0 +1,288 @@
+use shadow_ecs::{
+    core::{DenseMap, Resource},
+    system::{IntoSystem, RunCondition, System},
+    world::{
+        event::{Event, Events},
+        World,
+    },
+};
+use std::hash::Hash;
+
+pub trait States: Copy + Eq + Hash + Send + Sync + 'static {}
+
+pub struct State<S: States> {
+    current: Option<S>,
+    next: Option<S>,
+    reentrant: bool,
+}
+
+impl<S: States> State<S> {
+    pub fn new(initial: S) -> Self {
+        Self {
+            current: None,
+            next: Some(initial),
+            reentrant: false,
+        }
+    }
+
+    pub fn current(&self) -> Option<S> {
+        self.current
+    }
+
+    pub fn next(&self) -> Option<S> {
+        self.next
+    }
+
+    pub fn is(&self, state: S) -> bool {
+        self.current == Some(state)
+    }
+
+    pub fn is_reentrant(&self) -> bool {
+        self.reentrant
+    }
+
+    pub fn set(&mut self, state: S) {
+        self.next = Some(state);
+    }
+
+    pub fn set_reentrant(&mut self, reentrant: bool) {
+        self.reentrant = reentrant;
+    }
+}
+
+impl<S: States> Resource for State<S> {}
+
+pub struct StateHooks<S: States> {
+    enter: DenseMap<S, Vec<System>>,
+    exit: DenseMap<S, Vec<System>>,
+    guards: Vec<RunCondition>,
+}
+
+impl<S: States> StateHooks<S> {
+    pub fn new() -> Self {
+        Self {
+            enter: DenseMap::new(),
+            exit: DenseMap::new(),
+            guards: vec![],
+        }
+    }
+
+    /// Holds queued transitions until every guard returns true.
+    pub fn add_guard(&mut self, guard: impl Fn(&World) -> bool + Send + Sync + 'static) {
+        self.guards.push(Box::new(guard));
+    }
+
+    pub fn can_transition(&self, world: &World) -> bool {
+        self.guards.iter().all(|guard| guard(world))
+    }
+
+    pub fn add_enter<M>(&mut self, state: S, system: impl IntoSystem<M>) {
+        Self::add(&mut self.enter, state, system.into_system());
+    }
+
+    pub fn add_exit<M>(&mut self, state: S, system: impl IntoSystem<M>) {
+        Self::add(&mut self.exit, state, system.into_system());
+    }
+
+    pub fn enter(&self, state: &S) -> &[System] {
+        self.enter.get(state).map(|s| s.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn exit(&self, state: &S) -> &[System] {
+        self.exit.get(state).map(|s| s.as_slice()).unwrap_or(&[])
+    }
+
+    fn add(hooks: &mut DenseMap<S, Vec<System>>, state: S, system: System) {
+        if let Some(systems) = hooks.get_mut(&state) {
+            systems.push(system);
+        } else {
+            hooks.insert(state, vec![system]);
+        }
+    }
+}
+
+impl<S: States> Default for StateHooks<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: States> Resource for StateHooks<S> {}
+
+pub struct Transition<S: States> {
+    pub exited: Option<S>,
+    pub entered: S,
+}
+
+pub struct StateTransition<S: States>(std::marker::PhantomData<S>);
+
+impl<S: States> StateTransition<S> {
+    pub fn new() -> Self {
+        Self(std::marker::PhantomData)
+    }
+}
+
+impl<S: States> Default for StateTransition<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: States> Event for StateTransition<S> {
+    type Output = Transition<S>;
+
+    fn invoke(self, world: &mut World) -> Option<Self::Output> {
+        let world: &World = world;
+        let hooks = world.resource::<StateHooks<S>>();
+        if !hooks.can_transition(world) {
+            return None;
+        }
+
+        let state = world.resource_mut::<State<S>>();
+        let next = state.next.take()?;
+        if state.current == Some(next) && !state.reentrant {
+            return None;
+        }
+
+        let exited = state.current;
+        if let Some(exited) = &exited {
+            hooks
+                .exit(exited)
+                .iter()
+                .for_each(|system| system.run(world));
+        }
+
+        world.resource_mut::<State<S>>().current = Some(next);
+        hooks
+            .enter(&next)
+            .iter()
+            .for_each(|system| system.run(world));
+
+        Some(Transition {
+            exited,
+            entered: next,
+        })
+    }
+}
+
+pub fn in_state<S: States>(state: S) -> impl Fn(&World) -> bool + Send + Sync + 'static {
+    move |world| {
+        world
+            .try_resource::<State<S>>()
+            .is_some_and(|current| current.is(state))
+    }
+}
+
+pub(crate) fn apply_state_transition<S: States>(state: &State<S>, events: &Events) {
+    if state.next.is_some() {
+        events.add(StateTransition::<S>::new());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{in_state, State, States};
+    use crate::{game::Game, phases::Update};
+    use shadow_ecs::{core::Resource, system::IntoSystem};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum AppState {
+        Menu,
+        Playing,
+        Paused,
+    }
+
+    impl States for AppState {}
+
+    #[derive(Default)]
+    struct Counts {
+        menu: usize,
+        playing: usize,
+        paused: usize,
+        entered: usize,
+        exited: usize,
+    }
+
+    impl Resource for Counts {}
+
+    fn game() -> Game {
+        let mut game = Game::new();
+        game.init_resource::<Counts>()
+            .add_state(AppState::Menu)
+            .add_system(
+                Update,
+                (|counts: &mut Counts| counts.menu += 1).run_if(in_state(AppState::Menu)),
+            )
+            .add_system(
+                Update,
+                (|counts: &mut Counts| counts.playing += 1).run_if(in_state(AppState::Playing)),
+            )
+            .on_enter(AppState::Playing, |counts: &mut Counts| counts.entered += 1)
+            .on_enter(AppState::Paused, |counts: &mut Counts| counts.paused += 1)
+            .on_exit(AppState::Menu, |counts: &mut Counts| counts.exited += 1);
+        game
+    }
+
+    #[test]
+    fn systems_run_in_state() {
+        let mut game = game();
+        game.set_runner(|game: &mut Game| {
+            game.start();
+            game.update();
+            game.resource_mut::<State<AppState>>()
+                .set(AppState::Playing);
+            game.update();
+            game.update();
+        });
+        game.run();
+
+        let counts = game.resource::<Counts>();
+        assert_eq!(counts.menu, 2);
+        assert_eq!(counts.playing, 1);
+        assert_eq!(counts.entered, 1);
+        assert_eq!(counts.exited, 1);
+    }
+
+    #[test]
+    fn queued_transitions_settle_on_last() {
+        let mut game = game();
+        game.set_runner(|game: &mut Game| {
+            game.start();
+            game.update();
+            let state = game.resource_mut::<State<AppState>>();
+            state.set(AppState::Playing);
+            state.set(AppState::Paused);
+            game.update();
+        });
+        game.run();
+
+        let state = game.resource::<State<AppState>>();
+        assert_eq!(state.current(), Some(AppState::Paused));
+        let counts = game.resource::<Counts>();
+        assert_eq!(counts.entered, 0);
+        assert_eq!(counts.paused, 1);
+        assert_eq!(counts.exited, 1);
+    }
+
+    #[test]
+    fn guards_hold_transitions() {
+        #[derive(Default)]
+        struct Ready(bool);
+        impl Resource for Ready {}
+
+        let mut game = game();
+        game.init_resource::<Ready>()
+            .add_state_guard::<AppState>(|world| world.resource::<Ready>().0);
+        game.set_runner(|game: &mut Game| {
+            game.start();
+            game.update();
+            assert_eq!(game.resource::<State<AppState>>().current(), None);
+            game.resource_mut::<Ready>().0 = true;
+            game.update();
+        });
+        game.run();
+
+        let state = game.resource::<State<AppState>>();
+        assert_eq!(state.current(), Some(AppState::Menu));
+    }
+}